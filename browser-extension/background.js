@@ -0,0 +1,46 @@
+// Report the active tab to FlowMode: over native messaging when
+// `flowmode browser-host --install` registered the host, else straight to the daemon.
+const HOST = "dev.flowmode.host";
+const ENDPOINT = "http://localhost:5555/api/browser/tab";
+
+let port = null;
+
+function connect() {
+  try {
+    port = chrome.runtime.connectNative(HOST);
+    port.onDisconnect.addListener(() => {
+      port = null;
+    });
+  } catch (e) {
+    port = null;
+  }
+}
+
+function send(report) {
+  if (!port) connect();
+  if (port) {
+    try {
+      port.postMessage(report);
+      return;
+    } catch (e) {
+      port = null;
+    }
+  }
+  fetch(ENDPOINT, {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(report),
+  }).catch(() => {});
+}
+
+async function reportActiveTab() {
+  const [tab] = await chrome.tabs.query({ active: true, lastFocusedWindow: true });
+  if (!tab || !tab.url) return;
+  send({ url: tab.url, title: tab.title || "", incognito: tab.incognito });
+}
+
+chrome.tabs.onActivated.addListener(reportActiveTab);
+chrome.tabs.onUpdated.addListener((_id, change, tab) => {
+  if (tab.active && (change.url || change.title)) reportActiveTab();
+});
+chrome.windows.onFocusChanged.addListener(reportActiveTab);
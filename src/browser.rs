@@ -0,0 +1,318 @@
+//! Active browser tab, reported by a companion extension
+//!
+//! Window titles only say what a page calls itself. The extension in
+//! `browser-extension/` sends the active tab's URL and title, either to
+//! `flowmode browser-host` over native messaging (which forwards it to the
+//! daemon) or straight to `POST /api/browser/tab`. The daemon keeps the last
+//! report and uses it while the browser window shows that tab.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::client::DaemonClient;
+
+/// Native messaging host name, as registered in the browsers' manifests
+pub const HOST_NAME: &str = "dev.flowmode.host";
+
+/// Browsers cap messages to a host at 4 GiB; tab reports are tiny
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// The active tab as reported by the extension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserTab {
+    pub url: String,
+    pub title: String,
+    /// Private (incognito) window: the URL is not recorded
+    #[serde(default)]
+    pub incognito: bool,
+}
+
+impl BrowserTab {
+    /// Whether a browser window titled `window_title` is showing this tab
+    pub fn shown_in(&self, window_title: &str) -> bool {
+        let title = self.title.trim();
+        !title.is_empty() && window_title.trim_start().starts_with(title)
+    }
+}
+
+/// Latest tab report, shared by the web server and the tracking loop
+#[derive(Debug, Default)]
+pub struct ActiveTab(Mutex<Option<BrowserTab>>);
+
+pub type SharedActiveTab = Arc<ActiveTab>;
+
+impl ActiveTab {
+    pub fn report(&self, tab: BrowserTab) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(tab);
+    }
+
+    /// The reported tab, if the browser window titled `window_title` shows it.
+    /// Private tabs are never returned.
+    pub fn for_window(&self, window_title: &str) -> Option<BrowserTab> {
+        self.reported_for(window_title).filter(|tab| !tab.incognito)
+    }
+
+    /// Like `for_window`, private tabs included
+    pub fn reported_for(&self, window_title: &str) -> Option<BrowserTab> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|tab| tab.shown_in(window_title))
+            .cloned()
+    }
+}
+
+/// Host of an http(s) URL without "www.", e.g. "docs.rs"
+pub fn domain(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+/// Whether `url` is on `site`: a domain ("acme.com", subdomains included),
+/// optionally with a path prefix ("github.com/acme")
+pub fn url_on_site(url: &str, site: &str) -> bool {
+    let (Some(host), Ok(url)) = (domain(url), url::Url::parse(url)) else {
+        return false;
+    };
+    let site = site.trim().to_lowercase();
+    let site = site.strip_prefix("www.").unwrap_or(&site);
+    let (site_host, site_path) = site.split_once('/').unwrap_or((site, ""));
+    let site_path = site_path.trim_end_matches('/');
+
+    let host_ok = host == site_host || host.ends_with(&format!(".{}", site_host));
+    let path = url.path().trim_start_matches('/').to_lowercase();
+    let path_ok = site_path.is_empty()
+        || path == site_path
+        || path.strip_prefix(site_path).is_some_and(|rest| rest.starts_with('/'));
+    host_ok && path_ok
+}
+
+/// Read one native messaging frame: a native-endian u32 length, then JSON.
+/// `None` at end of input, when the browser closed the port.
+pub fn read_message<R: Read>(reader: &mut R) -> Result<Option<Value>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_MESSAGE_BYTES {
+        bail!("Message of {} bytes is too large", len);
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).context("Truncated message")?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Write one native messaging frame
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(&(body.len() as u32).to_ne_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Answer tab reports from `reader` until it closes, handing each to `forward`.
+/// Bad messages and failed forwards get an error reply; the host keeps running.
+pub fn serve<R, W, F>(reader: &mut R, writer: &mut W, mut forward: F) -> Result<usize>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&BrowserTab) -> Result<()>,
+{
+    let mut forwarded = 0;
+    loop {
+        let message = match read_message(reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(forwarded),
+            // The stream can't be resynchronised after a bad frame
+            Err(e) if e.downcast_ref::<std::io::Error>().is_some() => return Err(e),
+            Err(e) => {
+                write_message(writer, &serde_json::json!({"status": "error", "error": e.to_string()}))?;
+                continue;
+            }
+        };
+
+        let reply = match serde_json::from_value::<BrowserTab>(message)
+            .map_err(|e| anyhow!("Invalid tab report: {}", e))
+            .and_then(|tab| forward(&tab))
+        {
+            Ok(()) => {
+                forwarded += 1;
+                serde_json::json!({"status": "ok"})
+            }
+            Err(e) => serde_json::json!({"status": "error", "error": e.to_string()}),
+        };
+        write_message(writer, &reply)?;
+    }
+}
+
+/// Run as the native messaging host: forward reports on stdin to the daemon
+pub fn run_host(client: &DaemonClient) -> Result<()> {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    serve(&mut stdin, &mut stdout, |tab| {
+        client.post::<Value>("/api/browser/tab", &serde_json::to_value(tab)?).map(|_| ())
+    })?;
+    Ok(())
+}
+
+/// Browsers `flowmode browser-host --install` can register with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrowserKind {
+    Chrome,
+    Chromium,
+    Brave,
+    Firefox,
+}
+
+impl FromStr for BrowserKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chrome" | "google-chrome" => Ok(BrowserKind::Chrome),
+            "chromium" => Ok(BrowserKind::Chromium),
+            "brave" => Ok(BrowserKind::Brave),
+            "firefox" => Ok(BrowserKind::Firefox),
+            other => Err(format!("unknown browser '{}' (expected chrome, chromium, brave or firefox)", other)),
+        }
+    }
+}
+
+impl BrowserKind {
+    /// Directory the browser reads native messaging host manifests from
+    pub fn manifest_dir(&self) -> Result<PathBuf> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("No home directory"))?;
+        let config = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+        Ok(match self {
+            BrowserKind::Chrome => config.join("google-chrome/NativeMessagingHosts"),
+            BrowserKind::Chromium => config.join("chromium/NativeMessagingHosts"),
+            BrowserKind::Brave => config.join("BraveSoftware/Brave-Browser/NativeMessagingHosts"),
+            BrowserKind::Firefox => home.join(".mozilla/native-messaging-hosts"),
+        })
+    }
+
+    /// Host manifest allowing `extension_id` to start `exe`
+    pub fn manifest(&self, exe: &str, extension_id: &str) -> Value {
+        let mut manifest = serde_json::json!({
+            "name": HOST_NAME,
+            "description": "FlowMode browser tab bridge",
+            "path": exe,
+            "type": "stdio",
+        });
+        match self {
+            BrowserKind::Firefox => manifest["allowed_extensions"] = serde_json::json!([extension_id]),
+            _ => {
+                let id = extension_id.trim_start_matches("chrome-extension://").trim_end_matches('/');
+                manifest["allowed_origins"] = serde_json::json!([format!("chrome-extension://{}/", id)]);
+            }
+        }
+        manifest
+    }
+}
+
+/// Register the host with a browser, returning the manifest path
+pub fn install_manifest(browser: BrowserKind, extension_id: &str) -> Result<PathBuf> {
+    // Browsers run the manifest's `path` with only the caller's origin as an
+    // argument, so it points at a wrapper that adds the subcommand
+    let exe = std::env::current_exe()?;
+    let dir = browser.manifest_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let wrapper = crate::config::Config::data_dir().join("browser-host.sh");
+    std::fs::create_dir_all(wrapper.parent().unwrap())?;
+    std::fs::write(&wrapper, format!("#!/bin/sh\nexec '{}' browser-host \"$@\"\n", exe.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&wrapper, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    let manifest = browser.manifest(&wrapper.to_string_lossy(), extension_id);
+    std::fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(body: &[u8]) -> Vec<u8> {
+        let mut bytes = (body.len() as u32).to_ne_bytes().to_vec();
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    #[test]
+    fn test_host_forwards_scripted_extension_messages() {
+        // What the extension sends: a report, garbage, a report without a URL, a private tab
+        let mut input = Vec::new();
+        input.extend(frame(br#"{"url":"https://docs.rs/regex","title":"regex - Rust"}"#));
+        input.extend(frame(b"not json"));
+        input.extend(frame(br#"{"title":"New Tab"}"#));
+        input.extend(frame(br#"{"url":"https://bank.example","title":"Bank","incognito":true}"#));
+
+        let mut output = Vec::new();
+        let mut received = Vec::new();
+        let forwarded = serve(&mut input.as_slice(), &mut output, |tab| {
+            received.push(tab.clone());
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(forwarded, 2);
+        assert_eq!(received[0].url, "https://docs.rs/regex");
+        assert!(received[1].incognito);
+
+        let mut replies = output.as_slice();
+        let statuses: Vec<String> = std::iter::from_fn(|| read_message(&mut replies).unwrap())
+            .map(|reply| reply["status"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(statuses, ["ok", "error", "error", "ok"]);
+    }
+
+    #[test]
+    fn test_active_tab_matches_its_window() {
+        let active = ActiveTab::default();
+        assert!(active.for_window("regex - Rust - Brave").is_none());
+
+        active.report(BrowserTab { url: "https://docs.rs/regex".into(), title: "regex - Rust".into(), incognito: false });
+        assert_eq!(active.for_window("regex - Rust - Brave").unwrap().url, "https://docs.rs/regex");
+        // Another tab or window is focused
+        assert!(active.for_window("Inbox - Brave").is_none());
+
+        active.report(BrowserTab { url: "https://bank.example".into(), title: "Bank".into(), incognito: true });
+        assert!(active.for_window("Bank - Brave").is_none());
+    }
+
+    #[test]
+    fn test_domains_and_manifests() {
+        assert_eq!(domain("https://www.GitHub.com/acme/app?tab=1").as_deref(), Some("github.com"));
+        assert!(url_on_site("https://jira.acme.com/browse/ACME-12", "acme.com"));
+        assert!(url_on_site("https://github.com/Acme/app/pulls", "github.com/acme"));
+        assert!(!url_on_site("https://github.com/acme-labs/app", "github.com/acme"));
+        assert!(!url_on_site("https://notacme.com/", "acme.com"));
+        assert_eq!(domain("chrome://newtab/"), None);
+        assert_eq!(domain("not a url"), None);
+
+        let chrome = BrowserKind::Brave.manifest("/bin/host", "abcdef");
+        assert_eq!(chrome["allowed_origins"][0], "chrome-extension://abcdef/");
+        let firefox = "Firefox".parse::<BrowserKind>().unwrap().manifest("/bin/host", "flowmode@example.org");
+        assert_eq!(firefox["allowed_extensions"][0], "flowmode@example.org");
+        assert!("safari".parse::<BrowserKind>().is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Client for the running daemon's HTTP API
+//!
+//! The CLI and TUI drive the pomodoro timer through the same endpoints as the
+//! web dashboard, so there is only ever one timer. Requests are plain
+//! HTTP/1.0 over a local TCP connection.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+pub struct DaemonClient {
+    addr: SocketAddr,
+}
+
+impl DaemonClient {
+    pub fn new(port: u16) -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], port)),
+        }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.request("GET", path, None)
+    }
+
+    pub fn post<T: DeserializeOwned>(&self, path: &str, body: &Value) -> Result<T> {
+        self.request("POST", path, Some(body))
+    }
+
+    fn request<T: DeserializeOwned>(&self, method: &str, path: &str, body: Option<&Value>) -> Result<T> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, TIMEOUT).map_err(|e| match e.kind() {
+            ErrorKind::ConnectionRefused => anyhow!("FlowMode is not running (start it with `flowmode start`)"),
+            _ => anyhow!("Failed to reach FlowMode at {}: {}", self.addr, e),
+        })?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+
+        let body = body.map(Value::to_string).unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).context("Failed to read the daemon's response")?;
+        let (status, body) = parse_response(&response)?;
+
+        if !(200..300).contains(&status) {
+            let message = serde_json::from_str::<Value>(body)
+                .ok()
+                .and_then(|v| v.get("error").and_then(Value::as_str).map(str::to_string))
+                .unwrap_or_else(|| body.trim().to_string());
+            bail!("{} (HTTP {})", message, status);
+        }
+        serde_json::from_str(body).with_context(|| format!("Unexpected response from {}", path))
+    }
+}
+
+/// Split a raw HTTP response into its status code and body
+fn parse_response(response: &str) -> Result<(u16, &str)> {
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("Malformed response from the daemon"))?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow!("Malformed response from the daemon"))?;
+    Ok((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+
+    /// Answer one request with `status` and `body`, returning the request line and body
+    fn serve_once(status: &'static str, body: &'static str) -> (u16, std::thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut request_body = vec![0; length];
+            reader.read_exact(&mut request_body).unwrap();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.0 {}\r\ncontent-type: application/json\r\n\r\n{}",
+                status,
+                body
+            )
+            .unwrap();
+            (request_line.trim().to_string(), String::from_utf8(request_body).unwrap())
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_post_and_error_responses() {
+        let (port, server) = serve_once("200 OK", r#"{"status":"started"}"#);
+        let reply: Value = DaemonClient::new(port)
+            .post("/api/pomodoro/start", &serde_json::json!({"label": "Report"}))
+            .unwrap();
+        assert_eq!(reply["status"], "started");
+        let (request_line, body) = server.join().unwrap();
+        assert_eq!(request_line, "POST /api/pomodoro/start HTTP/1.0");
+        assert_eq!(body, r#"{"label":"Report"}"#);
+
+        let (port, server) = serve_once("409 Conflict", r#"{"error":"no work session is running"}"#);
+        let err = DaemonClient::new(port)
+            .post::<Value>("/api/pomodoro/interruption", &serde_json::json!({"kind": "internal"}))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no work session is running (HTTP 409)");
+        server.join().unwrap();
+    }
+}
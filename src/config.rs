@@ -2,6 +2,10 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::pomodoro::{
+    DEFAULT_LONG_BREAK_MINS, DEFAULT_SHORT_BREAK_MINS, DEFAULT_WORK_MINS, POMODOROS_UNTIL_LONG_BREAK,
+};
+
 /// App definition for tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackedApp {
@@ -19,11 +23,254 @@ pub enum MatchType {
     Process,      // Match by process name
 }
 
+/// Database retention settings (`[storage]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Keep raw sessions (with window titles) for this many days, then roll
+    /// them up into per-day totals. 0 keeps raw sessions forever.
+    pub retention_days: u32,
+    /// How often the daemon checks for sessions to roll up
+    pub compact_interval_hours: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 0,
+            compact_interval_hours: 6,
+        }
+    }
+}
+
+/// How much of a window title is stored (`keep` < `parsed_only` < `hash` < `drop`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitlePolicy {
+    /// The title as shown, after the scrubbers
+    #[default]
+    Keep,
+    /// Only the parsed context (chat partner, project, site...)
+    ParsedOnly,
+    /// A salted hash, so identical titles still group together
+    Hash,
+    /// No title at all
+    Drop,
+}
+
+/// Title policy for an app or category (`[[privacy.policies]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitlePolicyRule {
+    /// App name from `[[apps]]` (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// Category (case-insensitive); an app rule wins over a category rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub policy: TitlePolicy,
+}
+
+/// Window title redaction (`[privacy]`), applied before titles are stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Policy for apps without a `[[privacy.policies]]` entry
+    pub title_policy: TitlePolicy,
+    /// Built-in scrubbers: "emails", "ticket_ids", "tokens"
+    pub scrub: Vec<String>,
+    /// Extra regexes whose matches are replaced with "[redacted]"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scrub_patterns: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<TitlePolicyRule>,
+    /// Track private browsing windows and password managers as "Private"
+    pub detect_private: bool,
+    /// More windows to track as "Private": title regexes, WM_CLASS substrings
+    /// and browser sites ("bank.com" or "site.com/path")
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensitive_titles: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensitive_classes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensitive_domains: Vec<String>,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            title_policy: TitlePolicy::Keep,
+            scrub: vec!["emails".into(), "tokens".into()],
+            scrub_patterns: Vec::new(),
+            policies: Vec::new(),
+            detect_private: true,
+            sensitive_titles: Vec::new(),
+            sensitive_classes: Vec::new(),
+            sensitive_domains: Vec::new(),
+        }
+    }
+}
+
+/// Pomodoro timer settings (`[pomodoro]`), durations in minutes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub work_duration: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    /// Completed pomodoros before a long break
+    pub long_break_after: u32,
+    /// Start the break as soon as a work session ends (otherwise it waits, paused)
+    pub auto_start_breaks: bool,
+    /// Start the next work session as soon as a break ends
+    pub auto_start_work: bool,
+    /// Completed pomodoros to aim for each day
+    pub daily_target: u32,
+    /// Pause a work session while you are idle and resume it when you return
+    pub pause_on_idle: bool,
+    /// Pause the timer while tracking is paused from the tray
+    pub pause_with_tracking: bool,
+    /// A break only counts as taken after this long idle during it (0 = every break counts)
+    pub break_idle_secs: u64,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            work_duration: DEFAULT_WORK_MINS,
+            short_break: DEFAULT_SHORT_BREAK_MINS,
+            long_break: DEFAULT_LONG_BREAK_MINS,
+            long_break_after: POMODOROS_UNTIL_LONG_BREAK,
+            auto_start_breaks: true,
+            auto_start_work: true,
+            daily_target: 8,
+            pause_on_idle: true,
+            pause_with_tracking: true,
+            break_idle_secs: 60,
+        }
+    }
+}
+
+/// Desktop notifications (`[notifications]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Sound file played with each notification (via paplay, pw-play or aplay)
+    pub sound: Option<PathBuf>,
+    /// Remind again while a due break has not been started for this long (0 = never)
+    pub break_overdue_mins: u64,
+    /// Warn after this long at the computer without going idle (0 = never)
+    pub long_session_mins: u64,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sound: None,
+            break_overdue_mins: 5,
+            long_session_mins: 90,
+        }
+    }
+}
+
+/// Title parsing rule (`[[title_rules]]`), tried in order before the built-in parsers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitleRule {
+    /// Only apply to this app (name from `[[apps]]`, case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// Only apply to apps in this category (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Regex matched against the window title; named captures fill the templates
+    pub pattern: String,
+    /// Display template, e.g. "Jira: {key}"
+    pub display: String,
+    pub context_type: String,
+    /// Context template; the whole title when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+/// Project (`[[projects]]`): sessions matching any of its rules count toward it.
+/// Projects are tried in order and the first match wins.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectRule {
+    pub name: String,
+    /// Regexes matched against the window title of any app
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub title_patterns: Vec<String>,
+    /// Git repositories (directory names) of terminal sessions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repos: Vec<String>,
+    /// Domains of browser sessions, e.g. "github.com/acme"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domains: Vec<String>,
+    /// Teams channels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub teams_channels: Vec<String>,
+}
+
+/// Site rule (`[[sites]]`): browser sessions on these sites get its category and
+/// productivity score. Rules are tried in order, before the built-in defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SiteRule {
+    /// Category reported for the matched browser time, e.g. "Entertainment"
+    pub category: String,
+    /// Productivity score from -2 (distracting) to +2 (productive)
+    #[serde(default)]
+    pub score: i8,
+    /// Domains, optionally with a path, e.g. "youtube.com" or "github.com/acme"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domains: Vec<String>,
+    /// Parsed context types, e.g. "video" or "email"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_types: Vec<String>,
+    /// Text contained in the parsed context, e.g. a channel or page name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contexts: Vec<String>,
+}
+
+/// A workspace mode (`[[modes]]`). Only the pomodoro overrides are used so far.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeConfig {
+    pub name: String,
+    #[serde(default)]
+    pub focus: ModeFocus,
+}
+
+/// Focus settings of a mode (`[modes.focus]`); unset values fall back to `[pomodoro]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModeFocus {
+    /// Whether the pomodoro timer is available in this mode
+    pub pomodoro: Option<bool>,
+    pub work_duration: Option<u64>,
+    pub short_break: Option<u64>,
+    pub long_break: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub idle_timeout_secs: u64,
     pub poll_interval_secs: u64,
     pub apps: Vec<TrackedApp>,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub pomodoro: PomodoroConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<ModeConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub title_rules: Vec<TitleRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<ProjectRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sites: Vec<SiteRule>,
 }
 
 impl Default for Config {
@@ -94,6 +341,14 @@ impl Default for Config {
                     category: "Files".into(),
                 },
             ],
+            storage: StorageConfig::default(),
+            pomodoro: PomodoroConfig::default(),
+            notifications: NotificationConfig::default(),
+            privacy: PrivacyConfig::default(),
+            modes: Vec::new(),
+            title_rules: Vec::new(),
+            projects: Vec::new(),
+            sites: Vec::new(),
         }
     }
 }
@@ -131,6 +386,36 @@ impl Config {
         }
     }
 
+    /// Follow `config.toml`, starting from `initial`: edits are picked up within
+    /// a few seconds and sent to the receivers. Edits that don't parse are
+    /// logged and skipped.
+    pub fn watch(initial: Config) -> tokio::sync::watch::Receiver<Config> {
+        let (tx, rx) = tokio::sync::watch::channel(initial);
+        tokio::spawn(async move {
+            let modified = || std::fs::metadata(Self::config_path()).and_then(|m| m.modified()).ok();
+            let mut last = modified();
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+            loop {
+                interval.tick().await;
+                let current = modified();
+                if current == last {
+                    continue;
+                }
+                last = current;
+                match Self::load() {
+                    Ok(config) => {
+                        if tx.send(config).is_err() {
+                            break;
+                        }
+                        tracing::info!("Reloaded {:?}", Self::config_path());
+                    }
+                    Err(e) => tracing::warn!("Ignoring invalid config: {}", e),
+                }
+            }
+        });
+        rx
+    }
+
     pub fn save(&self) -> Result<()> {
         let path = Self::config_path();
         std::fs::create_dir_all(path.parent().unwrap())?;
@@ -139,6 +424,16 @@ impl Config {
         Ok(())
     }
 
+    /// Find a mode by name (case-insensitive)
+    pub fn mode(&self, name: &str) -> Option<&ModeConfig> {
+        self.modes.iter().find(|m| m.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Find an app by name (case-insensitive)
+    pub fn app(&self, name: &str) -> Option<&TrackedApp> {
+        self.apps.iter().find(|a| a.name.eq_ignore_ascii_case(name))
+    }
+
     /// Find matching app for given window class and title
     pub fn match_window(&self, window_class: &str, window_title: &str) -> Option<&TrackedApp> {
         let class_lower = window_class.to_lowercase();
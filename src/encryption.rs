@@ -0,0 +1,388 @@
+//! Encryption at rest for activity.db (SQLCipher)
+//!
+//! The database is encrypted with `flowmode db encrypt`, keyed either with a
+//! passphrase (SQLCipher derives the key with PBKDF2) or with a random key kept
+//! in the freedesktop Secret Service. Every reader, the daemon and its web
+//! server included, goes through `Storage::open`, which calls `unlock` for
+//! encrypted files. The key is looked up once per process:
+//!
+//! 1. `FLOWMODE_DB_PASSPHRASE`
+//! 2. the Secret Service item for the database
+//! 3. a passphrase prompt, when stdin is a terminal
+
+use anyhow::{anyhow, bail, Context, Result};
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::blocking::LocalConnection;
+use dbus::message::MatchRule;
+use rusqlite::Connection;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Environment variable with the database passphrase
+pub const PASSPHRASE_ENV: &str = "FLOWMODE_DB_PASSPHRASE";
+
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Key of the unlocked database: (path, `PRAGMA key` value)
+static UNLOCKED: Mutex<Option<(PathBuf, String)>> = Mutex::new(None);
+
+/// Whether the file at `path` exists and is not a plain SQLite database
+pub fn is_encrypted(path: &Path) -> Result<bool> {
+    let mut header = Vec::with_capacity(SQLITE_HEADER.len());
+    match std::fs::File::open(path) {
+        Ok(file) => file.take(SQLITE_HEADER.len() as u64).read_to_end(&mut header)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(!header.is_empty() && header != SQLITE_HEADER)
+}
+
+/// Key `conn`, an encrypted database at `path`
+pub fn unlock(conn: &Connection, path: &Path) -> Result<()> {
+    let mut unlocked = UNLOCKED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, key)) = unlocked.as_ref().filter(|(p, _)| p == path) {
+        return apply_key(conn, key, path);
+    }
+    let key = find_key(path)?;
+    apply_key(conn, &key, path)?;
+    *unlocked = Some((path.to_path_buf(), key));
+    Ok(())
+}
+
+fn apply_key(conn: &Connection, key: &str, path: &Path) -> Result<()> {
+    conn.pragma_update(None, "key", key)?;
+    // SQLCipher only notices a wrong key on the first read
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|_| anyhow!("Wrong key for the encrypted database {}", path.display()))
+}
+
+/// Key for an encrypted database: passphrase variable, Secret Service, prompt
+fn find_key(path: &Path) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    match SecretService::connect().and_then(|service| service.find(path)) {
+        Ok(Some(key)) => return Ok(raw_key(&key)),
+        Ok(None) => {}
+        Err(e) => tracing::debug!("No database key from the Secret Service: {}", e),
+    }
+    if std::io::stdin().is_terminal() {
+        return read_passphrase(&format!("Passphrase for {}: ", path.display()));
+    }
+    bail!(
+        "{} is encrypted: set {} or keep its key in the Secret Service (`flowmode db encrypt --secret-service`)",
+        path.display(),
+        PASSPHRASE_ENV
+    )
+}
+
+/// `PRAGMA key` value for a raw 256-bit key, skipping SQLCipher's key derivation
+fn raw_key(hex: &str) -> String {
+    format!("x'{}'", hex)
+}
+
+/// Where the key of a newly encrypted database comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    Passphrase,
+    SecretService,
+}
+
+/// Encrypt the plain database at `path` in place
+pub fn encrypt(path: &Path, source: KeySource) -> Result<()> {
+    if is_encrypted(path)? {
+        bail!("{} is already encrypted", path.display());
+    }
+    let key = match source {
+        KeySource::Passphrase => new_passphrase()?,
+        KeySource::SecretService => {
+            let mut bytes = [0u8; 32];
+            getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("No randomness for the database key: {}", e))?;
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            SecretService::connect()
+                .and_then(|service| service.store(path, &hex))
+                .context("Failed to store the database key in the Secret Service")?;
+            raw_key(&hex)
+        }
+    };
+
+    let conn = Connection::open(path)?;
+    rewrite(&conn, path, &key)?;
+    *UNLOCKED.lock().unwrap_or_else(|e| e.into_inner()) = Some((path.to_path_buf(), key));
+    Ok(())
+}
+
+/// Decrypt the encrypted database at `path` in place
+pub fn decrypt(path: &Path) -> Result<()> {
+    if !is_encrypted(path)? {
+        bail!("{} is not encrypted", path.display());
+    }
+    let conn = Connection::open(path)?;
+    unlock(&conn, path)?;
+    rewrite(&conn, path, "")?;
+    *UNLOCKED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+    if let Err(e) = SecretService::connect().and_then(|service| service.delete(path)) {
+        tracing::debug!("Database key not removed from the Secret Service: {}", e);
+    }
+    Ok(())
+}
+
+/// Copy the database open in `conn` to a file keyed with `key` ("" = plain)
+/// and swap it in for `path`
+fn rewrite(conn: &Connection, path: &Path, key: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".rewrite");
+    let tmp = PathBuf::from(tmp);
+    if tmp.exists() {
+        std::fs::remove_file(&tmp)?;
+    }
+
+    let copied = (|| -> Result<()> {
+        conn.execute("ATTACH DATABASE ?1 AS rewritten KEY ?2", rusqlite::params![tmp.to_string_lossy(), key])?;
+        conn.query_row("SELECT sqlcipher_export('rewritten')", [], |_| Ok(()))?;
+        conn.execute("DETACH DATABASE rewritten", [])?;
+        Ok(())
+    })();
+    if let Err(e) = copied {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.context("Failed to copy the database"));
+    }
+
+    std::fs::File::open(&tmp)?.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Passphrase for a newly encrypted database, from the environment or asked twice
+fn new_passphrase() -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if passphrase.is_empty() {
+            bail!("{} is empty", PASSPHRASE_ENV);
+        }
+        return Ok(passphrase);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("Set {} or run this in a terminal", PASSPHRASE_ENV);
+    }
+    let passphrase = read_passphrase("New passphrase: ")?;
+    if passphrase.is_empty() {
+        bail!("The passphrase can't be empty");
+    }
+    if read_passphrase("Repeat passphrase: ")? != passphrase {
+        bail!("The passphrases don't match");
+    }
+    Ok(passphrase)
+}
+
+/// Read a line from the terminal without echoing it
+fn read_passphrase(prompt: &str) -> Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+    terminal::enable_raw_mode()?;
+    let read = (|| -> Result<String> {
+        let mut passphrase = String::new();
+        loop {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => return Ok(passphrase),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => bail!("Cancelled"),
+                KeyCode::Esc => bail!("Cancelled"),
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                _ => {}
+            }
+        }
+    })();
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    read
+}
+
+const SECRETS: &str = "org.freedesktop.secrets";
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const DEFAULT_COLLECTION: &str = "/org/freedesktop/secrets/aliases/default";
+const SERVICE_IFACE: &str = "org.freedesktop.Secret.Service";
+const COLLECTION_IFACE: &str = "org.freedesktop.Secret.Collection";
+const ITEM_IFACE: &str = "org.freedesktop.Secret.Item";
+const PROMPT_IFACE: &str = "org.freedesktop.Secret.Prompt";
+const DBUS_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait for the user to answer an unlock prompt
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Secret as sent over D-Bus: (session, parameters, value, content type)
+type Secret = (dbus::Path<'static>, Vec<u8>, Vec<u8>, String);
+
+/// Session with the freedesktop Secret Service (GNOME Keyring, KWallet...)
+struct SecretService {
+    conn: LocalConnection,
+    session: dbus::Path<'static>,
+}
+
+impl SecretService {
+    fn connect() -> Result<Self> {
+        let conn = LocalConnection::new_session()?;
+        let (_, session): (Variant<Box<dyn RefArg>>, dbus::Path<'static>) = conn
+            .with_proxy(SECRETS, SERVICE_PATH, DBUS_TIMEOUT)
+            .method_call(SERVICE_IFACE, "OpenSession", ("plain", Variant("")))?;
+        Ok(Self { conn, session })
+    }
+
+    /// Lookup attributes of the key for the database at `path`
+    fn attributes(path: &Path) -> HashMap<String, String> {
+        HashMap::from([
+            ("application".to_string(), "flowmode".to_string()),
+            ("database".to_string(), path.to_string_lossy().into_owned()),
+        ])
+    }
+
+    fn items(&self, path: &Path) -> Result<(Vec<dbus::Path<'static>>, Vec<dbus::Path<'static>>)> {
+        let items = self
+            .conn
+            .with_proxy(SECRETS, SERVICE_PATH, DBUS_TIMEOUT)
+            .method_call(SERVICE_IFACE, "SearchItems", (Self::attributes(path),))?;
+        Ok(items)
+    }
+
+    /// Stored key for the database at `path`, unlocking the keyring if needed
+    fn find(&self, path: &Path) -> Result<Option<String>> {
+        let (unlocked, locked) = self.items(path)?;
+        let item = match (unlocked.into_iter().next(), locked.into_iter().next()) {
+            (Some(item), _) => item,
+            (None, Some(item)) => {
+                let (_, prompt): (Vec<dbus::Path<'static>>, dbus::Path<'static>) = self
+                    .conn
+                    .with_proxy(SECRETS, SERVICE_PATH, DBUS_TIMEOUT)
+                    .method_call(SERVICE_IFACE, "Unlock", (vec![item.clone()],))?;
+                self.prompt(prompt)?;
+                item
+            }
+            (None, None) => return Ok(None),
+        };
+        let (secret,): (Secret,) = self
+            .conn
+            .with_proxy(SECRETS, item, DBUS_TIMEOUT)
+            .method_call(ITEM_IFACE, "GetSecret", (&self.session,))?;
+        Ok(Some(String::from_utf8(secret.2).context("Database key in the Secret Service is not text")?))
+    }
+
+    /// Store `key` for the database at `path` in the default collection
+    fn store(&self, path: &Path, key: &str) -> Result<()> {
+        let mut properties = PropMap::new();
+        properties.insert(
+            "org.freedesktop.Secret.Item.Label".to_string(),
+            Variant(Box::new(format!("FlowMode database key ({})", path.display()))),
+        );
+        properties.insert(
+            "org.freedesktop.Secret.Item.Attributes".to_string(),
+            Variant(Box::new(Self::attributes(path))),
+        );
+        let secret: Secret = (self.session.clone(), Vec::new(), key.as_bytes().to_vec(), "text/plain".to_string());
+        let (item, prompt): (dbus::Path<'static>, dbus::Path<'static>) = self
+            .conn
+            .with_proxy(SECRETS, DEFAULT_COLLECTION, DBUS_TIMEOUT)
+            .method_call(COLLECTION_IFACE, "CreateItem", (properties, secret, true))?;
+        if &*item == "/" {
+            // The collection is locked; the item is created once the user unlocks it
+            self.prompt(prompt)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the stored key for the database at `path`
+    fn delete(&self, path: &Path) -> Result<()> {
+        let (unlocked, locked) = self.items(path)?;
+        for item in unlocked.into_iter().chain(locked) {
+            let (prompt,): (dbus::Path<'static>,) =
+                self.conn.with_proxy(SECRETS, item, DBUS_TIMEOUT).method_call(ITEM_IFACE, "Delete", ())?;
+            self.prompt(prompt)?;
+        }
+        Ok(())
+    }
+
+    /// Show a Secret Service prompt ("/" = none needed) and wait for the answer
+    fn prompt(&self, prompt: dbus::Path<'static>) -> Result<()> {
+        if &*prompt == "/" {
+            return Ok(());
+        }
+        let dismissed = Rc::new(Cell::new(None));
+        let answer = dismissed.clone();
+        let rule = MatchRule::new_signal(PROMPT_IFACE, "Completed").with_path(prompt.clone());
+        self.conn.add_match(rule, move |(was_dismissed, _): (bool, Variant<Box<dyn RefArg>>), _, _| {
+            answer.set(Some(was_dismissed));
+            false
+        })?;
+        self.conn
+            .with_proxy(SECRETS, prompt, DBUS_TIMEOUT)
+            .method_call::<(), _, _, _>(PROMPT_IFACE, "Prompt", ("",))?;
+
+        let started = Instant::now();
+        while dismissed.get().is_none() {
+            if started.elapsed() > PROMPT_TIMEOUT {
+                bail!("No answer to the Secret Service prompt");
+            }
+            self.conn.process(Duration::from_millis(250))?;
+        }
+        if dismissed.get() == Some(true) {
+            bail!("The Secret Service prompt was dismissed");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt_database() {
+        let dir = std::env::temp_dir().join(format!("flowmode-encryption-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("activity.db");
+        let _ = std::fs::remove_file(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch("CREATE TABLE activity (title TEXT); INSERT INTO activity VALUES ('Inbox');").unwrap();
+        }
+        assert!(!is_encrypted(&path).unwrap());
+        assert!(!is_encrypted(&dir.join("missing.db")).unwrap());
+
+        let key = "correct horse";
+        let conn = Connection::open(&path).unwrap();
+        rewrite(&conn, &path, key).unwrap();
+        drop(conn);
+        assert!(is_encrypted(&path).unwrap());
+        assert!(!std::fs::read(&path).unwrap().windows(5).any(|w| w == b"Inbox"));
+
+        let conn = Connection::open(&path).unwrap();
+        assert!(apply_key(&conn, "wrong", &path).is_err());
+        let conn = Connection::open(&path).unwrap();
+        apply_key(&conn, key, &path).unwrap();
+        let title: String = conn.query_row("SELECT title FROM activity", [], |row| row.get(0)).unwrap();
+        assert_eq!(title, "Inbox");
+
+        rewrite(&conn, &path, "").unwrap();
+        drop(conn);
+        assert!(!is_encrypted(&path).unwrap());
+        let conn = Connection::open(&path).unwrap();
+        let count: i64 = conn.query_row("SELECT count(*) FROM activity", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
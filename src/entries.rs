@@ -0,0 +1,213 @@
+//! Manual time entries and edits of stored sessions
+//!
+//! Meetings, calls and whiteboard sessions away from the computer never reach
+//! the tracker, so they are added by hand (`flowmode log add`,
+//! `POST /api/activities`), and misattributed sessions are corrected, split or
+//! merged. This module parses the times people type and fills in what they
+//! leave out from the config; `Storage` does the rest.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Local, NaiveTime};
+
+use crate::config::{Config, TrackedApp};
+use crate::project::UNASSIGNED;
+use crate::storage::{parse_date, ActivityRecord, ManualSession, SessionEdit};
+use crate::title_parser::TitleParser;
+
+/// Category of manual entries for apps missing from `[[apps]]`
+pub const MANUAL_CATEGORY: &str = "Manual";
+
+/// Parse a point in time: `HH:MM[:SS]` (today), `YYYY-MM-DD HH:MM`,
+/// `yesterday HH:MM` or RFC 3339
+pub fn parse_time(s: &str) -> Result<DateTime<Local>> {
+    let s = s.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Local));
+    }
+    let (date, time) = match s.rsplit_once([' ', 'T']) {
+        Some((date, time)) => (parse_date(date)?, time),
+        None => (Local::now().date_naive(), s),
+    };
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .map_err(|_| anyhow!("invalid time '{}' (expected HH:MM, YYYY-MM-DD HH:MM or yesterday HH:MM)", s))?;
+    date.and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| anyhow!("{} doesn't exist in the local time zone", s))
+}
+
+/// Parse a length of time: `45m`, `1h30m`, `90s` or plain minutes
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let text = s.trim().to_lowercase();
+    let invalid = || anyhow!("invalid duration '{}' (expected e.g. 45m, 1h30m or 2h)", s);
+
+    let secs = match text.parse::<i64>() {
+        Ok(minutes) => minutes * 60,
+        Err(_) => {
+            let mut secs = 0;
+            let mut digits = String::new();
+            for c in text.chars() {
+                if c.is_ascii_digit() {
+                    digits.push(c);
+                    continue;
+                }
+                let n: i64 = digits.parse().map_err(|_| invalid())?;
+                digits.clear();
+                secs += n * match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    's' => 1,
+                    _ => return Err(invalid()),
+                };
+            }
+            if !digits.is_empty() {
+                return Err(invalid());
+            }
+            secs
+        }
+    };
+    if secs <= 0 {
+        bail!("a duration has to be longer than zero");
+    }
+    Ok(Duration::seconds(secs))
+}
+
+/// Project name given by the user; empty or "Unassigned" means none
+pub fn project_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    (!name.is_empty() && !name.eq_ignore_ascii_case(UNASSIGNED)).then(|| name.to_string())
+}
+
+/// A manual entry as typed; `None` fields get defaults
+#[derive(Debug, Clone)]
+pub struct EntryDraft {
+    pub app_name: String,
+    pub category: Option<String>,
+    pub window_title: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub project: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Fills in manual entries and edits from the config: the category of a
+/// configured app and the parse of a new title
+pub struct Entries {
+    apps: Vec<TrackedApp>,
+    parser: TitleParser,
+}
+
+impl Entries {
+    pub fn from_config(config: &Config) -> Self {
+        Self { apps: config.apps.clone(), parser: TitleParser::from_config(config) }
+    }
+
+    /// Category of the `[[apps]]` entry called `app` (case-insensitive)
+    fn app_category(&self, app: &str) -> Option<&str> {
+        self.apps
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(app.trim()))
+            .map(|a| a.category.as_str())
+    }
+
+    /// The session to store for `draft`: the category defaults to the app's (or
+    /// "Manual"), the window title to the note
+    pub fn manual_session(&self, draft: EntryDraft) -> ManualSession {
+        let app_name = draft.app_name.trim().to_string();
+        let category = draft
+            .category
+            .filter(|c| !c.trim().is_empty())
+            .unwrap_or_else(|| self.app_category(&app_name).unwrap_or(MANUAL_CATEGORY).to_string());
+        let note = draft.note.filter(|n| !n.trim().is_empty());
+        let window_title = draft.window_title.or_else(|| note.clone()).unwrap_or_default();
+        ManualSession {
+            parsed: self.parser.parse(&app_name, &category, &window_title),
+            app_name,
+            category,
+            window_title,
+            started_at: draft.started_at,
+            ended_at: draft.ended_at,
+            project: draft.project.as_deref().and_then(project_name),
+            note,
+        }
+    }
+
+    /// Complete an edit of `record`: moving a session to a configured app takes
+    /// that app's category unless one is given, and a new app, category or
+    /// title is parsed again
+    pub fn complete_edit(&self, record: &ActivityRecord, edit: &mut SessionEdit) {
+        if edit.category.is_none() {
+            if let Some(app) = &edit.app_name {
+                edit.category = self.app_category(app).map(str::to_string);
+            }
+        }
+        if edit.app_name.is_some() || edit.category.is_some() || edit.window_title.is_some() {
+            edit.parsed = Some(self.parser.parse(
+                edit.app_name.as_deref().unwrap_or(&record.app_name),
+                edit.category.as_deref().unwrap_or(&record.category),
+                edit.window_title.as_deref().unwrap_or(&record.window_title),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone, Timelike};
+
+    #[test]
+    fn test_parse_time_and_duration() {
+        let expected = Local.with_ymd_and_hms(2024, 3, 5, 14, 30, 0).unwrap();
+        assert_eq!(parse_time("2024-03-05 14:30").unwrap(), expected);
+        assert_eq!(parse_time("2024-03-05T14:30:00").unwrap(), expected);
+        assert_eq!(parse_time(&expected.to_rfc3339()).unwrap(), expected);
+        let today = parse_time("9:05").unwrap();
+        assert_eq!((today.date_naive(), today.hour(), today.minute()), (Local::now().date_naive(), 9, 5));
+        let yesterday = parse_time("yesterday 23:00").unwrap();
+        assert_eq!(yesterday.date_naive(), Local::now().date_naive() - Duration::days(1));
+        assert!(parse_time("25:00").is_err());
+        assert!(parse_time("2024-03-05").is_err());
+
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("45").unwrap(), Duration::minutes(45));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::seconds(90));
+        for invalid in ["", "0m", "1h30", "1d", "h"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_entry_defaults() {
+        let entries = Entries::from_config(&Config::default());
+        let start = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap().and_hms_opt(10, 0, 0).unwrap();
+        let start = start.and_local_timezone(Local).unwrap();
+        let draft = EntryDraft {
+            app_name: "Whiteboard".into(),
+            category: None,
+            window_title: None,
+            started_at: start,
+            ended_at: start + Duration::hours(1),
+            project: Some("unassigned".into()),
+            note: Some("Sprint planning".into()),
+        };
+        let session = entries.manual_session(draft.clone());
+        assert_eq!((session.category.as_str(), session.window_title.as_str()), (MANUAL_CATEGORY, "Sprint planning"));
+        assert_eq!(session.project, None);
+        let session = entries.manual_session(EntryDraft { app_name: "teams".into(), ..draft });
+        assert_eq!(session.category, "Communication");
+
+        // Moving a session to a configured app takes its category and re-parses the title
+        let storage = crate::storage::Storage::open_in_memory().unwrap();
+        let id = storage.add_manual_session(&session).unwrap();
+        let record = storage.get_session(id).unwrap().unwrap();
+        let mut edit = SessionEdit { app_name: Some("VS Code".into()), ..Default::default() };
+        entries.complete_edit(&record, &mut edit);
+        assert_eq!(edit.category.as_deref(), Some("Development"));
+        assert!(edit.parsed.is_some());
+        let mut edit = SessionEdit { note: Some(None), ..Default::default() };
+        entries.complete_edit(&record, &mut edit);
+        assert!(edit.parsed.is_none());
+    }
+}
@@ -0,0 +1,494 @@
+//! Export - Write tracked sessions as CSV, JSON Lines or iCalendar
+//!
+//! Sessions are streamed straight from `Storage` into any writer, so a
+//! year-long export never has to fit in memory.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::storage::{ActivityRecord, SessionFilter, Storage, TimeRange};
+
+/// Consecutive sessions of the same app at most this far apart become one calendar event
+const MERGE_GAP_SECS: i64 = 120;
+
+/// Number of window titles listed in a calendar event description
+const ICS_MAX_TITLES: usize = 5;
+
+/// Output format for `flowmode export` and `/api/export`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Ics,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Ics => "text/calendar; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Ics => "ics",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" | "ndjson" | "json" => Ok(ExportFormat::Jsonl),
+            "ics" | "ical" | "icalendar" => Ok(ExportFormat::Ics),
+            other => Err(format!("unknown export format '{}' (expected csv, jsonl or ics)", other)),
+        }
+    }
+}
+
+/// Stream sessions in `range` to `out`, returning the number of records written
+/// (rows for CSV/JSONL, merged events for ICS)
+pub fn export<W: Write>(
+    storage: &Storage,
+    range: &TimeRange,
+    filter: &SessionFilter,
+    format: ExportFormat,
+    out: W,
+) -> Result<usize> {
+    match format {
+        ExportFormat::Csv => write_all(storage, range, filter, CsvWriter::new(out)?),
+        ExportFormat::Jsonl => write_all(storage, range, filter, JsonlWriter { out, written: 0 }),
+        ExportFormat::Ics => write_all(storage, range, filter, IcsWriter::new(out)?),
+    }
+}
+
+fn write_all<S: SessionSink>(
+    storage: &Storage,
+    range: &TimeRange,
+    filter: &SessionFilter,
+    mut sink: S,
+) -> Result<usize> {
+    storage.for_each_session(range, filter, |record| sink.push(record))?;
+    sink.finish()
+}
+
+/// Receives sessions in start order
+trait SessionSink {
+    fn push(&mut self, record: ActivityRecord) -> Result<()>;
+    fn finish(self) -> Result<usize>;
+}
+
+/// One row per session
+struct CsvWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> CsvWriter<W> {
+    fn new(mut out: W) -> Result<Self> {
+        writeln!(out, "id,app_name,category,window_title,started_at,ended_at,duration_secs,active_secs,passive_secs,source,pomodoro,cwd,repo,branch,project,url,domain,site_category,score,redacted,note,importer")?;
+        Ok(Self { out, written: 0 })
+    }
+}
+
+impl<W: Write> SessionSink for CsvWriter<W> {
+    fn push(&mut self, r: ActivityRecord) -> Result<()> {
+        writeln!(
+            self.out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            r.id,
+            csv_field(&r.app_name),
+            csv_field(&r.category),
+            csv_field(&r.window_title),
+            r.started_at.to_rfc3339(),
+            r.ended_at.map(|e| e.to_rfc3339()).unwrap_or_default(),
+            r.duration_secs,
+            r.active_secs,
+            r.passive_secs,
+            r.source,
+            r.pomodoro.as_deref().unwrap_or(""),
+            csv_field(r.cwd.as_deref().unwrap_or("")),
+            csv_field(r.repo.as_deref().unwrap_or("")),
+            csv_field(r.branch.as_deref().unwrap_or("")),
+            csv_field(r.project.as_deref().unwrap_or("")),
+            csv_field(r.url.as_deref().unwrap_or("")),
+            csv_field(r.domain.as_deref().unwrap_or("")),
+            csv_field(r.site_category.as_deref().unwrap_or("")),
+            r.score.map(|s| s.to_string()).unwrap_or_default(),
+            r.redacted.map(|p| p.as_str()).unwrap_or(""),
+            csv_field(r.note.as_deref().unwrap_or("")),
+            r.importer.as_deref().unwrap_or(""),
+        )?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<usize> {
+        self.out.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonlRow<'a> {
+    id: i64,
+    app_name: &'a str,
+    category: &'a str,
+    window_title: &'a str,
+    started_at: String,
+    ended_at: Option<String>,
+    duration_secs: i64,
+    active_secs: i64,
+    passive_secs: i64,
+    source: &'a str,
+    pomodoro: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    site_category: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redacted: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    importer: Option<&'a str>,
+}
+
+/// One JSON object per line per session
+struct JsonlWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> SessionSink for JsonlWriter<W> {
+    fn push(&mut self, r: ActivityRecord) -> Result<()> {
+        let row = JsonlRow {
+            id: r.id,
+            app_name: &r.app_name,
+            category: &r.category,
+            window_title: &r.window_title,
+            started_at: r.started_at.to_rfc3339(),
+            ended_at: r.ended_at.map(|e| e.to_rfc3339()),
+            duration_secs: r.duration_secs,
+            active_secs: r.active_secs,
+            passive_secs: r.passive_secs,
+            source: &r.source,
+            pomodoro: r.pomodoro.as_deref(),
+            cwd: r.cwd.as_deref(),
+            repo: r.repo.as_deref(),
+            branch: r.branch.as_deref(),
+            project: r.project.as_deref(),
+            url: r.url.as_deref(),
+            domain: r.domain.as_deref(),
+            site_category: r.site_category.as_deref(),
+            score: r.score,
+            redacted: r.redacted.map(|p| p.as_str()),
+            note: r.note.as_deref(),
+            importer: r.importer.as_deref(),
+        };
+        serde_json::to_writer(&mut self.out, &row)?;
+        self.out.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<usize> {
+        self.out.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// Consecutive sessions of one app, exported as a single VEVENT
+struct MergedSession {
+    first_id: i64,
+    app_name: String,
+    category: String,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    /// Window titles with their combined duration, in first-seen order
+    titles: Vec<(String, i64)>,
+}
+
+impl MergedSession {
+    fn from_record(r: ActivityRecord) -> Self {
+        let end = r.end_time();
+        Self {
+            first_id: r.id,
+            app_name: r.app_name,
+            category: r.category,
+            start: r.started_at,
+            end,
+            titles: vec![(r.window_title, r.duration_secs)],
+        }
+    }
+
+    fn can_absorb(&self, r: &ActivityRecord) -> bool {
+        r.app_name == self.app_name && (r.started_at - self.end).num_seconds() <= MERGE_GAP_SECS
+    }
+
+    fn absorb(&mut self, r: ActivityRecord) {
+        self.end = self.end.max(r.end_time());
+        match self.titles.iter_mut().find(|(title, _)| *title == r.window_title) {
+            Some((_, secs)) => *secs += r.duration_secs,
+            None => self.titles.push((r.window_title, r.duration_secs)),
+        }
+    }
+}
+
+/// iCalendar output with one VEVENT per merged session
+struct IcsWriter<W: Write> {
+    out: W,
+    pending: Option<MergedSession>,
+    stamp: String,
+    written: usize,
+}
+
+impl<W: Write> IcsWriter<W> {
+    fn new(mut out: W) -> Result<Self> {
+        for line in [
+            "BEGIN:VCALENDAR",
+            "VERSION:2.0",
+            "PRODID:-//FlowMode//Activity Export//EN",
+            "CALSCALE:GREGORIAN",
+            "X-WR-CALNAME:FlowMode",
+        ] {
+            write!(out, "{}\r\n", line)?;
+        }
+        Ok(Self {
+            out,
+            pending: None,
+            stamp: ics_time(&Local::now()),
+            written: 0,
+        })
+    }
+
+    fn write_event(&mut self, event: &MergedSession) -> Result<()> {
+        let mut titles = event.titles.clone();
+        titles.sort_by_key(|(_, secs)| std::cmp::Reverse(*secs));
+        let description = titles
+            .iter()
+            .filter(|(title, _)| !title.is_empty())
+            .take(ICS_MAX_TITLES)
+            .map(|(title, secs)| format!("{} ({})", title, crate::tray::format_duration(*secs)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let lines = [
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:flowmode-{}-{}@flowmode", event.first_id, event.start.timestamp()),
+            format!("DTSTAMP:{}", self.stamp),
+            format!("DTSTART:{}", ics_time(&event.start)),
+            format!("DTEND:{}", ics_time(&event.end)),
+            format!("SUMMARY:{}", ics_text(&event.app_name)),
+            format!("CATEGORIES:{}", ics_text(&event.category)),
+            format!("DESCRIPTION:{}", ics_text(&description)),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ];
+        for line in &lines {
+            self.out.write_all(fold_ics_line(line).as_bytes())?;
+        }
+        self.written += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> SessionSink for IcsWriter<W> {
+    fn push(&mut self, r: ActivityRecord) -> Result<()> {
+        match self.pending.as_mut() {
+            Some(pending) if pending.can_absorb(&r) => pending.absorb(r),
+            _ => {
+                if let Some(done) = self.pending.replace(MergedSession::from_record(r)) {
+                    self.write_event(&done)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<usize> {
+        if let Some(done) = self.pending.take() {
+            self.write_event(&done)?;
+        }
+        self.out.write_all(b"END:VCALENDAR\r\n")?;
+        self.out.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// UTC timestamp in iCalendar basic format
+fn ics_time(dt: &DateTime<Local>) -> String {
+    dt.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape an iCalendar TEXT value (RFC 5545 §3.3.11)
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+/// Fold a content line at 75 octets and terminate it with CRLF
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn record(id: i64, app: &str, title: &str, start_min: i64, secs: i64) -> ActivityRecord {
+        let started_at = Local.with_ymd_and_hms(2024, 3, 5, 9, 0, 0).unwrap() + Duration::minutes(start_min);
+        ActivityRecord {
+            id,
+            app_name: app.to_string(),
+            category: "Development".to_string(),
+            window_title: title.to_string(),
+            started_at,
+            ended_at: Some(started_at + Duration::seconds(secs)),
+            duration_secs: secs,
+            active_secs: secs,
+            passive_secs: 0,
+            source: "tracked".to_string(),
+            pomodoro: None,
+            parsed: None,
+            cwd: None,
+            repo: None,
+            branch: None,
+            project: None,
+            url: None,
+            domain: None,
+            site_category: None,
+            score: None,
+            redacted: None,
+            note: None,
+            importer: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let mut buf = Vec::new();
+        let mut csv = CsvWriter::new(&mut buf).unwrap();
+        csv.push(record(1, "VS Code", "main.rs, \"draft\"", 0, 60)).unwrap();
+        assert_eq!(csv.finish().unwrap(), 1);
+
+        let text = String::from_utf8(buf).unwrap();
+        let row = text.lines().nth(1).unwrap();
+        assert!(row.starts_with("1,VS Code,Development,\"main.rs, \"\"draft\"\"\","));
+    }
+
+    #[test]
+    fn test_jsonl_one_object_per_line() {
+        let mut buf = Vec::new();
+        let mut jsonl = JsonlWriter { out: &mut buf, written: 0 };
+        jsonl.push(record(1, "VS Code", "a", 0, 60)).unwrap();
+        jsonl.push(record(2, "Ghostty", "b", 1, 60)).unwrap();
+        jsonl.finish().unwrap();
+
+        let text = String::from_utf8(buf).unwrap();
+        let rows: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["app_name"], "Ghostty");
+    }
+
+    #[test]
+    fn test_ics_merges_consecutive_sessions() {
+        let mut buf = Vec::new();
+        let mut ics = IcsWriter::new(&mut buf).unwrap();
+        // Two VS Code sessions one minute apart merge; Ghostty and the later VS Code session don't
+        ics.push(record(1, "VS Code", "main.rs", 0, 600)).unwrap();
+        ics.push(record(2, "VS Code", "lib.rs", 11, 300)).unwrap();
+        ics.push(record(3, "Ghostty", "~/flowmode", 16, 120)).unwrap();
+        ics.push(record(4, "VS Code", "main.rs", 60, 60)).unwrap();
+        assert_eq!(ics.finish().unwrap(), 3);
+
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(text.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(text.matches("BEGIN:VEVENT").count(), 3);
+        // First event spans both VS Code sessions: 09:00 to 09:16 local
+        let start = ics_time(&Local.with_ymd_and_hms(2024, 3, 5, 9, 0, 0).unwrap());
+        let end = ics_time(&Local.with_ymd_and_hms(2024, 3, 5, 9, 16, 0).unwrap());
+        assert!(text.contains(&format!("DTSTART:{}\r\nDTEND:{}", start, end)));
+        assert!(text.contains("main.rs (10m)\\nlib.rs (5m)"));
+    }
+
+    #[test]
+    fn test_ics_description_skips_untitled_sessions() {
+        let mut buf = Vec::new();
+        let mut ics = IcsWriter::new(&mut buf).unwrap();
+        // The untitled session is the longest, but must not push a titled one out of the list
+        ics.push(record(1, "VS Code", "", 0, 600)).unwrap();
+        for (i, title) in ["a.rs", "b.rs", "c.rs", "d.rs", "e.rs"].iter().enumerate() {
+            ics.push(record(i as i64 + 2, "VS Code", title, 10 + i as i64, 60)).unwrap();
+        }
+        assert_eq!(ics.finish().unwrap(), 1);
+
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.contains("e.rs (1m)"));
+    }
+
+    #[test]
+    fn test_ics_escaping_and_folding() {
+        assert_eq!(ics_text("a;b,c\\d\ne"), "a\\;b\\,c\\\\d\\ne");
+
+        let long = format!("DESCRIPTION:{}", "é".repeat(60));
+        let folded = fold_ics_line(&long);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", long));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("ICS".parse::<ExportFormat>().unwrap(), ExportFormat::Ics);
+        assert_eq!("ndjson".parse::<ExportFormat>().unwrap(), ExportFormat::Jsonl);
+        assert!("xlsx".parse::<ExportFormat>().is_err());
+    }
+}
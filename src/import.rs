@@ -0,0 +1,366 @@
+//! Import - Bring in history from other trackers
+//!
+//! Supported sources:
+//! - ActivityWatch: `aw-watcher-window` bucket exports (JSON)
+//! - Timewarrior: the `*.data` files in its data directory
+//!
+//! Every event is mapped through the configured `TrackedApp` rules, exactly
+//! like a live window would be. Events that match no app are skipped, and
+//! private or sensitive windows become "Private" time without a title.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::privacy::{self, SensitiveWindows};
+use crate::storage::NewSession;
+use crate::tracker::WindowInfo;
+
+/// A window or time interval read from another tracker, before app matching
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignEvent {
+    /// Window class (ActivityWatch `app`) or tag to match `TrackedApp` rules against
+    pub classes: Vec<String>,
+    pub title: String,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+}
+
+/// Events mapped to tracked apps, plus how many matched nothing
+#[derive(Debug, Default)]
+pub struct MappedEvents {
+    pub sessions: Vec<NewSession>,
+    pub unmatched: usize,
+}
+
+/// Map foreign events onto tracked apps using the current config
+pub fn map_events(config: &Config, events: Vec<ForeignEvent>) -> MappedEvents {
+    let mut mapped = MappedEvents::default();
+    let sensitive = SensitiveWindows::from_config(&config.privacy);
+    let private_app = privacy::private_app();
+
+    for mut event in events {
+        // Private browsing and sensitive windows count as "Private", without a title
+        let private = is_sensitive(&sensitive, &event);
+        if private {
+            event.title.clear();
+        }
+
+        let app = if private {
+            Some(&private_app)
+        } else {
+            event
+                .classes
+                .iter()
+                .find_map(|class| config.match_window(class, &event.title))
+                .or_else(|| config.match_window("", &event.title))
+        };
+
+        match app {
+            Some(app) => mapped.sessions.push(NewSession {
+                app_name: app.name.clone(),
+                category: app.category.clone(),
+                window_title: event.title,
+                started_at: event.started_at,
+                ended_at: event.ended_at,
+            }),
+            None => mapped.unmatched += 1,
+        }
+    }
+
+    mapped
+}
+
+/// Whether the tracking loop would have treated the window of `event` as
+/// private or sensitive, trying each of its classes
+fn is_sensitive(sensitive: &SensitiveWindows, event: &ForeignEvent) -> bool {
+    let window = |class: &str| WindowInfo {
+        window_id: String::new(),
+        window_class: class.to_string(),
+        window_class_name: class.to_string(),
+        window_title: event.title.clone(),
+        pid: None,
+    };
+    std::iter::once("")
+        .chain(event.classes.iter().map(String::as_str))
+        .any(|class| sensitive.is_sensitive(&window(class), None))
+}
+
+/// Read an ActivityWatch export file
+pub fn read_activitywatch(path: &Path) -> Result<Vec<ForeignEvent>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_activitywatch(&content)
+}
+
+/// Parse ActivityWatch JSON.
+///
+/// Accepts a full export (`{"buckets": {...}}`), a map of buckets, a single
+/// bucket (`{"id": ..., "events": [...]}`) or a bare array of events. Only
+/// window-watcher buckets are read when bucket metadata is present.
+pub fn parse_activitywatch(json: &str) -> Result<Vec<ForeignEvent>> {
+    let root: Value = serde_json::from_str(json).context("Invalid ActivityWatch JSON")?;
+
+    let buckets: Vec<&Value> = if let Some(events) = root.as_array() {
+        return Ok(events.iter().filter_map(aw_event).collect());
+    } else if root.get("events").is_some() {
+        vec![&root]
+    } else if let Some(map) = root.get("buckets").and_then(Value::as_object) {
+        map.values().collect()
+    } else if let Some(map) = root.as_object() {
+        map.values().collect()
+    } else {
+        return Err(anyhow!("Unrecognised ActivityWatch export"));
+    };
+
+    let mut events = Vec::new();
+    for bucket in buckets {
+        if !is_window_bucket(bucket) {
+            continue;
+        }
+        if let Some(list) = bucket.get("events").and_then(Value::as_array) {
+            events.extend(list.iter().filter_map(aw_event));
+        }
+    }
+    events.sort_by_key(|e| e.started_at);
+    Ok(events)
+}
+
+fn is_window_bucket(bucket: &Value) -> bool {
+    let id = bucket.get("id").and_then(Value::as_str).unwrap_or("");
+    let kind = bucket.get("type").and_then(Value::as_str).unwrap_or("");
+    (id.is_empty() && kind.is_empty()) || id.starts_with("aw-watcher-window") || kind == "currentwindow"
+}
+
+fn aw_event(event: &Value) -> Option<ForeignEvent> {
+    let timestamp = event.get("timestamp")?.as_str()?;
+    let started_at = DateTime::parse_from_rfc3339(timestamp).ok()?.with_timezone(&Local);
+    let duration_ms = (event.get("duration")?.as_f64()? * 1000.0) as i64;
+    if duration_ms < 1000 {
+        return None;
+    }
+
+    let data = event.get("data")?;
+    let app = data.get("app").and_then(Value::as_str).unwrap_or("");
+    let title = data.get("title").and_then(Value::as_str).unwrap_or("");
+
+    Some(ForeignEvent {
+        classes: vec![app.to_string()],
+        title: title.to_string(),
+        started_at,
+        ended_at: started_at + Duration::milliseconds(duration_ms),
+    })
+}
+
+/// Read every `*.data` file in a Timewarrior directory (or its `data/` subdirectory)
+pub fn read_timewarrior(dir: &Path) -> Result<Vec<ForeignEvent>> {
+    let data_dir = if dir.join("data").is_dir() { dir.join("data") } else { dir.to_path_buf() };
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&data_dir)
+        .with_context(|| format!("Failed to read {}", data_dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "data"))
+        .collect();
+    files.sort();
+
+    let mut events = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+        events.extend(content.lines().filter_map(parse_timewarrior_line));
+    }
+    Ok(events)
+}
+
+/// Parse one Timewarrior interval line:
+/// `inc 20240305T090000Z - 20240305T100000Z # tag "tag two" # "annotation"`.
+/// Open intervals (still running) are skipped.
+pub fn parse_timewarrior_line(line: &str) -> Option<ForeignEvent> {
+    let rest = line.trim().strip_prefix("inc ")?;
+    let (times, meta) = match rest.split_once(" # ") {
+        Some((times, meta)) => (times, meta),
+        None => (rest, ""),
+    };
+
+    let (start, end) = times.split_once(" - ")?;
+    let started_at = parse_timew_time(start.trim())?;
+    let ended_at = parse_timew_time(end.trim())?;
+
+    let (tag_part, annotation) = match meta.split_once(" # ") {
+        Some((tags, annotation)) => (tags, Some(unquote(annotation.trim()))),
+        None => (meta, None),
+    };
+    let tags = split_tags(tag_part);
+
+    let title = annotation
+        .filter(|a| !a.is_empty())
+        .unwrap_or_else(|| tags.join(", "));
+
+    Some(ForeignEvent {
+        classes: tags,
+        title,
+        started_at,
+        ended_at,
+    })
+}
+
+fn parse_timew_time(s: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ").ok()?;
+    Some(Utc.from_utc_datetime(&naive).with_timezone(&Local))
+}
+
+/// Split a Timewarrior tag list, honouring double quotes and `\"` escapes
+fn split_tags(s: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '"' => in_quotes = !in_quotes,
+            ' ' if !in_quotes => {
+                if !current.is_empty() {
+                    tags.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tags.push(current);
+    }
+    tags
+}
+
+fn unquote(s: &str) -> String {
+    split_tags(s).join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MatchType, TrackedApp};
+
+    const AW_EXPORT: &str = r#"{
+        "buckets": {
+            "aw-watcher-window_laptop": {
+                "id": "aw-watcher-window_laptop",
+                "type": "currentwindow",
+                "events": [
+                    {"timestamp": "2024-03-05T09:00:05.120000+00:00", "duration": 312.5,
+                     "data": {"app": "code", "title": "main.rs - flowmode - Visual Studio Code"}},
+                    {"timestamp": "2024-03-05T09:05:20+00:00", "duration": 0.4,
+                     "data": {"app": "code", "title": "flicker"}},
+                    {"timestamp": "2024-03-05T08:00:00+00:00", "duration": 60,
+                     "data": {"app": "Spotify", "title": "Spotify"}}
+                ]
+            },
+            "aw-watcher-afk_laptop": {
+                "id": "aw-watcher-afk_laptop",
+                "type": "afkstatus",
+                "events": [
+                    {"timestamp": "2024-03-05T09:00:00+00:00", "duration": 900, "data": {"status": "not-afk"}}
+                ]
+            }
+        }
+    }"#;
+
+    fn test_config() -> Config {
+        Config {
+            apps: vec![
+                TrackedApp {
+                    name: "VS Code".into(),
+                    match_type: MatchType::WindowClass,
+                    pattern: "code".into(),
+                    category: "Development".into(),
+                },
+                TrackedApp {
+                    name: "Teams".into(),
+                    match_type: MatchType::WindowTitle,
+                    pattern: "Teams".into(),
+                    category: "Communication".into(),
+                },
+            ],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_activitywatch_export() {
+        let events = parse_activitywatch(AW_EXPORT).unwrap();
+        // The sub-second event and the AFK bucket are ignored; events are sorted by start
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].classes, vec!["Spotify"]);
+        assert_eq!(events[1].title, "main.rs - flowmode - Visual Studio Code");
+        assert_eq!((events[1].ended_at - events[1].started_at).num_milliseconds(), 312_500);
+    }
+
+    #[test]
+    fn test_parse_activitywatch_bare_events() {
+        let json = r#"[{"timestamp": "2024-03-05T09:00:00Z", "duration": 10, "data": {"app": "code", "title": "x"}}]"#;
+        assert_eq!(parse_activitywatch(json).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_timewarrior_line() {
+        let event = parse_timewarrior_line(
+            r#"inc 20240305T090000Z - 20240305T103000Z # code "client \"A\"" # "Sprint planning""#,
+        )
+        .unwrap();
+        assert_eq!(event.classes, vec!["code", "client \"A\""]);
+        assert_eq!(event.title, "Sprint planning");
+        assert_eq!((event.ended_at - event.started_at).num_minutes(), 90);
+
+        let untagged = parse_timewarrior_line("inc 20240305T090000Z - 20240305T091000Z").unwrap();
+        assert!(untagged.classes.is_empty());
+
+        // Still-running intervals have no end
+        assert!(parse_timewarrior_line("inc 20240305T090000Z # code").is_none());
+        assert!(parse_timewarrior_line("garbage").is_none());
+    }
+
+    #[test]
+    fn test_map_events_through_app_rules() {
+        let mut events = parse_activitywatch(AW_EXPORT).unwrap();
+        events.push(parse_timewarrior_line("inc 20240305T110000Z - 20240305T113000Z # meeting # \"Microsoft Teams call\"").unwrap());
+
+        let mapped = map_events(&test_config(), events);
+        assert_eq!(mapped.unmatched, 1); // Spotify
+        let apps: Vec<&str> = mapped.sessions.iter().map(|s| s.app_name.as_str()).collect();
+        assert_eq!(apps, vec!["VS Code", "Teams"]);
+        assert_eq!(mapped.sessions[1].category, "Communication");
+    }
+
+    #[test]
+    fn test_map_events_keeps_private_windows_private() {
+        let json = r#"[
+            {"timestamp": "2024-03-05T09:00:00Z", "duration": 600,
+             "data": {"app": "firefox", "title": "Clinic results — Mozilla Firefox Private Browsing"}},
+            {"timestamp": "2024-03-05T09:10:00Z", "duration": 60,
+             "data": {"app": "KeePassXC", "title": "Passwords.kdbx - KeePassXC"}},
+            {"timestamp": "2024-03-05T09:20:00Z", "duration": 60,
+             "data": {"app": "code", "title": "main.rs - flowmode - Visual Studio Code"}}
+        ]"#;
+
+        // Private windows are kept as time, even of apps with no rule, but lose their title
+        let mapped = map_events(&test_config(), parse_activitywatch(json).unwrap());
+        assert_eq!(mapped.unmatched, 0);
+        let sessions: Vec<(&str, &str)> =
+            mapped.sessions.iter().map(|s| (s.app_name.as_str(), s.window_title.as_str())).collect();
+        assert_eq!(
+            sessions,
+            vec![
+                ("Private", ""),
+                ("Private", ""),
+                ("VS Code", "main.rs - flowmode - Visual Studio Code"),
+            ]
+        );
+    }
+}
@@ -1,14 +1,26 @@
 use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate};
 use clap::{Parser, Subcommand};
+use std::io::Write;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, debug, Level};
 use tracing_subscriber::FmtSubscriber;
 
+mod browser;
+mod client;
 mod config;
+mod encryption;
+mod entries;
+mod export;
+mod import;
+mod notify;
 mod storage;
 mod pomodoro;
+mod privacy;
+mod project;
+mod sites;
 mod title_parser;
 mod tracker;
 mod tray;
@@ -16,7 +28,12 @@ mod tui;
 mod web;
 
 use config::Config;
-use storage::Storage;
+use export::ExportFormat;
+use entries::{EntryDraft, Entries};
+use storage::{
+    ActivityRecord, Breakdown, ContextFilter, ForgetSelection, GroupBy, Importer, SessionEdit, SessionFilter,
+    SessionScope, SessionSelection, Source, Storage, TimeRange,
+};
 use tray::{start_tray_service, TrayCommand, TrayHandles, format_duration};
 
 const WEB_PORT: u16 = 5555;
@@ -42,8 +59,24 @@ enum Commands {
     /// Start FlowMode daemon (background tracking + web dashboard)
     Start,
 
-    /// Show today's activity stats (summary)
-    Stats,
+    /// Show activity stats (summary), today by default
+    Stats {
+        /// First day to include (YYYY-MM-DD, "today" or "yesterday")
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+
+        /// Last day to include (defaults to today, or to --from if that is later)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+
+        /// Also break totals down by day, week or month
+        #[arg(long)]
+        group_by: Option<GroupBy>,
+
+        /// Totals per app, category or project
+        #[arg(long, default_value = "app")]
+        by: Breakdown,
+    },
 
     /// Show detailed stats with window titles (tabs, folders)
     Detailed,
@@ -52,7 +85,11 @@ enum Commands {
     Dashboard,
 
     /// Open web dashboard in browser
-    Web,
+    Web {
+        /// Print the API token for destructive endpoints (e.g. /api/forget) instead
+        #[arg(long)]
+        token: bool,
+    },
 
     /// List tracked apps
     Apps,
@@ -63,6 +100,116 @@ enum Commands {
     /// Reset today's data (start fresh)
     Reset,
 
+    /// Delete sessions by app, window title or date range (asks after a preview)
+    Forget {
+        /// Only sessions of this app
+        #[arg(long)]
+        app: Option<String>,
+        /// Only sessions whose window title matches this regex
+        #[arg(long)]
+        title_regex: Option<String>,
+        /// First day (YYYY-MM-DD, today, yesterday)
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive); defaults to `from` or today
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Session ids (see `flowmode export`)
+        #[arg(long = "id")]
+        ids: Vec<i64>,
+        /// Only show what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask before deleting
+        #[arg(long, short)]
+        yes: bool,
+        /// Overwrite the deleted data and compact the database file
+        #[arg(long)]
+        vacuum: bool,
+    },
+
+    /// Add, correct, split and merge sessions by hand
+    Log {
+        #[command(subcommand)]
+        command: LogCommand,
+    },
+
+    /// Export sessions as CSV, JSON Lines or iCalendar (stdout by default)
+    Export {
+        /// Output format: csv, jsonl or ics
+        #[arg(long, default_value = "csv")]
+        format: ExportFormat,
+
+        /// First day to include (YYYY-MM-DD, "today" or "yesterday")
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+
+        /// Last day to include (defaults to today, or to --from if that is later)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+
+        /// Only export sessions of this app
+        #[arg(long)]
+        app: Option<String>,
+
+        /// Only export sessions in this category
+        #[arg(long)]
+        category: Option<String>,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Import history from another tracker
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+
+    /// Control the running daemon's pomodoro timer
+    Pomodoro {
+        #[command(subcommand)]
+        command: PomodoroCliCommand,
+    },
+
+    /// Window title parsing
+    Titles {
+        #[command(subcommand)]
+        command: TitlesCommand,
+    },
+
+    /// Projects: apply [[projects]] rules and assign sessions by hand
+    Projects {
+        #[command(subcommand)]
+        command: ProjectsCommand,
+    },
+
+    /// Browser sites: apply [[sites]] categories and productivity scores
+    Sites {
+        #[command(subcommand)]
+        command: SitesCommand,
+    },
+
+    /// Native messaging host for the browser extension (started by the browser)
+    BrowserHost {
+        /// Register the host with a browser (chrome, chromium, brave, firefox) instead
+        #[arg(long, requires = "extension_id")]
+        install: Option<browser::BrowserKind>,
+        /// Extension allowed to start the host (Chrome ID or Firefox add-on ID)
+        #[arg(long)]
+        extension_id: Option<String>,
+        /// Caller origin and manifest path passed by the browser (ignored)
+        #[arg(hide = true)]
+        caller: Vec<String>,
+    },
+
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+
     /// Generate default config
     Init,
 
@@ -73,15 +220,284 @@ enum Commands {
     Version,
 }
 
+#[derive(Subcommand)]
+enum LogCommand {
+    /// List sessions with their ids, today by default
+    List {
+        /// First day (YYYY-MM-DD, today, yesterday)
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive); defaults to `from` or today
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Only sessions of this app
+        #[arg(long)]
+        app: Option<String>,
+    },
+    /// Add time spent away from the computer, e.g. a meeting or a phone call
+    Add {
+        /// What you did, e.g. "Meeting" (a configured app's category is used)
+        app: String,
+        /// Start: HH:MM (today), YYYY-MM-DD HH:MM or yesterday HH:MM
+        #[arg(long, value_parser = parse_time_arg)]
+        start: DateTime<Local>,
+        /// End, in the same formats as --start
+        #[arg(long, value_parser = parse_time_arg, required_unless_present = "duration", conflicts_with = "duration")]
+        end: Option<DateTime<Local>>,
+        /// Length instead of --end, e.g. 45m or 1h30m
+        #[arg(long, value_parser = parse_duration_arg)]
+        duration: Option<chrono::Duration>,
+        /// Category (defaults to the app's, or "Manual")
+        #[arg(long)]
+        category: Option<String>,
+        /// Title shown in stats (defaults to the note)
+        #[arg(long)]
+        title: Option<String>,
+        /// Project to book the time on
+        #[arg(long)]
+        project: Option<String>,
+        /// What it was about
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Change a session's times, app, category, title, project or note
+    Edit {
+        /// Session id (see `flowmode log list`)
+        id: i64,
+        /// New start: HH:MM (today), YYYY-MM-DD HH:MM or yesterday HH:MM
+        #[arg(long, value_parser = parse_time_arg)]
+        start: Option<DateTime<Local>>,
+        /// New end, in the same formats as --start
+        #[arg(long, value_parser = parse_time_arg)]
+        end: Option<DateTime<Local>>,
+        /// Move the session to another app
+        #[arg(long)]
+        app: Option<String>,
+        /// New category (defaults to the new app's, if it is configured)
+        #[arg(long)]
+        category: Option<String>,
+        /// New window title
+        #[arg(long)]
+        title: Option<String>,
+        /// New project ("Unassigned" to clear)
+        #[arg(long)]
+        project: Option<String>,
+        /// New note ("" to remove it)
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Split a session in two at a point in time
+    Split {
+        /// Session id (see `flowmode log list`)
+        id: i64,
+        /// Where to split: HH:MM (today), YYYY-MM-DD HH:MM or yesterday HH:MM
+        #[arg(long, value_parser = parse_time_arg)]
+        at: DateTime<Local>,
+    },
+    /// Merge sessions into the earliest one, keeping its app, title and project
+    Merge {
+        /// Session ids (see `flowmode log list`)
+        #[arg(required = true, num_args = 2..)]
+        ids: Vec<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommand {
+    /// Import an ActivityWatch export (JSON from the web UI or the buckets API)
+    Activitywatch {
+        /// Path to the exported JSON file
+        file: std::path::PathBuf,
+    },
+    /// Import intervals from a Timewarrior data directory
+    Timewarrior {
+        /// Timewarrior directory (e.g. ~/.timewarrior) or its data/ subdirectory
+        dir: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum PomodoroCliCommand {
+    /// Start a work session, optionally labelled with a task or project
+    Start {
+        /// What the session is for, e.g. "Quarterly report"
+        label: Option<String>,
+    },
+    /// Pause the running session
+    Pause,
+    /// Resume a paused session
+    Resume,
+    /// End the current session and move on to the next one
+    Skip,
+    /// Stop the timer
+    Reset,
+    /// Show the timer, e.g. for a status bar
+    Status {
+        /// Print the daemon's status as JSON; with --format, escape the values
+        /// for a JSON template
+        #[arg(long)]
+        json: bool,
+        /// Template such as "{icon} {remaining} {label}"; placeholders: icon, state,
+        /// phase, remaining, minutes, remaining_secs, label, completed, target, mode
+        #[arg(long)]
+        format: Option<String>,
+        /// Print a new line every second until interrupted (empty while the daemon is down)
+        #[arg(long)]
+        follow: bool,
+    },
+    /// Log an interruption of the running work session
+    Interrupt {
+        /// internal (your own distraction) or external (someone else)
+        kind: pomodoro::InterruptionKind,
+        /// What interrupted you
+        note: Vec<String>,
+    },
+    /// Show apps, window titles and interruptions of a work session
+    Summary {
+        /// Session id (defaults to the latest work session)
+        #[arg(long)]
+        id: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TitlesCommand {
+    /// Show how a window title is parsed, and by which rule
+    Test {
+        /// The window title
+        title: String,
+        /// App the title belongs to (defaults to the app whose title pattern matches)
+        #[arg(long)]
+        app: Option<String>,
+        /// Category to parse as (defaults to the app's category)
+        #[arg(long)]
+        category: Option<String>,
+    },
+    /// Parse stored window titles again, e.g. after changing [[title_rules]]
+    Reparse {
+        /// First day to re-parse (YYYY-MM-DD, today, yesterday); all history by default
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day to re-parse (inclusive)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Only parse sessions that were never parsed
+        #[arg(long)]
+        missing: bool,
+    },
+    /// Apply the [privacy] title policies and scrubbers to stored sessions
+    Redact {
+        /// First day to redact (YYYY-MM-DD, today, yesterday); all history by default
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day to redact (inclusive)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Count the sessions that would change without changing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show tracked time per parsed context (chat partner, project folder, website...)
+    Contexts {
+        /// First day (YYYY-MM-DD, today, yesterday); defaults to today
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive); defaults to `from` or today
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Only this context type, e.g. chat, call, folder, website
+        #[arg(long = "type")]
+        context_type: Option<String>,
+        /// Only sessions of this app
+        #[arg(long)]
+        app: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SitesCommand {
+    /// Run the [[sites]] rules over stored browser sessions, e.g. after changing them
+    Apply {
+        /// First day (YYYY-MM-DD, today, yesterday); all history by default
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectsCommand {
+    /// Run the [[projects]] rules over stored sessions (unassigned ones by default)
+    Apply {
+        /// First day (YYYY-MM-DD, today, yesterday); all history by default
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Also re-check sessions that already have a project (manual assignments are kept)
+        #[arg(long)]
+        all: bool,
+    },
+    /// Assign sessions to a project by hand ("Unassigned" to clear)
+    Assign {
+        /// Project name
+        project: String,
+        /// Session ids (see `flowmode export`)
+        #[arg(long = "id")]
+        ids: Vec<i64>,
+        /// First day (YYYY-MM-DD, today, yesterday)
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive); defaults to `from` or today
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<NaiveDate>,
+        /// Only sessions of this app
+        #[arg(long)]
+        app: Option<String>,
+        /// Only sessions with this context, e.g. a chat partner or repo
+        #[arg(long)]
+        context: Option<String>,
+        /// Only sessions whose window title contains this
+        #[arg(long)]
+        title: Option<String>,
+        /// Only sessions without a project
+        #[arg(long)]
+        unassigned: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Roll sessions before a date up into daily totals, dropping window titles
+    Prune {
+        /// First day to keep raw sessions for (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date_arg)]
+        before: NaiveDate,
+    },
+    /// Encrypt the database with a passphrase (asked, or FLOWMODE_DB_PASSPHRASE)
+    Encrypt {
+        /// Use a random key kept in the Secret Service (GNOME Keyring, KWallet) instead
+        #[arg(long)]
+        secret_service: bool,
+    },
+    /// Decrypt the database back to a plain SQLite file
+    Decrypt,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Setup logging
+    // Setup logging, on stderr: stdout carries exports, `--json` output and the
+    // browser host's native messages
     let level = if cli.verbose { Level::DEBUG } else { Level::INFO };
-    let _subscriber = FmtSubscriber::builder()
+    FmtSubscriber::builder()
         .with_max_level(level)
         .with_target(false)
+        .with_writer(std::io::stderr)
         .compact()
         .init();
 
@@ -92,8 +508,8 @@ async fn main() -> Result<()> {
         Some(Commands::Start) | None => {
             start_daemon().await
         }
-        Some(Commands::Stats) => {
-            show_stats()
+        Some(Commands::Stats { from, to, group_by, by }) => {
+            show_stats(from, to, group_by, by)
         }
         Some(Commands::Detailed) => {
             show_detailed_stats()
@@ -101,8 +517,8 @@ async fn main() -> Result<()> {
         Some(Commands::Dashboard) => {
             show_dashboard()
         }
-        Some(Commands::Web) => {
-            open_web_dashboard()
+        Some(Commands::Web { token }) => {
+            open_web_dashboard(token)
         }
         Some(Commands::Apps) => {
             list_apps()
@@ -113,6 +529,95 @@ async fn main() -> Result<()> {
         Some(Commands::Reset) => {
             reset_today()
         }
+        Some(Commands::Forget { app, title_regex, from, to, ids, dry_run, yes, vacuum }) => {
+            let range = selection_range(from, to)?;
+            let title_regex = title_regex
+                .map(|r| regex::Regex::new(&r).map_err(|e| anyhow::anyhow!("Invalid --title-regex: {}", e)))
+                .transpose()?;
+            forget_sessions(&ForgetSelection { ids, range, app, title_regex }, dry_run, yes, vacuum)
+        }
+        Some(Commands::Log { command }) => {
+            match command {
+                LogCommand::List { from, to, app } => list_sessions(from, to, app),
+                LogCommand::Add { app, start, end, duration, category, title, project, note } => {
+                    let ended_at = match (end, duration) {
+                        (Some(end), _) => end,
+                        (None, Some(duration)) => start + duration,
+                        (None, None) => anyhow::bail!("Pass --end or --duration"),
+                    };
+                    add_entry(EntryDraft { app_name: app, category, window_title: title, started_at: start, ended_at, project, note })
+                }
+                LogCommand::Edit { id, start, end, app, category, title, project, note } => {
+                    let edit = SessionEdit {
+                        started_at: start,
+                        ended_at: end,
+                        app_name: app,
+                        category,
+                        window_title: title,
+                        parsed: None,
+                        project: project.as_deref().map(entries::project_name),
+                        note: note.map(|n| Some(n).filter(|n| !n.trim().is_empty())),
+                    };
+                    edit_entry(id, edit)
+                }
+                LogCommand::Split { id, at } => split_entry(id, at),
+                LogCommand::Merge { ids } => merge_entries(&ids),
+            }
+        }
+        Some(Commands::Export { format, from, to, app, category, output }) => {
+            export_sessions(format, from, to, SessionFilter { app, category }, output)
+        }
+        Some(Commands::Import { command }) => {
+            match command {
+                ImportCommand::Activitywatch { file } => {
+                    import_history(import::read_activitywatch(&file)?, Importer::ActivityWatch)
+                }
+                ImportCommand::Timewarrior { dir } => {
+                    import_history(import::read_timewarrior(&dir)?, Importer::Timewarrior)
+                }
+            }
+        }
+        Some(Commands::Pomodoro { command }) => {
+            pomodoro_command(command)
+        }
+        Some(Commands::Titles { command }) => {
+            match command {
+                TitlesCommand::Test { title, app, category } => test_title(&title, app, category),
+                TitlesCommand::Reparse { from, to, missing } => reparse_titles(from, to, missing),
+                TitlesCommand::Redact { from, to, dry_run } => redact_titles(from, to, dry_run),
+                TitlesCommand::Contexts { from, to, context_type, app } => {
+                    show_contexts(from, to, ContextFilter { context_type, app })
+                }
+            }
+        }
+        Some(Commands::Projects { command }) => {
+            match command {
+                ProjectsCommand::Apply { from, to, all } => apply_projects(from, to, all),
+                ProjectsCommand::Assign { project, ids, from, to, app, context, title, unassigned } => {
+                    let range = selection_range(from, to)?;
+                    let selection = SessionSelection { ids, range, app, context, title, unassigned_only: unassigned };
+                    assign_project(&project, &selection)
+                }
+            }
+        }
+        Some(Commands::Sites { command }) => {
+            match command {
+                SitesCommand::Apply { from, to } => apply_sites(from, to),
+            }
+        }
+        Some(Commands::BrowserHost { install, extension_id, caller: _ }) => {
+            match (install, extension_id) {
+                (Some(browser), Some(extension_id)) => install_browser_host(browser, &extension_id),
+                _ => browser::run_host(&client::DaemonClient::new(WEB_PORT)),
+            }
+        }
+        Some(Commands::Db { command }) => {
+            match command {
+                DbCommand::Prune { before } => prune_database(before),
+                DbCommand::Encrypt { secret_service } => encrypt_database(secret_service),
+                DbCommand::Decrypt => decrypt_database(),
+            }
+        }
         Some(Commands::Init) => {
             init_config()
         }
@@ -129,8 +634,9 @@ async fn main() -> Result<()> {
 async fn start_daemon() -> Result<()> {
     info!("Starting FlowMode v{}...", env!("CARGO_PKG_VERSION"));
 
-    // Load config
-    let config = Config::load().unwrap_or_default();
+    // Load config, and follow edits to it
+    let mut config = Config::load().unwrap_or_default();
+    let mut reloads = Config::watch(config.clone());
     info!("Tracking {} apps", config.apps.len());
 
     // Open database
@@ -139,10 +645,63 @@ async fn start_daemon() -> Result<()> {
     // Close any orphaned sessions from previous runs
     storage.close_open_sessions()?;
 
+    // Parse titles of rows stored before parsing on insert (or imported)
+    let mut title_parser = title_parser::TitleParser::from_config(&config);
+    match storage.reparse_titles(SessionScope::All, true, |app, category, title| title_parser.parse(app, category, title)) {
+        Ok(0) => {}
+        Ok(n) => info!("Parsed window titles of {} stored sessions", n),
+        Err(e) => tracing::warn!("Failed to parse stored window titles: {}", e),
+    }
+    let proc_fs = project::ProcFs::new();
+    let mut projects = project::ProjectMatcher::from_config(&config);
+    let mut sites = sites::SiteMatcher::from_config(&config);
+    let title_hash_salt = storage.title_hash_salt()?;
+    let mut redactor = privacy::Redactor::from_config(&config.privacy, &title_hash_salt);
+    let mut sensitive = privacy::SensitiveWindows::from_config(&config.privacy);
+
+    // Roll up raw sessions older than the retention window
+    if config.storage.retention_days > 0 {
+        let retention_days = config.storage.retention_days;
+        let every = std::time::Duration::from_secs(config.storage.compact_interval_hours.max(1) * 3600);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                if let Err(e) = compact_old_sessions(retention_days) {
+                    tracing::error!("Compaction failed: {}", e);
+                }
+            }
+        });
+        info!("Keeping raw sessions for {} days", retention_days);
+    }
+
+    // Desktop notifications (pomodoro transitions, reminders, long sessions)
+    let notifications = if config.notifications.enabled {
+        match notify::Notifier::start(None, config.notifications.sound.clone()) {
+            Ok(notifications) => Some(notifications),
+            Err(e) => {
+                tracing::warn!("Desktop notifications unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let notifier = notifications.as_ref().map(|(notifier, _)| notifier.clone());
+
+    // One pomodoro timer, driven by the web API, the tray and notification buttons
+    let pomodoro = pomodoro::create_shared_pomodoro();
+    pomodoro::start(pomodoro.clone(), Config::db_path(), notifications, reloads.clone());
+
     // Start web server in background
     let db_path = Config::db_path();
+    let web_pomodoro = pomodoro.clone();
+    let browser_tab = browser::SharedActiveTab::default();
+    let web_browser_tab = browser_tab.clone();
+    let api_token = storage.api_token()?;
+    let web_api_token = api_token.clone();
     tokio::spawn(async move {
-        if let Err(e) = web::start_web_server(db_path, WEB_PORT).await {
+        if let Err(e) = web::start_web_server(db_path, WEB_PORT, web_pomodoro, web_browser_tab, web_api_token).await {
             tracing::error!("Web server error: {}", e);
         }
     });
@@ -150,9 +709,19 @@ async fn start_daemon() -> Result<()> {
     info!("Web dashboard at http://localhost:{}", WEB_PORT);
 
     // Start system tray
-    let (tray_service, mut tray_rx, handles) = start_tray_service()?;
+    let (tray_service, mut tray_rx, handles) = start_tray_service(pomodoro.clone())?;
     let TrayHandles { tracking: is_tracking, is_idle, idle_secs: idle_secs_handle, today_time } = handles;
 
+    // Redraw the tray every second so the pomodoro countdown moves
+    let tray_handle = tray_service.handle();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            tray_handle.update(|_| {});
+        }
+    });
+
     // Spawn tray in separate thread
     std::thread::spawn(move || {
         let _ = tray_service.run();
@@ -173,8 +742,13 @@ async fn start_daemon() -> Result<()> {
     info!("FlowMode is running. Check the system tray.");
 
     // Main tracking loop
-    let poll_interval = std::time::Duration::from_secs(config.poll_interval_secs);
-    let idle_timeout = config.idle_timeout_secs;
+    let mut poll_interval = std::time::Duration::from_secs(config.poll_interval_secs);
+    let mut idle_timeout = config.idle_timeout_secs;
+    let mut long_session = std::time::Duration::from_secs(config.notifications.long_session_mins * 60);
+
+    // Time at the computer without going idle, for long-session warnings
+    let mut active_since: Option<std::time::Instant> = None;
+    let mut long_session_warned = false;
 
     loop {
         tokio::select! {
@@ -183,8 +757,7 @@ async fn start_daemon() -> Result<()> {
                 match cmd {
                     TrayCommand::OpenDashboard => {
                         info!("Opening web dashboard...");
-                        let url = format!("http://localhost:{}", WEB_PORT);
-                        if let Err(e) = open::that(&url) {
+                        if let Err(e) = open::that(dashboard_url(&api_token)) {
                             tracing::error!("Failed to open browser: {}", e);
                         }
                     }
@@ -195,9 +768,20 @@ async fn start_daemon() -> Result<()> {
                         if let Some(id) = session.take() {
                             storage.end_activity(id)?;
                         }
+                        if pomodoro.pause_for_tracking() {
+                            save_pomodoro(&pomodoro);
+                        }
                     }
                     TrayCommand::Resume => {
                         info!("Tracking resumed");
+                        if pomodoro.resume_auto() {
+                            save_pomodoro(&pomodoro);
+                        }
+                    }
+                    TrayCommand::Pomodoro(command) => {
+                        if let Err(e) = pomodoro::run_command(&pomodoro, &Config::db_path(), command) {
+                            tracing::warn!("Failed to save pomodoro session: {}", e);
+                        }
                     }
                     TrayCommand::Quit => {
                         info!("Shutting down...");
@@ -213,14 +797,33 @@ async fn start_daemon() -> Result<()> {
 
             // Tracking tick
             _ = tokio::time::sleep(poll_interval) => {
+                // Apply config.toml edits: app rules, parsers, privacy and timings
+                if reloads.has_changed().unwrap_or(false) {
+                    config = reloads.borrow_and_update().clone();
+                    title_parser = title_parser::TitleParser::from_config(&config);
+                    projects = project::ProjectMatcher::from_config(&config);
+                    sites = sites::SiteMatcher::from_config(&config);
+                    redactor = privacy::Redactor::from_config(&config.privacy, &title_hash_salt);
+                    sensitive = privacy::SensitiveWindows::from_config(&config.privacy);
+                    poll_interval = std::time::Duration::from_secs(config.poll_interval_secs);
+                    idle_timeout = config.idle_timeout_secs;
+                    long_session = std::time::Duration::from_secs(config.notifications.long_session_mins * 60);
+                    info!("Tracking {} apps", config.apps.len());
+                }
+
+                // Check idle (also while paused, so a break away from the desk counts)
+                let idle_secs = tracker::get_idle_time_secs().unwrap_or(0);
+                pomodoro.note_idle(idle_secs);
+
                 if !is_tracking.load(Ordering::Relaxed) {
                     continue;
                 }
 
-                // Check idle
-                let idle_secs = tracker::get_idle_time_secs().unwrap_or(0);
                 if idle_secs > idle_timeout {
                     debug!("User idle for {}s", idle_secs);
+                    // Going idle counts as a break
+                    active_since = None;
+                    long_session_warned = false;
                     // Update tray idle status
                     is_idle.store(true, Ordering::Relaxed);
                     idle_secs_handle.store(idle_secs, Ordering::Relaxed);
@@ -229,23 +832,105 @@ async fn start_daemon() -> Result<()> {
                     if let Some(id) = session.take() {
                         storage.end_activity(id)?;
                     }
+                    if pomodoro.pause_for_idle(idle_secs) {
+                        info!("Pomodoro paused while idle");
+                        save_pomodoro(&pomodoro);
+                    }
                     continue;
                 } else {
                     // Not idle - clear idle status
                     is_idle.store(false, Ordering::Relaxed);
                     idle_secs_handle.store(0, Ordering::Relaxed);
+
+                    if pomodoro.resume_auto() {
+                        info!("Pomodoro resumed");
+                        save_pomodoro(&pomodoro);
+                    }
+
+                    let active_for = active_since.get_or_insert_with(std::time::Instant::now).elapsed();
+                    if let Some(notifier) = &notifier {
+                        if !long_session.is_zero() && active_for >= long_session && !long_session_warned {
+                            long_session_warned = true;
+                            notifier.send(notify::Notification {
+                                topic: notify::Topic::LongSession,
+                                summary: "Time for a break".to_string(),
+                                body: format!(
+                                    "You have been at it for {} without a pause.",
+                                    format_duration(active_for.as_secs() as i64)
+                                ),
+                                actions: Vec::new(),
+                                urgent: true,
+                            });
+                        }
+                    }
                 }
 
                 // Get active window
                 match tracker::get_active_window() {
-                    Ok(window) => {
+                    Ok(mut window) => {
+                        // Private browsing and sensitive windows count as "Private", without a title
+                        let private_app = sensitive
+                            .is_sensitive(&window, browser_tab.reported_for(&window.window_title).as_ref())
+                            .then(privacy::private_app);
+                        if private_app.is_some() {
+                            window.window_title.clear();
+                        }
+
                         // Check if it matches a tracked app
-                        if let Some(app) = config.match_window(&window.window_class, &window.window_title) {
+                        let app = private_app
+                            .as_ref()
+                            .or_else(|| config.match_window(&window.window_class, &window.window_title));
+                        if let Some(app) = app {
                             let mut session = current_session.write().await;
 
-                            // Check if we need to start new session
+                            // Check if we need to start new session (new app, pomodoro phase,
+                            // title context, e.g. another chat or folder, or project)
+                            let phase = pomodoro.running_kind();
+                            let workdir = window
+                                .pid
+                                .filter(|_| app.category == "Terminal")
+                                .and_then(|pid| proc_fs.working_dir(pid, &window.window_title));
+                            let tab = Some(&app.category)
+                                .filter(|c| c.as_str() == "Browser")
+                                .and_then(|_| browser_tab.for_window(&window.window_title));
+                            let origin = storage::SessionOrigin {
+                                workdir: workdir.as_ref(),
+                                url: tab.as_ref().map(|t| t.url.as_str()),
+                            };
+                            let parsed = title_parser.parse_with_origin(
+                                &app.name,
+                                &app.category,
+                                &window.window_title,
+                                &origin,
+                            );
+                            let facts = project::SessionFacts {
+                                category: &app.category,
+                                window_title: &window.window_title,
+                                parsed: Some(&parsed),
+                                repo: workdir.as_ref().and_then(|w| w.repo.as_ref()).map(|r| r.name.as_str()),
+                                url: origin.url,
+                            };
+                            let project_name = projects.match_session(&facts);
+                            let site = sites.match_session(&facts);
+                            // Only the redacted title is stored or compared
+                            let stored = redactor.redact(
+                                redactor.policy(&app.name, &app.category),
+                                &window.window_title,
+                                &parsed,
+                                origin.url,
+                            );
+                            let origin = stored.origin(origin);
                             let need_new_session = match storage.get_active_session() {
-                                Ok(Some(active)) => active.app_name != app.name,
+                                Ok(Some(active)) => {
+                                    active.app_name != app.name
+                                        || active.project.as_deref().map(str::to_lowercase)
+                                            != project_name.map(str::to_lowercase)
+                                        || active.pomodoro.as_deref() != phase.map(|k| k.as_str())
+                                        || active.parsed.as_ref().is_none_or(|p| {
+                                            p.context_type != stored.parsed.context_type
+                                                || p.context != stored.parsed.context
+                                        })
+                                }
                                 Ok(None) => true,
                                 Err(_) => true,
                             };
@@ -260,9 +945,21 @@ async fn start_daemon() -> Result<()> {
                                 let id = storage.start_activity(
                                     &app.name,
                                     &app.category,
-                                    &window.window_title
+                                    &stored.title,
+                                    phase,
+                                    &stored.parsed,
+                                    &origin,
                                 )?;
                                 *session = Some(id);
+                                if stored.policy != config::TitlePolicy::Keep {
+                                    storage.set_activity_redacted(id, stored.policy)?;
+                                }
+                                if project_name.is_some() {
+                                    storage.set_activity_project(id, project_name)?;
+                                }
+                                if site.is_some() {
+                                    storage.set_activity_site(id, site.as_ref())?;
+                                }
 
                                 info!("Tracking: {} ({})", app.name, app.category);
                             } else if let Some(id) = *session {
@@ -316,26 +1013,77 @@ async fn start_daemon() -> Result<()> {
     Ok(())
 }
 
-/// Show today's stats in CLI
-fn show_stats() -> Result<()> {
+/// Save a timer change made by the tracking loop; failures are logged, not fatal
+fn save_pomodoro(timer: &pomodoro::PomodoroTimer) {
+    if let Err(e) = pomodoro::record_transition(timer, &Config::db_path(), None) {
+        tracing::warn!("Failed to save pomodoro session: {}", e);
+    }
+}
+
+/// Parse a CLI date argument
+fn parse_date_arg(s: &str) -> Result<NaiveDate> {
+    storage::parse_date(s)
+}
+
+/// Parse a CLI time argument
+fn parse_time_arg(s: &str) -> Result<DateTime<Local>> {
+    entries::parse_time(s)
+}
+
+/// Parse a CLI duration argument
+fn parse_duration_arg(s: &str) -> Result<chrono::Duration> {
+    entries::parse_duration(s)
+}
+
+/// Resolve optional --from/--to arguments into a range (defaults to today)
+fn resolve_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<TimeRange> {
+    let today = chrono::Local::now().date_naive();
+    let from = from.unwrap_or(today);
+    let to = to.unwrap_or_else(|| from.max(today));
+    if to < from {
+        anyhow::bail!("--to ({}) is before --from ({})", to, from);
+    }
+    Ok(TimeRange::days(from, to))
+}
+
+/// Range for commands that pick sessions: none without --from/--to
+fn selection_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Option<TimeRange>> {
+    if from.is_none() && to.is_none() {
+        return Ok(None);
+    }
+    resolve_range(from, to).map(Some)
+}
+
+/// Show stats for a date range in CLI
+fn show_stats(from: Option<NaiveDate>, to: Option<NaiveDate>, group_by: Option<GroupBy>, by: Breakdown) -> Result<()> {
+    let range = resolve_range(from, to)?;
     let storage = Storage::open(&Config::db_path())?;
-    tui::print_stats(&storage)
+    tui::print_stats(&storage, &range, group_by, by)
 }
 
 /// Show live TUI dashboard
 fn show_dashboard() -> Result<()> {
     let storage = Storage::open(&Config::db_path())?;
-    tui::run_tui(&storage)
+    tui::run_tui(&storage, &client::DaemonClient::new(WEB_PORT))
 }
 
 /// Open web dashboard in browser
-fn open_web_dashboard() -> Result<()> {
-    let url = format!("http://localhost:{}", WEB_PORT);
-    println!("Opening {} in browser...", url);
-    open::that(&url)?;
+fn open_web_dashboard(print_token: bool) -> Result<()> {
+    let token = Storage::open(&Config::db_path())?.api_token()?;
+    if print_token {
+        println!("{}", token);
+        return Ok(());
+    }
+    println!("Opening http://localhost:{} in browser...", WEB_PORT);
+    open::that(dashboard_url(&token))?;
     Ok(())
 }
 
+/// Dashboard URL; the fragment hands the API token to the page without sending it to the server
+fn dashboard_url(api_token: &str) -> String {
+    format!("http://localhost:{}/#token={}", WEB_PORT, api_token)
+}
+
 /// List tracked apps
 fn list_apps() -> Result<()> {
     let config = Config::load().unwrap_or_default();
@@ -408,6 +1156,544 @@ fn reset_today() -> Result<()> {
     Ok(())
 }
 
+/// Sessions shown before forgetting them
+const FORGET_PREVIEW: usize = 20;
+
+/// Preview the sessions matching `selection`, then delete them
+fn forget_sessions(selection: &ForgetSelection, dry_run: bool, yes: bool, vacuum: bool) -> Result<()> {
+    if selection.is_empty() {
+        anyhow::bail!("Pick sessions with --app, --title-regex, --id or a date range (--from/--to)");
+    }
+    let storage = Storage::open(&Config::db_path())?;
+    let sessions = storage.get_forget_sessions(selection)?;
+    let total: i64 = sessions.iter().map(|r| r.duration_secs).sum();
+
+    for record in sessions.iter().take(FORGET_PREVIEW) {
+        print_session_row(record);
+    }
+    if sessions.len() > FORGET_PREVIEW {
+        println!("  ... and {} more", sessions.len() - FORGET_PREVIEW);
+    }
+    println!("{} sessions, {} tracked.", sessions.len(), format_duration(total));
+    if dry_run {
+        return Ok(());
+    }
+
+    if !yes {
+        if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+            anyhow::bail!("Re-run with --yes to delete these sessions");
+        }
+        print!("Forget them? This can't be undone [y/N] ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("Nothing deleted.");
+            return Ok(());
+        }
+    }
+
+    let stats = storage.forget(selection, vacuum)?;
+    println!("Forgot {} sessions ({}).", stats.sessions, format_duration(stats.secs));
+    if stats.rollup_rows > 0 {
+        println!("Removed {} rolled-up daily totals.", stats.rollup_rows);
+    }
+    if vacuum {
+        println!("Overwrote the deleted data and compacted the database.");
+    }
+    Ok(())
+}
+
+/// One session per line: id, start, length, app and title, plus where it came
+/// from and its note when there is something to say
+fn print_session_row(record: &ActivityRecord) {
+    let mut extra = String::new();
+    match &record.importer {
+        Some(importer) => extra.push_str(&format!("  [imported from {}]", importer)),
+        None if record.source != Source::Tracked.as_str() => extra.push_str(&format!("  [{}]", record.source)),
+        None => {}
+    }
+    if let Some(note) = record.note.as_deref().filter(|n| *n != record.window_title) {
+        extra.push_str(&format!("  ({})", note));
+    }
+    println!(
+        "  {:>6}  {}  {:>8}  {:<16} {}{}",
+        record.id,
+        record.started_at.format("%Y-%m-%d %H:%M"),
+        format_duration(record.duration_secs),
+        record.app_name,
+        record.window_title,
+        extra
+    );
+}
+
+/// List sessions with their ids, for `flowmode log edit/split/merge`
+fn list_sessions(from: Option<NaiveDate>, to: Option<NaiveDate>, app: Option<String>) -> Result<()> {
+    let range = resolve_range(from, to)?;
+    let storage = Storage::open(&Config::db_path())?;
+    let listed = storage.for_each_session(&range, &SessionFilter { app, category: None }, |record| {
+        print_session_row(&record);
+        Ok(())
+    })?;
+    if listed == 0 {
+        println!("No sessions between {} and {}.", range.first_day(), range.last_day());
+    }
+    Ok(())
+}
+
+/// Point out sessions that now count twice because `record` overlaps them
+fn warn_overlaps(storage: &Storage, record: &ActivityRecord) -> Result<()> {
+    let overlaps = storage.get_overlapping_sessions(record.started_at, record.end_time(), Some(record.id))?;
+    if overlaps.is_empty() {
+        return Ok(());
+    }
+    println!("It overlaps {} other sessions, so that time counts twice:", overlaps.len());
+    for other in &overlaps {
+        print_session_row(other);
+    }
+    println!("Shorten them with `flowmode log edit` or delete them with `flowmode forget --id`.");
+    Ok(())
+}
+
+/// Store a manual entry
+fn add_entry(draft: EntryDraft) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let storage = Storage::open(&Config::db_path())?;
+    let session = Entries::from_config(&config).manual_session(draft);
+    let id = storage.add_manual_session(&session)?;
+    let record = storage.get_session(id)?.ok_or_else(|| anyhow::anyhow!("Session {} vanished", id))?;
+    println!("Added:");
+    print_session_row(&record);
+    warn_overlaps(&storage, &record)
+}
+
+/// Change a stored session
+fn edit_entry(id: i64, mut edit: SessionEdit) -> Result<()> {
+    if edit.is_empty() {
+        anyhow::bail!("Nothing to change; pass --start, --end, --app, --category, --title, --project or --note");
+    }
+    let config = Config::load().unwrap_or_default();
+    let storage = Storage::open(&Config::db_path())?;
+    let record = storage.get_session(id)?.ok_or_else(|| anyhow::anyhow!("No session with id {}", id))?;
+    Entries::from_config(&config).complete_edit(&record, &mut edit);
+    let record = storage.edit_session(id, &edit)?;
+    println!("Updated:");
+    print_session_row(&record);
+    if edit.started_at.is_some() || edit.ended_at.is_some() {
+        warn_overlaps(&storage, &record)?;
+    }
+    Ok(())
+}
+
+/// Split a session in two
+fn split_entry(id: i64, at: DateTime<Local>) -> Result<()> {
+    let storage = Storage::open(&Config::db_path())?;
+    let second = storage.split_session(id, at)?;
+    println!("Split into:");
+    for part in [id, second] {
+        if let Some(record) = storage.get_session(part)? {
+            print_session_row(&record);
+        }
+    }
+    Ok(())
+}
+
+/// Merge sessions into the earliest one
+fn merge_entries(ids: &[i64]) -> Result<()> {
+    let storage = Storage::open(&Config::db_path())?;
+    let merged = storage.merge_sessions(ids)?;
+    println!("Merged into:");
+    print_session_row(&merged);
+    Ok(())
+}
+
+/// Export sessions to stdout or a file
+fn export_sessions(
+    format: ExportFormat,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    filter: SessionFilter,
+    output: Option<std::path::PathBuf>,
+) -> Result<()> {
+    use std::io::{BufWriter, Write};
+
+    let range = resolve_range(from, to)?;
+    let storage = Storage::open(&Config::db_path())?;
+
+    let out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let written = export::export(&storage, &range, &filter, format, BufWriter::new(out))?;
+
+    if let Some(path) = output {
+        eprintln!("Exported {} records to {}", written, path.display());
+    }
+    Ok(())
+}
+
+/// Map imported events onto tracked apps and store them
+fn import_history(events: Vec<import::ForeignEvent>, importer: Importer) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let total = events.len();
+    let mapped = import::map_events(&config, events);
+
+    let storage = Storage::open(&Config::db_path())?;
+    let stats = storage.import_sessions(&mapped.sessions, importer)?;
+    // Parse, assign and redact only the new rows; the rest of history keeps its edits
+    if let Some(scope) = stats.scope {
+        let parser = title_parser::TitleParser::from_config(&config);
+        storage.reparse_titles(scope, true, |app, category, title| parser.parse(app, category, title))?;
+        let projects = project::ProjectMatcher::from_config(&config);
+        storage.apply_projects(scope, true, |record| projects.match_session(&record.into()).map(str::to_string))?;
+        let sites = sites::SiteMatcher::from_config(&config);
+        storage.apply_sites(scope, |record| sites.match_session(&record.into()))?;
+        let redactor = privacy::Redactor::from_config(&config.privacy, &storage.title_hash_salt()?);
+        storage.redact_titles(scope, false, |record| redactor.redact_stored(record))?;
+    }
+
+    println!("Read {} events from {}.", total, importer.as_str());
+    println!("  Imported:          {}", stats.inserted);
+    println!("  Already tracked:   {}", stats.duplicates);
+    if stats.rolled_up > 0 {
+        println!("  On rolled-up days: {}", stats.rolled_up);
+    }
+    println!("  No matching app:   {}", mapped.unmatched);
+    if mapped.unmatched > 0 {
+        println!();
+        println!("Add [[apps]] rules to {:?} and re-run to import the rest.", Config::config_path());
+    }
+
+    Ok(())
+}
+
+/// Drive the daemon's pomodoro timer from the command line
+fn pomodoro_command(command: PomodoroCliCommand) -> Result<()> {
+    let client = client::DaemonClient::new(WEB_PORT);
+    match command {
+        PomodoroCliCommand::Start { label } => {
+            let reply: serde_json::Value = client.post("/api/pomodoro/start", &serde_json::json!({ "label": label }))?;
+            if reply["status"] == "disabled" {
+                anyhow::bail!("Pomodoro is off in the current mode");
+            }
+            match label.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+                Some(label) => println!("🍅 Work session started: {}", label),
+                None => println!("🍅 Work session started"),
+            }
+        }
+        PomodoroCliCommand::Pause => post_pomodoro(&client, "pause")?,
+        PomodoroCliCommand::Resume => post_pomodoro(&client, "resume")?,
+        PomodoroCliCommand::Skip => post_pomodoro(&client, "skip")?,
+        PomodoroCliCommand::Reset => post_pomodoro(&client, "reset")?,
+        PomodoroCliCommand::Status { json, format, follow } => {
+            let render = |status: &pomodoro::PomodoroStatus| match (&format, json) {
+                (Some(template), json) => status.render(template, json),
+                (None, true) => serde_json::to_string(status).unwrap_or_default(),
+                (None, false) => status.summary_line(),
+            };
+            if !follow {
+                println!("{}", render(&client.get("/api/pomodoro/status")?));
+                return Ok(());
+            }
+
+            let mut stdout = std::io::stdout();
+            let mut last = None;
+            loop {
+                let line = client
+                    .get("/api/pomodoro/status")
+                    .map(|status| render(&status))
+                    .unwrap_or_default();
+                if last.as_ref() != Some(&line) {
+                    writeln!(stdout, "{}", line)?;
+                    stdout.flush()?;
+                    last = Some(line);
+                }
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+        PomodoroCliCommand::Interrupt { kind, note } => {
+            let note = note.join(" ");
+            let _: serde_json::Value = client.post(
+                "/api/pomodoro/interruption",
+                &serde_json::json!({ "kind": kind.as_str(), "note": note }),
+            )?;
+            println!("Logged {} interruption", kind.as_str());
+        }
+        PomodoroCliCommand::Summary { id } => {
+            let summary = pomodoro::load_summary(&Config::db_path(), id)?;
+            print_pomodoro_summary(&summary);
+        }
+    }
+    Ok(())
+}
+
+/// Send a bodiless timer command and print the daemon's reply
+fn post_pomodoro(client: &client::DaemonClient, command: &str) -> Result<()> {
+    let reply: serde_json::Value = client.post(&format!("/api/pomodoro/{}", command), &serde_json::json!({}))?;
+    println!("{}", reply["message"].as_str().unwrap_or(command));
+    Ok(())
+}
+
+fn print_pomodoro_summary(summary: &pomodoro::PomodoroSummary) {
+    let session = &summary.session;
+    println!();
+    println!("🍅 Pomodoro #{}{}", session.id,
+        session.label.as_deref().map(|l| format!(" · {}", l)).unwrap_or_default());
+    println!("═══════════════════════════════════════");
+    let end = session.ended_at.map(|e| e.format("%H:%M").to_string()).unwrap_or_else(|| "now".to_string());
+    let outcome = session.outcome.map(|o| o.as_str()).unwrap_or("running");
+    println!("  {} {} – {} ({})", session.started_at.format("%Y-%m-%d"), session.started_at.format("%H:%M"), end, outcome);
+    println!();
+
+    if summary.apps.is_empty() {
+        println!("  No tracked activity during this session.");
+    }
+    for (app, secs) in &summary.apps {
+        println!("  {:<20} {:>8}", app, format_duration(*secs));
+        for (_, title, secs) in summary.titles.iter().filter(|(a, _, _)| a == app).take(3) {
+            let title: String = title.chars().take(40).collect();
+            println!("    {:<38} {:>8}", title, format_duration(*secs));
+        }
+    }
+
+    if !summary.interruptions.is_empty() {
+        println!();
+        println!("  Interruptions:");
+        for interruption in &summary.interruptions {
+            println!("    {} {:<8} {}", interruption.at.format("%H:%M"), interruption.kind.as_str(),
+                interruption.note.as_deref().unwrap_or(""));
+        }
+    }
+    println!();
+}
+
+/// Parse a title with the configured rules and explain the result
+fn test_title(title: &str, app: Option<String>, category: Option<String>) -> Result<()> {
+    let config = Config::load()?;
+    let tracked = match &app {
+        Some(name) => config.app(name),
+        None => config.match_window("", title),
+    };
+    let app = tracked.map(|a| a.name.clone()).or(app).unwrap_or_default();
+    let category = category.or_else(|| tracked.map(|a| a.category.clone())).unwrap_or_default();
+
+    // Invalid rules are logged and skipped, as in the daemon
+    let parser = title_parser::TitleParser::from_config(&config);
+    let matched = parser.match_rule(&app, &category, title);
+    let parsed = parser.parse(&app, &category, title);
+
+    let or_none = |s: &str| if s.is_empty() { "(none)".to_string() } else { s.to_string() };
+    println!("Title:        {}", title);
+    println!("App:          {}", or_none(&app));
+    println!("Category:     {}", or_none(&category));
+    match &matched {
+        Some(m) => {
+            println!("Rule:         title_rules[{}] {}", m.index, m.pattern);
+            for (name, value) in &m.captures {
+                println!("  {:<12}{}", name, value);
+            }
+        }
+        None => println!("Rule:         built-in parser ({} rules checked)", config.title_rules.len()),
+    }
+    println!("Display:      {}", parsed.display);
+    println!("Context type: {}", parsed.context_type);
+    println!("Context:      {}", parsed.context);
+
+    // A dry run: the database is only read, and only if it's there and not encrypted
+    let salt = Storage::peek_title_hash_salt(&Config::db_path())?;
+    let redactor = privacy::Redactor::from_config(&config.privacy, salt.as_deref().unwrap_or("preview"));
+    let policy = redactor.policy(&app, &category);
+    let stored = redactor.redact(policy, title, &parsed, None);
+    println!("Policy:       {}", policy.as_str());
+    println!("Stored as:    {}", or_none(&stored.title));
+    if policy == config::TitlePolicy::Hash && salt.is_none() {
+        println!("              (hashed with a placeholder salt, the database's is not readable)");
+    }
+    Ok(())
+}
+
+/// Parse stored window titles with the current rules
+fn reparse_titles(from: Option<NaiveDate>, to: Option<NaiveDate>, missing_only: bool) -> Result<()> {
+    let range = history_range(from, to)?;
+    let config = Config::load()?;
+    let parser = title_parser::TitleParser::from_config(&config);
+    let storage = Storage::open(&Config::db_path())?;
+    let updated = storage.reparse_titles(range.into(), missing_only, |app, category, title| {
+        parser.parse(app, category, title)
+    })?;
+    println!("Re-parsed window titles of {} sessions.", updated);
+    Ok(())
+}
+
+/// Redact stored window titles with the current [privacy] settings
+fn redact_titles(from: Option<NaiveDate>, to: Option<NaiveDate>, dry_run: bool) -> Result<()> {
+    let range = history_range(from, to)?;
+    let config = Config::load()?;
+    let storage = Storage::open(&Config::db_path())?;
+    let parser = title_parser::TitleParser::from_config(&config);
+    // Parse first, so `parsed_only` keeps what the configured rules extract
+    if !dry_run {
+        storage.reparse_titles(range.into(), true, |app, category, title| parser.parse(app, category, title))?;
+    }
+    let redactor = privacy::Redactor::from_config(&config.privacy, &storage.title_hash_salt()?);
+    let changed = storage.redact_titles(range.into(), dry_run, |record| redactor.redact_stored(record))?;
+    if dry_run {
+        println!("Would redact the titles of {} sessions (nothing changed).", changed);
+    } else {
+        println!("Redacted the titles of {} sessions.", changed);
+    }
+    Ok(())
+}
+
+/// Range for commands that cover all history unless limited
+fn history_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Option<TimeRange>> {
+    if from.is_none() && to.is_none() {
+        return Ok(None);
+    }
+    let from = from.unwrap_or_default();
+    let to = to.unwrap_or_else(|| chrono::Local::now().date_naive());
+    if to < from {
+        anyhow::bail!("--to ({}) is before --from ({})", to, from);
+    }
+    Ok(Some(TimeRange::days(from, to)))
+}
+
+/// Run the project rules over stored sessions
+fn apply_projects(from: Option<NaiveDate>, to: Option<NaiveDate>, all: bool) -> Result<()> {
+    let range = history_range(from, to)?;
+    let config = Config::load()?;
+    let matcher = project::ProjectMatcher::from_config(&config);
+    if matcher.is_empty() {
+        anyhow::bail!("No [[projects]] in {:?}", Config::config_path());
+    }
+    let storage = Storage::open(&Config::db_path())?;
+    let changed = storage.apply_projects(range.into(), !all, |record| {
+        matcher.match_session(&record.into()).map(str::to_string)
+    })?;
+    println!("Updated the project of {} sessions.", changed);
+    Ok(())
+}
+
+/// Run the [[sites]] rules (and the built-in sites) over stored browser sessions
+fn apply_sites(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<()> {
+    let range = history_range(from, to)?;
+    let config = Config::load()?;
+    let matcher = sites::SiteMatcher::from_config(&config);
+    let storage = Storage::open(&Config::db_path())?;
+    let changed = storage.apply_sites(range.into(), |record| matcher.match_session(&record.into()))?;
+    println!("Updated the site category of {} browser sessions.", changed);
+    Ok(())
+}
+
+/// Assign sessions to a project by hand
+fn assign_project(project: &str, selection: &SessionSelection) -> Result<()> {
+    if selection.ids.is_empty() && selection.range.is_none() {
+        anyhow::bail!("Pick sessions with --id or a date range (--from/--to)");
+    }
+    let project = Some(project.trim()).filter(|p| !p.is_empty() && !p.eq_ignore_ascii_case(project::UNASSIGNED));
+    let storage = Storage::open(&Config::db_path())?;
+    let assigned = storage.assign_project(selection, project)?;
+    println!("Assigned {} sessions to {}.", assigned, project.unwrap_or(project::UNASSIGNED));
+    Ok(())
+}
+
+/// Register `flowmode browser-host` as a native messaging host with a browser
+fn install_browser_host(browser: browser::BrowserKind, extension_id: &str) -> Result<()> {
+    let manifest = browser::install_manifest(browser, extension_id)?;
+    println!("Installed {} for {:?}: {}", browser::HOST_NAME, browser, manifest.display());
+    println!("Reload the extension so it connects; tab URLs are recorded while `flowmode start` runs.");
+    Ok(())
+}
+
+/// Print tracked time per parsed context
+fn show_contexts(from: Option<NaiveDate>, to: Option<NaiveDate>, filter: ContextFilter) -> Result<()> {
+    let range = resolve_range(from, to)?;
+    let storage = Storage::open(&Config::db_path())?;
+    let contexts = storage.get_range_contexts(&range, &filter)?;
+
+    if contexts.is_empty() {
+        println!("No parsed contexts in this range. Run `flowmode titles reparse` for older sessions.");
+        return Ok(());
+    }
+    println!("{:<10} {:<40} {:>9} {:>5}", "TYPE", "CONTEXT", "TIME", "DAYS");
+    for c in &contexts {
+        let context: String = c.context.chars().take(40).collect();
+        println!("{:<10} {:<40} {:>9} {:>5}", c.context_type, context, format_duration(c.total_secs), c.days);
+    }
+    Ok(())
+}
+
+/// Roll up sessions older than `retention_days` into daily totals
+fn compact_old_sessions(retention_days: u32) -> Result<()> {
+    let before = chrono::Local::now().date_naive() - chrono::Duration::days(retention_days as i64);
+    let storage = Storage::open(&Config::db_path())?;
+    let stats = storage.compact_before(before)?;
+    if stats.sessions_removed > 0 {
+        info!(
+            "Rolled up {} sessions from {} days before {}",
+            stats.sessions_removed, stats.days, before
+        );
+    }
+    Ok(())
+}
+
+/// Roll up all sessions before a date
+fn prune_database(before: NaiveDate) -> Result<()> {
+    let storage = Storage::open(&Config::db_path())?;
+    let stats = storage.compact_before(before)?;
+
+    println!("Rolled up {} sessions from {} days into {} daily totals.",
+        stats.sessions_removed, stats.days, stats.rollup_rows);
+    println!("Window titles before {} have been removed.", before);
+
+    Ok(())
+}
+
+/// Encrypt activity.db in place
+fn encrypt_database(secret_service: bool) -> Result<()> {
+    ensure_daemon_stopped()?;
+    let path = Config::db_path();
+    if encryption::is_encrypted(&path)? {
+        anyhow::bail!("{} is already encrypted", path.display());
+    }
+    // Create the database first, so there is something to encrypt
+    drop(Storage::open(&path)?);
+    let source = if secret_service {
+        encryption::KeySource::SecretService
+    } else {
+        encryption::KeySource::Passphrase
+    };
+    encryption::encrypt(&path, source)?;
+    println!("Encrypted {}.", path.display());
+    match source {
+        encryption::KeySource::SecretService => println!("Its key is kept in the Secret Service."),
+        encryption::KeySource::Passphrase => println!(
+            "FlowMode asks for the passphrase, or reads {}, when it opens the database.",
+            encryption::PASSPHRASE_ENV
+        ),
+    }
+    println!("Backups of the old file are still readable; replace them.");
+    Ok(())
+}
+
+/// Decrypt activity.db back to a plain SQLite file
+fn decrypt_database() -> Result<()> {
+    ensure_daemon_stopped()?;
+    let path = Config::db_path();
+    encryption::decrypt(&path)?;
+    println!("Decrypted {}.", path.display());
+    Ok(())
+}
+
+/// Refuse to rewrite the database while the daemon has it open
+fn ensure_daemon_stopped() -> Result<()> {
+    let client = client::DaemonClient::new(WEB_PORT);
+    if client.get::<serde_json::Value>("/api/pomodoro/status").is_ok() {
+        anyhow::bail!("Stop FlowMode first (`systemctl --user stop flowmode` or Ctrl+C)");
+    }
+    Ok(())
+}
+
 /// Self-update from GitHub releases
 fn self_update() -> Result<()> {
     println!("Checking for updates...");
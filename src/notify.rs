@@ -0,0 +1,344 @@
+//! Desktop notifications over D-Bus (`org.freedesktop.Notifications`)
+//!
+//! A background thread owns the D-Bus connection. It sends the notifications
+//! queued through `Notifier` and reports clicked action buttons back over a
+//! channel, so the daemon can apply them to the pomodoro timer.
+
+use anyhow::{anyhow, Result};
+use dbus::arg::{PropMap, Variant};
+use dbus::blocking::LocalConnection;
+use dbus::channel::Channel;
+use dbus::message::MatchRule;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc::{self, TryRecvError};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+const BUS_NAME: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+const APP_NAME: &str = "FlowMode";
+const ICON: &str = "appointment-soon";
+
+/// Button shown on a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyAction {
+    StartBreak,
+    StartWork,
+    Skip,
+    /// Five more minutes of the work session that just ended
+    Extend,
+}
+
+impl NotifyAction {
+    pub fn key(&self) -> &'static str {
+        match self {
+            NotifyAction::StartBreak => "start-break",
+            NotifyAction::StartWork => "start-work",
+            NotifyAction::Skip => "skip",
+            NotifyAction::Extend => "extend",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotifyAction::StartBreak => "Start break",
+            NotifyAction::StartWork => "Start pomodoro",
+            NotifyAction::Skip => "Skip",
+            NotifyAction::Extend => "+5 min",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        [NotifyAction::StartBreak, NotifyAction::StartWork, NotifyAction::Skip, NotifyAction::Extend]
+            .into_iter()
+            .find(|a| a.key() == key)
+    }
+}
+
+/// What a notification is about. A new notification replaces the previous
+/// one on the same topic instead of stacking up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Pomodoro,
+    LongSession,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub topic: Topic,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<NotifyAction>,
+    pub urgent: bool,
+}
+
+/// Clicked action buttons, in order
+pub type ActionReceiver = UnboundedReceiver<NotifyAction>;
+
+/// Handle for sending notifications; cheap to clone
+#[derive(Clone)]
+pub struct Notifier {
+    tx: mpsc::Sender<Notification>,
+    sound: Option<PathBuf>,
+}
+
+impl Notifier {
+    /// Connect to the session bus (or the bus at `address`) and start the
+    /// notification thread
+    pub fn start(address: Option<String>, sound: Option<PathBuf>) -> Result<(Self, ActionReceiver)> {
+        let (tx, rx) = mpsc::channel();
+        let (action_tx, action_rx) = unbounded_channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        // The connection is not Send, so it is created on the thread that uses it
+        std::thread::spawn(move || {
+            let actions = Rc::new(RefCell::new(HashMap::new()));
+            match connect(address.as_deref(), actions.clone(), action_tx) {
+                Ok(conn) => {
+                    let _ = ready_tx.send(Ok(()));
+                    run(&conn, &rx, &actions);
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                }
+            }
+        });
+
+        ready_rx
+            .recv()
+            .map_err(|_| anyhow!("Notification thread exited"))??;
+        Ok((Self { tx, sound }, action_rx))
+    }
+
+    /// Show a notification and play the configured sound
+    pub fn send(&self, notification: Notification) {
+        if let Some(sound) = &self.sound {
+            play_sound(sound);
+        }
+        let _ = self.tx.send(notification);
+    }
+}
+
+/// Notification id -> the actions it offers
+type ActionMap = Rc<RefCell<HashMap<u32, Vec<NotifyAction>>>>;
+
+fn connect(
+    address: Option<&str>,
+    actions: ActionMap,
+    action_tx: UnboundedSender<NotifyAction>,
+) -> Result<LocalConnection> {
+    let conn = match address {
+        Some(address) => {
+            let mut channel = Channel::open_private(address)?;
+            channel.register()?;
+            LocalConnection::from(channel)
+        }
+        None => LocalConnection::new_session()?,
+    };
+
+    conn.add_match(
+        MatchRule::new_signal(BUS_NAME, "ActionInvoked"),
+        move |(id, key): (u32, String), _, _| {
+            let offered = actions.borrow().get(&id).cloned().unwrap_or_default();
+            if let Some(action) = NotifyAction::from_key(&key).filter(|a| offered.contains(a)) {
+                let _ = action_tx.send(action);
+            }
+            true
+        },
+    )?;
+
+    Ok(conn)
+}
+
+fn run(conn: &LocalConnection, rx: &mpsc::Receiver<Notification>, actions: &ActionMap) {
+    let mut shown: HashMap<Topic, u32> = HashMap::new();
+
+    loop {
+        loop {
+            match rx.try_recv() {
+                Ok(notification) => {
+                    let replaces = shown.get(&notification.topic).copied().unwrap_or(0);
+                    match notify(conn, &notification, replaces) {
+                        Ok(id) => {
+                            shown.insert(notification.topic, id);
+                            actions.borrow_mut().insert(id, notification.actions);
+                        }
+                        Err(e) => tracing::warn!("Failed to send notification: {}", e),
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        if let Err(e) = conn.process(Duration::from_millis(250)) {
+            tracing::warn!("Notification connection lost: {}", e);
+            return;
+        }
+    }
+}
+
+fn notify(conn: &LocalConnection, notification: &Notification, replaces_id: u32) -> Result<u32, dbus::Error> {
+    let proxy = conn.with_proxy(BUS_NAME, OBJECT_PATH, Duration::from_secs(5));
+
+    let actions: Vec<&str> = notification
+        .actions
+        .iter()
+        .flat_map(|a| [a.key(), a.label()])
+        .collect();
+    let mut hints = PropMap::new();
+    let urgency: u8 = if notification.urgent { 2 } else { 1 };
+    hints.insert("urgency".to_string(), Variant(Box::new(urgency)));
+
+    let (id,): (u32,) = proxy.method_call(
+        BUS_NAME,
+        "Notify",
+        (
+            APP_NAME,
+            replaces_id,
+            ICON,
+            notification.summary.as_str(),
+            notification.body.as_str(),
+            actions,
+            hints,
+            -1i32,
+        ),
+    )?;
+    Ok(id)
+}
+
+/// Play a sound file with the first player that works, without blocking
+fn play_sound(path: &Path) {
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        for player in ["paplay", "pw-play", "aplay"] {
+            let status = Command::new(player)
+                .arg(&path)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            if status.is_ok_and(|s| s.success()) {
+                return;
+            }
+        }
+        tracing::debug!("No sound player could play {}", path.display());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::channel::{MatchingReceiver, Sender};
+    use dbus::Message;
+    use std::io::{BufRead, BufReader};
+    use std::process::Child;
+
+    /// A throwaway bus from `dbus-daemon`, killed on drop
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let stdout = daemon.stdout.take();
+            let mut bus = Self { daemon, address: String::new() };
+            BufReader::new(stdout?).read_line(&mut bus.address).ok()?;
+            bus.address = bus.address.trim().to_string();
+            // A daemon that can't listen exits without printing an address
+            (!bus.address.is_empty()).then_some(bus)
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// Fake notification server: reports each Notify call, then clicks `click`
+    /// Returns without reporting "ready" if it can't get onto the bus.
+    fn fake_server(address: String, click: &'static str, seen: mpsc::Sender<(String, Vec<String>)>) {
+        let Ok(mut channel) = Channel::open_private(&address) else {
+            return;
+        };
+        if channel.register().is_err() {
+            return;
+        }
+        let conn = LocalConnection::from(channel);
+        if conn.request_name(BUS_NAME, false, true, false).is_err() {
+            return;
+        }
+
+        let reports = seen.clone();
+        conn.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |msg: Message, conn: &LocalConnection| {
+                if msg.member().as_deref() == Some("Notify") {
+                    let (_, _, _, summary, _, actions): (String, u32, String, String, String, Vec<String>) =
+                        msg.read_all().unwrap();
+                    let _ = reports.send((summary, actions));
+                    let _ = conn.send(msg.method_return().append1(7u32));
+                    let signal = Message::new_signal(OBJECT_PATH, BUS_NAME, "ActionInvoked")
+                        .unwrap()
+                        .append2(7u32, click);
+                    let _ = conn.send(signal);
+                }
+                true
+            }),
+        );
+        let _ = seen.send(("ready".to_string(), Vec::new()));
+
+        while conn.process(Duration::from_millis(100)).is_ok() {}
+    }
+
+    #[test]
+    fn test_notification_actions_roundtrip() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let (seen_tx, seen_rx) = mpsc::channel();
+        let address = bus.address.clone();
+        std::thread::spawn(move || fake_server(address, "extend", seen_tx));
+        if seen_rx.recv_timeout(Duration::from_secs(5)).map(|(what, _)| what).as_deref() != Ok("ready") {
+            eprintln!("private bus not reachable, skipping");
+            return;
+        }
+
+        let (notifier, mut actions) = Notifier::start(Some(bus.address.clone()), None).unwrap();
+        notifier.send(Notification {
+            topic: Topic::Pomodoro,
+            summary: "Pomodoro complete".to_string(),
+            body: String::new(),
+            actions: vec![NotifyAction::StartBreak, NotifyAction::Extend],
+            urgent: false,
+        });
+
+        let (summary, sent) = seen_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(summary, "Pomodoro complete");
+        assert_eq!(sent, vec!["start-break", "Start break", "extend", "+5 min"]);
+
+        let mut clicked = None;
+        for _ in 0..50 {
+            if let Ok(action) = actions.try_recv() {
+                clicked = Some(action);
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        assert_eq!(clicked, Some(NotifyAction::Extend));
+    }
+}
@@ -1,21 +1,42 @@
-/// Pomodoro Timer Module
-///
-/// Implements a simple Pomodoro technique timer with:
-/// - 25 minute work sessions
-/// - 5 minute short breaks
-/// - 15 minute long breaks (every 4 pomodoros)
-
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-
-/// Default durations in seconds
+//! Pomodoro Timer Module
+//!
+//! Implements a simple Pomodoro technique timer with:
+//! - 25 minute work sessions
+//! - 5 minute short breaks
+//! - 15 minute long breaks (every 4 pomodoros)
+//!
+//! Time is measured against the wall clock: a session ends when its elapsed
+//! time reaches its duration, however often (or rarely) `tick` is called.
+//! Time spent suspended counts, time spent paused does not.
+//!
+//! The tracking loop reports idle time and tray pauses, so a work session can
+//! stop counting while you are away and a break only counts if you took it.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::watch;
+
+use crate::config::Config;
+use crate::notify::{ActionReceiver, Notification, Notifier, NotifyAction, Topic};
+use crate::storage::{PomodoroInterruption, PomodoroSession, Storage, TimeRange};
+use crate::tray::format_duration;
+
+/// Default durations in minutes
 pub const DEFAULT_WORK_MINS: u64 = 25;
 pub const DEFAULT_SHORT_BREAK_MINS: u64 = 5;
 pub const DEFAULT_LONG_BREAK_MINS: u64 = 15;
 pub const POMODOROS_UNTIL_LONG_BREAK: u32 = 4;
 
+/// How much the "+5 min" notification action adds to a finished work session
+pub const EXTEND_SECS: u64 = 5 * 60;
+
 /// Timer state
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TimerState {
@@ -36,157 +57,720 @@ impl TimerState {
             TimerState::Paused => "paused",
         }
     }
+
+    /// Session kind for a running state (`None` when idle or paused)
+    pub fn kind(&self) -> Option<PomodoroKind> {
+        match self {
+            TimerState::Working => Some(PomodoroKind::Work),
+            TimerState::ShortBreak => Some(PomodoroKind::ShortBreak),
+            TimerState::LongBreak => Some(PomodoroKind::LongBreak),
+            TimerState::Idle | TimerState::Paused => None,
+        }
+    }
+}
+
+/// Kind of a recorded pomodoro session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PomodoroKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PomodoroKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PomodoroKind::Work => "work",
+            PomodoroKind::ShortBreak => "short_break",
+            PomodoroKind::LongBreak => "long_break",
+        }
+    }
+
+    /// Human-readable name, e.g. for the tray
+    pub fn label(&self) -> &'static str {
+        match self {
+            PomodoroKind::Work => "Work",
+            PomodoroKind::ShortBreak => "Short break",
+            PomodoroKind::LongBreak => "Long break",
+        }
+    }
+
+    /// Timer state while a session of this kind runs
+    pub fn state(&self) -> TimerState {
+        match self {
+            PomodoroKind::Work => TimerState::Working,
+            PomodoroKind::ShortBreak => TimerState::ShortBreak,
+            PomodoroKind::LongBreak => TimerState::LongBreak,
+        }
+    }
+}
+
+impl FromStr for PomodoroKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "work" => Ok(PomodoroKind::Work),
+            "short_break" => Ok(PomodoroKind::ShortBreak),
+            "long_break" => Ok(PomodoroKind::LongBreak),
+            other => Err(format!("unknown pomodoro kind '{}'", other)),
+        }
+    }
+}
+
+/// How a recorded pomodoro session ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PomodoroOutcome {
+    Completed,
+    Skipped,
+    Interrupted,
+}
+
+impl PomodoroOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PomodoroOutcome::Completed => "completed",
+            PomodoroOutcome::Skipped => "skipped",
+            PomodoroOutcome::Interrupted => "interrupted",
+        }
+    }
+}
+
+impl FromStr for PomodoroOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "completed" => Ok(PomodoroOutcome::Completed),
+            "skipped" => Ok(PomodoroOutcome::Skipped),
+            "interrupted" => Ok(PomodoroOutcome::Interrupted),
+            other => Err(format!("unknown pomodoro outcome '{}'", other)),
+        }
+    }
+}
+
+/// Where an interruption during a work session came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterruptionKind {
+    /// You broke off yourself (a thought, checking mail)
+    Internal,
+    /// Someone or something else did (a call, a colleague)
+    External,
+}
+
+impl InterruptionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InterruptionKind::Internal => "internal",
+            InterruptionKind::External => "external",
+        }
+    }
+}
+
+impl FromStr for InterruptionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "internal" => Ok(InterruptionKind::Internal),
+            "external" => Ok(InterruptionKind::External),
+            other => Err(format!("unknown interruption kind '{}' (use internal or external)", other)),
+        }
+    }
+}
+
+/// Effective timer settings: `[pomodoro]` plus the active mode's overrides
+#[derive(Debug, Clone, PartialEq)]
+pub struct PomodoroSettings {
+    pub enabled: bool,
+    pub work_secs: u64,
+    pub short_break_secs: u64,
+    pub long_break_secs: u64,
+    pub long_break_after: u32,
+    pub auto_start_breaks: bool,
+    pub auto_start_work: bool,
+    pub daily_target: u32,
+    pub pause_on_idle: bool,
+    pub pause_with_tracking: bool,
+    pub break_idle_secs: u64,
+}
+
+impl PomodoroSettings {
+    /// Settings from the config, with overrides from `mode` if it exists
+    pub fn from_config(config: &Config, mode: Option<&str>) -> Self {
+        let base = &config.pomodoro;
+        let focus = mode.and_then(|name| config.mode(name)).map(|m| &m.focus);
+        let minutes = |over: Option<u64>, default: u64| over.unwrap_or(default).max(1) * 60;
+
+        Self {
+            enabled: focus.and_then(|f| f.pomodoro).unwrap_or(true),
+            work_secs: minutes(focus.and_then(|f| f.work_duration), base.work_duration),
+            short_break_secs: minutes(focus.and_then(|f| f.short_break), base.short_break),
+            long_break_secs: minutes(focus.and_then(|f| f.long_break), base.long_break),
+            long_break_after: base.long_break_after.max(1),
+            auto_start_breaks: base.auto_start_breaks,
+            auto_start_work: base.auto_start_work,
+            daily_target: base.daily_target,
+            pause_on_idle: base.pause_on_idle,
+            pause_with_tracking: base.pause_with_tracking,
+            break_idle_secs: base.break_idle_secs,
+        }
+    }
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self::from_config(&Config::default(), None)
+    }
+}
+
+/// `{name}` placeholder of a status bar template
+static PLACEHOLDER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// Point-in-time view of the timer, used to persist it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PomodoroSnapshot {
+    /// Current (or paused) session kind; `None` when idle
+    pub kind: Option<PomodoroKind>,
+    pub paused: bool,
+    pub remaining_secs: u64,
+}
+
+/// Timer status served by `/api/pomodoro/status` and shown by `flowmode pomodoro status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroStatus {
+    pub state: String,           // "idle", "working", "short_break", "long_break", "paused"
+    /// Session kind, also while paused: "work", "short_break" or "long_break"
+    pub kind: Option<String>,
+    pub remaining_secs: u64,
+    pub remaining_formatted: String,
+    pub completed_pomodoros: u64,
+    pub daily_target: u32,
+    pub enabled: bool,
+    pub mode: Option<String>,
+    pub label: Option<String>,
+}
+
+impl PomodoroStatus {
+    fn kind(&self) -> Option<PomodoroKind> {
+        self.kind.as_deref().and_then(|k| k.parse().ok())
+    }
+
+    fn icon(&self) -> &'static str {
+        match (self.state.as_str(), self.kind()) {
+            ("paused", _) => "⏸",
+            (_, Some(PomodoroKind::Work)) => "🍅",
+            (_, Some(_)) => "☕",
+            _ => "",
+        }
+    }
+
+    /// Fill a status bar template. Placeholders: `{icon}`, `{state}`, `{phase}`,
+    /// `{remaining}` (MM:SS), `{minutes}` (rounded up), `{remaining_secs}`,
+    /// `{label}`, `{completed}`, `{target}` and `{mode}`; other braces are kept.
+    /// Values are filled in once, so braces in a label stay as they are; with
+    /// `json` they are escaped for use inside a JSON string.
+    pub fn render(&self, template: &str, json: bool) -> String {
+        let phase = self.kind().map(|k| k.label()).unwrap_or("Idle");
+        let value = |name: &str| {
+            Some(match name {
+                "icon" => self.icon().to_string(),
+                "state" => self.state.clone(),
+                "phase" => phase.to_string(),
+                "remaining" => self.remaining_formatted.clone(),
+                "minutes" => self.remaining_secs.div_ceil(60).to_string(),
+                "remaining_secs" => self.remaining_secs.to_string(),
+                "label" => self.label.clone().unwrap_or_default(),
+                "completed" => self.completed_pomodoros.to_string(),
+                "target" => self.daily_target.to_string(),
+                "mode" => self.mode.clone().unwrap_or_default(),
+                _ => return None,
+            })
+        };
+        let line = PLACEHOLDER_RE.replace_all(template, |caps: &regex::Captures| match value(&caps[1]) {
+            Some(value) if json => {
+                let quoted = serde_json::to_string(&value).unwrap_or_default();
+                quoted.trim_matches('"').to_string()
+            }
+            Some(value) => value,
+            None => caps[0].to_string(),
+        });
+        line.trim().to_string()
+    }
+
+    /// One line for people, e.g. "🍅 Work 12:34 left · Report · 3/8 today"
+    pub fn summary_line(&self) -> String {
+        if !self.enabled {
+            return "Pomodoro is off in the current mode".to_string();
+        }
+        let today = format!("{}/{} today", self.completed_pomodoros, self.daily_target);
+        let Some(kind) = self.kind() else {
+            return format!("No pomodoro running · {}", today);
+        };
+        let mut line = format!("{} {} {} left", self.icon(), kind.label(), self.remaining_formatted);
+        if self.state == "paused" {
+            line.push_str(" (paused)");
+        }
+        if let Some(label) = &self.label {
+            line.push_str(" · ");
+            line.push_str(label);
+        }
+        format!("{} · {}", line, today)
+    }
+}
+
+/// Source of the current time, replaceable in tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// The real wall clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock that only moves when told to
+#[cfg(test)]
+pub struct ManualClock(Mutex<DateTime<Local>>);
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self(Mutex::new(Local::now())))
+    }
+
+    pub fn advance(&self, secs: i64) {
+        *self.0.lock().unwrap() += ChronoDuration::seconds(secs);
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Timing of the current session
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    state: TimerState,
+    /// State to return to on resume
+    state_before_pause: TimerState,
+    duration: ChronoDuration,
+    /// Time run before the last pause
+    elapsed_before: ChronoDuration,
+    /// When the timer last started or resumed; `None` while idle or paused
+    running_since: Option<DateTime<Local>>,
+    /// Paused by idle detection or a tracking pause rather than by the user
+    auto_paused: bool,
+    /// The user went idle during this break
+    went_idle: bool,
+}
+
+impl Session {
+    fn idle(duration: ChronoDuration) -> Self {
+        Self {
+            state: TimerState::Idle,
+            state_before_pause: TimerState::Idle,
+            duration,
+            elapsed_before: ChronoDuration::zero(),
+            running_since: None,
+            auto_paused: false,
+            went_idle: false,
+        }
+    }
+
+    fn running(state: TimerState, duration: ChronoDuration, now: DateTime<Local>) -> Self {
+        Self {
+            state,
+            state_before_pause: TimerState::Idle,
+            duration,
+            elapsed_before: ChronoDuration::zero(),
+            running_since: Some(now),
+            auto_paused: false,
+            went_idle: false,
+        }
+    }
+
+    /// A session that is ready but waits for `resume`
+    fn queued(state: TimerState, duration: ChronoDuration) -> Self {
+        Self {
+            state: TimerState::Paused,
+            state_before_pause: state,
+            duration,
+            elapsed_before: ChronoDuration::zero(),
+            running_since: None,
+            auto_paused: false,
+            went_idle: false,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Stop counting as of `at`
+    fn pause_at(&mut self, at: DateTime<Local>) {
+        self.elapsed_before = self.elapsed(at);
+        self.running_since = None;
+        self.state_before_pause = self.state;
+        self.state = TimerState::Paused;
+    }
+
+    fn elapsed(&self, now: DateTime<Local>) -> ChronoDuration {
+        let running = self
+            .running_since
+            .map(|since| (now - since).max(ChronoDuration::zero()))
+            .unwrap_or_else(ChronoDuration::zero);
+        self.elapsed_before + running
+    }
+
+    /// Whole seconds left, rounded up so the display reaches 00:00 only when done
+    fn remaining_secs(&self, now: DateTime<Local>) -> u64 {
+        let left = (self.duration - self.elapsed(now)).num_milliseconds().max(0) as u64;
+        left.div_ceil(1000)
+    }
 }
 
 /// Pomodoro timer
 pub struct PomodoroTimer {
-    state: RwLock<TimerState>,
-    state_before_pause: RwLock<TimerState>,
-    remaining_secs: AtomicU64,
+    session: Mutex<Session>,
     completed_pomodoros: AtomicU64,
-    last_tick: RwLock<Option<Instant>>,
-    enabled: AtomicBool,
-
-    // Configurable durations (in seconds)
-    work_duration: u64,
-    short_break_duration: u64,
-    long_break_duration: u64,
+    settings: Mutex<PomodoroSettings>,
+    /// Mode whose overrides are applied, re-read on config reload
+    mode: Mutex<Option<String>>,
+    /// What the current pomodoro is for; kept through breaks until the next start or reset
+    label: Mutex<Option<String>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl PomodoroTimer {
     /// Create a new timer with default durations
     pub fn new() -> Self {
-        Self {
-            state: RwLock::new(TimerState::Idle),
-            state_before_pause: RwLock::new(TimerState::Idle),
-            remaining_secs: AtomicU64::new(DEFAULT_WORK_MINS * 60),
-            completed_pomodoros: AtomicU64::new(0),
-            last_tick: RwLock::new(None),
-            enabled: AtomicBool::new(true),
-            work_duration: DEFAULT_WORK_MINS * 60,
-            short_break_duration: DEFAULT_SHORT_BREAK_MINS * 60,
-            long_break_duration: DEFAULT_LONG_BREAK_MINS * 60,
-        }
+        Self::with_settings(PomodoroSettings::default())
     }
 
     /// Create with custom durations (in minutes)
+    #[cfg(test)]
     pub fn with_durations(work_mins: u64, short_break_mins: u64, long_break_mins: u64) -> Self {
+        Self::with_settings(PomodoroSettings {
+            work_secs: work_mins * 60,
+            short_break_secs: short_break_mins * 60,
+            long_break_secs: long_break_mins * 60,
+            ..PomodoroSettings::default()
+        })
+    }
+
+    /// Create with the given settings
+    pub fn with_settings(settings: PomodoroSettings) -> Self {
         Self {
-            state: RwLock::new(TimerState::Idle),
-            state_before_pause: RwLock::new(TimerState::Idle),
-            remaining_secs: AtomicU64::new(work_mins * 60),
+            session: Mutex::new(Session::idle(ChronoDuration::seconds(settings.work_secs as i64))),
             completed_pomodoros: AtomicU64::new(0),
-            last_tick: RwLock::new(None),
-            enabled: AtomicBool::new(true),
-            work_duration: work_mins * 60,
-            short_break_duration: short_break_mins * 60,
-            long_break_duration: long_break_mins * 60,
+            settings: Mutex::new(settings),
+            mode: Mutex::new(None),
+            label: Mutex::new(None),
+            clock: Arc::new(SystemClock),
         }
     }
 
-    /// Start a work session
-    pub async fn start_work(&self) {
-        let mut state = self.state.write().await;
-        *state = TimerState::Working;
-        self.remaining_secs.store(self.work_duration, Ordering::SeqCst);
-        *self.last_tick.write().await = Some(Instant::now());
+    /// Use a different time source (tests fast-forward with `ManualClock`)
+    #[cfg(test)]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 
-    /// Start a break (auto-selects short or long based on completed pomodoros)
-    pub async fn start_break(&self) {
-        let completed = self.completed_pomodoros.load(Ordering::SeqCst) as u32;
-        let mut state = self.state.write().await;
+    fn session(&self) -> std::sync::MutexGuard<'_, Session> {
+        // The session is plain data, so a panic elsewhere cannot leave it inconsistent
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current settings
+    pub fn settings(&self) -> PomodoroSettings {
+        self.settings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the settings. A running session keeps its length; new durations
+    /// apply from the next session (or right away when idle).
+    pub fn apply_settings(&self, settings: PomodoroSettings) {
+        {
+            let mut session = self.session();
+            if session.state == TimerState::Idle {
+                session.duration = ChronoDuration::seconds(settings.work_secs as i64);
+            }
+        }
+        *self.settings.lock().unwrap_or_else(|e| e.into_inner()) = settings;
+    }
+
+    /// Name of the mode whose overrides are active
+    pub fn mode(&self) -> Option<String> {
+        self.mode.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
 
-        if completed > 0 && completed % POMODOROS_UNTIL_LONG_BREAK == 0 {
-            *state = TimerState::LongBreak;
-            self.remaining_secs.store(self.long_break_duration, Ordering::SeqCst);
+    fn set_mode(&self, mode: Option<String>) {
+        *self.mode.lock().unwrap_or_else(|e| e.into_inner()) = mode;
+    }
+
+    /// Task or project the current pomodoro is for
+    pub fn label(&self) -> Option<String> {
+        self.label.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Set the label; blank labels are dropped
+    pub fn set_label(&self, label: Option<String>) {
+        let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+        *self.label.lock().unwrap_or_else(|e| e.into_inner()) = label;
+    }
+
+    fn duration(&self, kind: PomodoroKind) -> ChronoDuration {
+        ChronoDuration::seconds(self.duration_for(kind) as i64)
+    }
+
+    /// Start a work session
+    pub fn start_work(&self) {
+        let now = self.clock.now();
+        *self.session() = Session::running(TimerState::Working, self.duration(PomodoroKind::Work), now);
+    }
+
+    /// Kind of break due next (long after every Nth completed pomodoro)
+    fn next_break(&self) -> PomodoroKind {
+        let completed = self.completed_pomodoros.load(Ordering::SeqCst);
+        let every = self.settings().long_break_after as u64;
+        if completed > 0 && completed.is_multiple_of(every) {
+            PomodoroKind::LongBreak
         } else {
-            *state = TimerState::ShortBreak;
-            self.remaining_secs.store(self.short_break_duration, Ordering::SeqCst);
+            PomodoroKind::ShortBreak
         }
-        *self.last_tick.write().await = Some(Instant::now());
     }
 
-    /// Pause the timer
-    pub async fn pause(&self) {
-        let mut state = self.state.write().await;
-        if *state != TimerState::Idle && *state != TimerState::Paused {
-            *self.state_before_pause.write().await = *state;
-            *state = TimerState::Paused;
+    /// Start a break (auto-selects short or long based on completed pomodoros)
+    pub fn start_break(&self) {
+        let kind = self.next_break();
+        let now = self.clock.now();
+        *self.session() = Session::running(kind.state(), self.duration(kind), now);
+    }
+
+    /// Pause the timer. Pausing a session that was paused automatically keeps
+    /// it paused when you come back.
+    pub fn pause(&self) {
+        let now = self.clock.now();
+        let mut session = self.session();
+        if session.is_running() {
+            session.pause_at(now);
         }
+        session.auto_paused = false;
     }
 
     /// Resume from pause
-    pub async fn resume(&self) {
-        let mut state = self.state.write().await;
-        if *state == TimerState::Paused {
-            *state = *self.state_before_pause.read().await;
-            *self.last_tick.write().await = Some(Instant::now());
+    pub fn resume(&self) {
+        let now = self.clock.now();
+        let mut session = self.session();
+        if session.state == TimerState::Paused {
+            session.state = session.state_before_pause;
+            session.running_since = Some(now);
+            session.auto_paused = false;
+        }
+    }
+
+    /// Pause a running work session because the user has been idle for
+    /// `idle_secs`; the time they were away does not count. Returns whether it paused.
+    pub fn pause_for_idle(&self, idle_secs: u64) -> bool {
+        if !self.settings().pause_on_idle {
+            return false;
+        }
+        let now = self.clock.now();
+        let mut session = self.session();
+        let Some(since) = session.running_since.filter(|_| session.state == TimerState::Working) else {
+            return false;
+        };
+        session.pause_at((now - ChronoDuration::seconds(idle_secs as i64)).max(since));
+        session.auto_paused = true;
+        true
+    }
+
+    /// Pause whatever is running because tracking was paused. Returns whether it paused.
+    pub fn pause_for_tracking(&self) -> bool {
+        if !self.settings().pause_with_tracking {
+            return false;
+        }
+        let now = self.clock.now();
+        let mut session = self.session();
+        if !session.is_running() {
+            return false;
+        }
+        session.pause_at(now);
+        session.auto_paused = true;
+        true
+    }
+
+    /// Resume a session paused by `pause_for_idle` or `pause_for_tracking`.
+    /// Returns whether it resumed; a session the user paused stays paused.
+    pub fn resume_auto(&self) -> bool {
+        if !self.session().auto_paused {
+            return false;
+        }
+        self.resume();
+        true
+    }
+
+    /// Report how long the user has been idle. A break counts as taken once
+    /// they have been away for `break_idle_secs` while it runs.
+    pub fn note_idle(&self, idle_secs: u64) {
+        let threshold = self.settings().break_idle_secs;
+        let mut session = self.session();
+        let on_break = matches!(session.state, TimerState::ShortBreak | TimerState::LongBreak);
+        if on_break && idle_secs >= threshold {
+            session.went_idle = true;
         }
     }
 
     /// Reset to idle state
-    pub async fn reset(&self) {
-        let mut state = self.state.write().await;
-        *state = TimerState::Idle;
-        self.remaining_secs.store(self.work_duration, Ordering::SeqCst);
+    pub fn reset(&self) {
+        *self.session() = Session::idle(self.duration(PomodoroKind::Work));
         self.completed_pomodoros.store(0, Ordering::SeqCst);
-        *self.last_tick.write().await = None;
+        self.set_label(None);
     }
 
-    /// Skip current session (work or break)
-    pub async fn skip(&self) {
-        let state = *self.state.read().await;
-        match state {
-            TimerState::Working => {
+    /// Skip current session (work or break), including a paused one
+    pub fn skip(&self) {
+        match self.snapshot().kind {
+            Some(PomodoroKind::Work) => {
                 // Don't count skipped work sessions
-                self.start_break().await;
+                self.start_break();
             }
-            TimerState::ShortBreak | TimerState::LongBreak => {
-                self.start_work().await;
+            Some(PomodoroKind::ShortBreak | PomodoroKind::LongBreak) => {
+                self.start_work();
             }
-            _ => {}
+            None => {}
         }
     }
 
-    /// Tick the timer (call this every second)
-    /// Returns true if the session just completed
-    pub async fn tick(&self) -> bool {
-        let state = *self.state.read().await;
-
-        if state == TimerState::Idle || state == TimerState::Paused {
+    /// Go back to the work session that just ended for `secs` more. Only applies
+    /// during the break that follows it; returns whether it did.
+    pub fn extend_work(&self, secs: u64) -> bool {
+        let is_break = matches!(
+            self.snapshot().kind,
+            Some(PomodoroKind::ShortBreak | PomodoroKind::LongBreak)
+        );
+        if !is_break || self.completed_pomodoros.load(Ordering::SeqCst) == 0 {
             return false;
         }
 
-        let remaining = self.remaining_secs.load(Ordering::SeqCst);
+        let now = self.clock.now();
+        let work = self.duration(PomodoroKind::Work);
+        let mut session = Session::running(TimerState::Working, work + ChronoDuration::seconds(secs as i64), now);
+        session.elapsed_before = work;
+        *self.session() = session;
+        // It counts again when the extended session completes
+        self.completed_pomodoros.fetch_sub(1, Ordering::SeqCst);
+        true
+    }
 
-        if remaining > 0 {
-            self.remaining_secs.fetch_sub(1, Ordering::SeqCst);
-            false
-        } else {
-            // Session complete
-            match state {
-                TimerState::Working => {
-                    self.completed_pomodoros.fetch_add(1, Ordering::SeqCst);
-                    self.start_break().await;
+    /// Check the clock (call this every second or so).
+    /// Returns how the session ended if it just ran out: completed, or skipped
+    /// for a break the user never stepped away during. The next session starts
+    /// now, or waits paused when auto-start is off for it.
+    pub fn tick(&self) -> Option<PomodoroOutcome> {
+        let now = self.clock.now();
+        let (state, went_idle) = {
+            let session = self.session();
+            if !session.is_running() || session.remaining_secs(now) > 0 {
+                return None;
+            }
+            (session.state, session.went_idle)
+        };
+
+        // Session complete
+        let settings = self.settings();
+        let mut outcome = PomodoroOutcome::Completed;
+        match state {
+            TimerState::Working => {
+                self.completed_pomodoros.fetch_add(1, Ordering::SeqCst);
+                if settings.auto_start_breaks {
+                    self.start_break();
+                } else {
+                    let kind = self.next_break();
+                    *self.session() = Session::queued(kind.state(), self.duration(kind));
                 }
-                TimerState::ShortBreak | TimerState::LongBreak => {
-                    self.start_work().await;
+            }
+            TimerState::ShortBreak | TimerState::LongBreak => {
+                if settings.break_idle_secs > 0 && !went_idle {
+                    outcome = PomodoroOutcome::Skipped;
+                }
+                if settings.auto_start_work {
+                    self.start_work();
+                } else {
+                    *self.session() = Session::queued(TimerState::Working, self.duration(PomodoroKind::Work));
                 }
-                _ => {}
             }
-            true
+            _ => {}
+        }
+        Some(outcome)
+    }
+
+    /// Restore a session that was running before a restart
+    pub fn restore(&self, kind: PomodoroKind, remaining_secs: u64, paused: bool) {
+        let now = self.clock.now();
+        let duration = self.duration(kind);
+        let remaining = ChronoDuration::seconds(remaining_secs as i64).min(duration);
+        let mut session = Session::running(kind.state(), duration, now);
+        session.elapsed_before = duration - remaining;
+        if paused {
+            session.pause_at(now);
+        }
+        *self.session() = session;
+    }
+
+    /// Set the completed pomodoro count (e.g. today's total after a restart)
+    pub fn set_completed_pomodoros(&self, completed: u64) {
+        self.completed_pomodoros.store(completed, Ordering::SeqCst);
+    }
+
+    /// Planned length of a session kind, in seconds
+    pub fn duration_for(&self, kind: PomodoroKind) -> u64 {
+        let settings = self.settings();
+        match kind {
+            PomodoroKind::Work => settings.work_secs,
+            PomodoroKind::ShortBreak => settings.short_break_secs,
+            PomodoroKind::LongBreak => settings.long_break_secs,
         }
     }
 
+    /// Current session kind, pause flag and remaining time
+    pub fn snapshot(&self) -> PomodoroSnapshot {
+        let now = self.clock.now();
+        let session = self.session();
+        let (kind, paused) = match session.state {
+            TimerState::Paused => (session.state_before_pause.kind(), true),
+            other => (other.kind(), false),
+        };
+        PomodoroSnapshot {
+            kind,
+            paused,
+            remaining_secs: session.remaining_secs(now),
+        }
+    }
+
+    /// Kind of the session that is counting down right now, for tagging tracked
+    /// activity; `None` when idle or paused
+    pub fn running_kind(&self) -> Option<PomodoroKind> {
+        let session = self.session();
+        session.running_since.and(session.state.kind())
+    }
+
     /// Get current state
-    pub async fn get_state(&self) -> TimerState {
-        *self.state.read().await
+    pub fn get_state(&self) -> TimerState {
+        self.session().state
     }
 
     /// Get remaining seconds
     pub fn get_remaining_secs(&self) -> u64 {
-        self.remaining_secs.load(Ordering::SeqCst)
+        self.session().remaining_secs(self.clock.now())
     }
 
     /// Get completed pomodoros count
@@ -196,25 +780,36 @@ impl PomodoroTimer {
 
     /// Check if timer is enabled
     pub fn is_enabled(&self) -> bool {
-        self.enabled.load(Ordering::SeqCst)
-    }
-
-    /// Enable/disable the timer
-    pub fn set_enabled(&self, enabled: bool) {
-        self.enabled.store(enabled, Ordering::SeqCst);
+        self.settings().enabled
     }
 
     /// Format remaining time as MM:SS
     pub fn format_remaining(&self) -> String {
-        let secs = self.remaining_secs.load(Ordering::SeqCst);
+        let secs = self.get_remaining_secs();
         let mins = secs / 60;
         let secs = secs % 60;
         format!("{:02}:{:02}", mins, secs)
     }
 
+    /// Status for the API and the CLI
+    pub fn status(&self) -> PomodoroStatus {
+        let snapshot = self.snapshot();
+        PomodoroStatus {
+            state: self.get_state().as_str().to_string(),
+            kind: snapshot.kind.map(|k| k.as_str().to_string()),
+            remaining_secs: snapshot.remaining_secs,
+            remaining_formatted: self.format_remaining(),
+            completed_pomodoros: self.get_completed_pomodoros(),
+            daily_target: self.settings().daily_target,
+            enabled: self.is_enabled(),
+            mode: self.mode(),
+            label: self.label(),
+        }
+    }
+
     /// Get status for tray display
-    pub async fn get_tray_status(&self) -> Option<String> {
-        let state = *self.state.read().await;
+    pub fn get_tray_status(&self) -> Option<String> {
+        let state = self.session().state;
         match state {
             TimerState::Idle => None,
             TimerState::Working => Some(format!(" | {} W", self.format_remaining())),
@@ -239,44 +834,802 @@ pub fn create_shared_pomodoro() -> SharedPomodoro {
     Arc::new(PomodoroTimer::new())
 }
 
+/// Apply `[pomodoro]` and the active mode's overrides, e.g. after a config reload
+pub fn apply_config(timer: &PomodoroTimer, config: &Config) {
+    let mode = timer.mode().filter(|name| config.mode(name).is_some());
+    timer.apply_settings(PomodoroSettings::from_config(config, mode.as_deref()));
+}
+
+/// Switch to a mode's pomodoro overrides (`None` goes back to plain `[pomodoro]`)
+pub fn activate_mode(timer: &PomodoroTimer, config: &Config, mode: Option<&str>) -> Result<()> {
+    let mode = match mode {
+        Some(name) => {
+            let found = config
+                .mode(name)
+                .ok_or_else(|| anyhow::anyhow!("no mode named '{}' in config", name))?;
+            Some(found.name.clone())
+        }
+        None => None,
+    };
+    timer.set_mode(mode);
+    apply_config(timer, config);
+    Ok(())
+}
+
+/// Notification for the end of a `finished` session; the timer has already moved on
+pub fn transition_notification(timer: &PomodoroTimer, finished: PomodoroKind) -> Notification {
+    let next = timer.snapshot();
+    let waiting = next.paused;
+    let mins = next.remaining_secs.div_ceil(60);
+
+    let (summary, body, actions) = match finished {
+        PomodoroKind::Work => {
+            let settings = timer.settings();
+            let break_name = match next.kind {
+                Some(PomodoroKind::LongBreak) => "long break",
+                _ => "break",
+            };
+            let progress = format!(
+                "{} of {} pomodoros today.",
+                timer.get_completed_pomodoros(),
+                settings.daily_target
+            );
+            if waiting {
+                (
+                    "Pomodoro complete".to_string(),
+                    format!("Time for a {} min {}. {}", mins, break_name, progress),
+                    vec![NotifyAction::StartBreak, NotifyAction::Skip, NotifyAction::Extend],
+                )
+            } else {
+                (
+                    "Pomodoro complete".to_string(),
+                    format!("{} min {} started. {}", mins, break_name, progress),
+                    vec![NotifyAction::Skip, NotifyAction::Extend],
+                )
+            }
+        }
+        PomodoroKind::ShortBreak | PomodoroKind::LongBreak => {
+            if waiting {
+                (
+                    "Break over".to_string(),
+                    "Ready for the next pomodoro?".to_string(),
+                    vec![NotifyAction::StartWork],
+                )
+            } else {
+                (
+                    "Break over".to_string(),
+                    format!("Next {} min pomodoro started.", mins),
+                    Vec::new(),
+                )
+            }
+        }
+    };
+
+    Notification {
+        topic: Topic::Pomodoro,
+        summary,
+        body,
+        actions,
+        urgent: false,
+    }
+}
+
+/// Reminds, every `every`, about a break that is due but not being taken
+/// (waiting for a start, or paused)
+pub struct BreakReminder {
+    every: ChronoDuration,
+    due_since: Option<DateTime<Local>>,
+    last_sent: Option<DateTime<Local>>,
+}
+
+impl BreakReminder {
+    pub fn new(every_mins: u64) -> Self {
+        Self {
+            every: ChronoDuration::minutes(every_mins as i64),
+            due_since: None,
+            last_sent: None,
+        }
+    }
+
+    /// Change how often to remind, e.g. after a config reload
+    pub fn set_interval(&mut self, every_mins: u64) {
+        self.every = ChronoDuration::minutes(every_mins as i64);
+    }
+
+    pub fn check(&mut self, timer: &PomodoroTimer, now: DateTime<Local>) -> Option<Notification> {
+        let snapshot = timer.snapshot();
+        let break_waiting = snapshot.paused
+            && matches!(snapshot.kind, Some(PomodoroKind::ShortBreak | PomodoroKind::LongBreak));
+        if !break_waiting || self.every <= ChronoDuration::zero() {
+            self.due_since = None;
+            self.last_sent = None;
+            return None;
+        }
+
+        let due_since = *self.due_since.get_or_insert(now);
+        if now - self.last_sent.unwrap_or(due_since) < self.every {
+            return None;
+        }
+        self.last_sent = Some(now);
+
+        Some(Notification {
+            topic: Topic::Pomodoro,
+            summary: "Break overdue".to_string(),
+            body: format!(
+                "Your break has been waiting for {}. Step away for {} min.",
+                format_duration((now - due_since).num_seconds()),
+                snapshot.remaining_secs.div_ceil(60)
+            ),
+            actions: vec![NotifyAction::StartBreak, NotifyAction::Skip],
+            urgent: true,
+        })
+    }
+}
+
+/// A timer control, from the web API or the tray menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum PomodoroCommand {
+    /// Start a work session for `label` (`None` for an unlabelled one)
+    Start { label: Option<String> },
+    Pause,
+    Resume,
+    Skip,
+    Reset,
+}
+
+/// Apply a timer control and record the change. Starting over while a
+/// session runs abandons it; `Start` does nothing while the timer is off.
+pub fn run_command(timer: &PomodoroTimer, db_path: &Path, command: PomodoroCommand) -> Result<()> {
+    let closed = match command {
+        PomodoroCommand::Start { label } => {
+            if !timer.is_enabled() {
+                return Ok(());
+            }
+            let running = timer.snapshot().kind.is_some();
+            timer.set_label(label);
+            timer.start_work();
+            running.then_some(PomodoroOutcome::Interrupted)
+        }
+        PomodoroCommand::Pause => {
+            timer.pause();
+            None
+        }
+        PomodoroCommand::Resume => {
+            timer.resume();
+            None
+        }
+        PomodoroCommand::Skip => {
+            timer.skip();
+            Some(PomodoroOutcome::Skipped)
+        }
+        PomodoroCommand::Reset => {
+            timer.reset();
+            Some(PomodoroOutcome::Interrupted)
+        }
+    };
+    record_transition(timer, db_path, closed)
+}
+
+/// Apply a clicked notification button to the timer and record the change
+pub fn handle_action(timer: &PomodoroTimer, db_path: &Path, action: NotifyAction) -> Result<()> {
+    let snapshot = timer.snapshot();
+    match action {
+        NotifyAction::StartBreak | NotifyAction::StartWork => {
+            let wanted_break = action == NotifyAction::StartBreak;
+            let is_break = matches!(snapshot.kind, Some(PomodoroKind::ShortBreak | PomodoroKind::LongBreak));
+            if snapshot.paused && is_break == wanted_break {
+                timer.resume();
+            } else if snapshot.kind.is_none() && !wanted_break {
+                timer.start_work();
+            } else {
+                return Ok(());
+            }
+            record_transition(timer, db_path, None)
+        }
+        NotifyAction::Skip => {
+            if snapshot.kind.is_none() {
+                return Ok(());
+            }
+            timer.skip();
+            record_transition(timer, db_path, Some(PomodoroOutcome::Skipped))
+        }
+        NotifyAction::Extend => {
+            if !timer.extend_work(EXTEND_SECS) {
+                return Ok(());
+            }
+            Storage::open(db_path)?.extend_last_work_pomodoro(EXTEND_SECS as i64)?;
+            record_transition(timer, db_path, None)
+        }
+    }
+}
+
+/// Mirror the timer into the `pomodoro_session` table after a state change.
+/// `closed` is how the previous session ended, if the change ended one.
+pub fn record_transition(
+    timer: &PomodoroTimer,
+    db_path: &Path,
+    closed: Option<PomodoroOutcome>,
+) -> Result<()> {
+    let snapshot = timer.snapshot();
+    let storage = Storage::open(db_path)?;
+    let now = Local::now();
+
+    let mut open = storage.get_open_pomodoro()?;
+    if let Some(session) = &open {
+        // A session of a different kind means the timer moved on without telling us
+        let outcome = closed.or_else(|| {
+            (snapshot.kind != Some(session.kind)).then_some(PomodoroOutcome::Interrupted)
+        });
+        if let Some(outcome) = outcome {
+            let remaining = match outcome {
+                PomodoroOutcome::Completed => 0,
+                _ => session.remaining_at(now),
+            };
+            storage.finish_pomodoro(session.id, outcome, remaining, now)?;
+            open = None;
+        }
+    }
+
+    let Some(kind) = snapshot.kind else {
+        return Ok(());
+    };
+    let remaining = snapshot.remaining_secs as i64;
+    let id = match open {
+        Some(session) => session.id,
+        None => storage.start_pomodoro(kind, timer.label().as_deref(), timer.duration_for(kind) as i64, now)?,
+    };
+    storage.checkpoint_pomodoro(id, remaining, snapshot.paused, now)?;
+    Ok(())
+}
+
+/// Log an interruption against the running work session
+pub fn log_interruption(db_path: &Path, kind: InterruptionKind, note: Option<&str>) -> Result<()> {
+    let storage = Storage::open(db_path)?;
+    let session = storage
+        .get_open_pomodoro()?
+        .filter(|s| s.kind == PomodoroKind::Work)
+        .ok_or_else(|| anyhow::anyhow!("no work session is running"))?;
+    let note = note.map(str::trim).filter(|n| !n.is_empty());
+    storage.add_pomodoro_interruption(session.id, kind, note, Local::now())?;
+    Ok(())
+}
+
+/// What a work session was spent on: tracked time per app and window title
+/// while it ran, and what interrupted it
+#[derive(Debug, Clone)]
+pub struct PomodoroSummary {
+    pub session: PomodoroSession,
+    /// (app, seconds), most first
+    pub apps: Vec<(String, i64)>,
+    /// (app, window title, seconds), most first
+    pub titles: Vec<(String, String, i64)>,
+    pub interruptions: Vec<PomodoroInterruption>,
+}
+
+impl PomodoroSummary {
+    /// Summarise `session`, clipping tracked activity to its start and end
+    pub fn load(storage: &Storage, session: PomodoroSession, now: DateTime<Local>) -> Result<Self> {
+        let start = session.started_at;
+        let end = session.end_or(now);
+        // A row that started before the session can still run into it
+        let range = TimeRange { start: start - ChronoDuration::days(1), end };
+
+        let mut apps: HashMap<String, i64> = HashMap::new();
+        let mut titles: HashMap<(String, String), i64> = HashMap::new();
+        for record in storage.get_range_timeline(&range)? {
+            let secs = (record.end_time().min(end) - record.started_at.max(start)).num_seconds();
+            if secs <= 0 {
+                continue;
+            }
+            *apps.entry(record.app_name.clone()).or_default() += secs;
+            *titles.entry((record.app_name, record.window_title)).or_default() += secs;
+        }
+
+        let mut apps: Vec<(String, i64)> = apps.into_iter().collect();
+        apps.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let mut titles: Vec<(String, String, i64)> =
+            titles.into_iter().map(|((app, title), secs)| (app, title, secs)).collect();
+        titles.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(&b.1)));
+
+        let interruptions = storage.get_pomodoro_interruptions(session.id)?;
+        Ok(Self { session, apps, titles, interruptions })
+    }
+
+    /// One line for notifications, e.g. "VS Code 18m, Brave 5m · 1 interruption"
+    pub fn headline(&self) -> String {
+        let mut line = if self.apps.is_empty() {
+            "No tracked apps".to_string()
+        } else {
+            self.apps
+                .iter()
+                .take(3)
+                .map(|(app, secs)| format!("{} {}", app, format_duration(*secs)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self.interruptions.len() {
+            0 => {}
+            1 => line.push_str(" · 1 interruption"),
+            n => line.push_str(&format!(" · {} interruptions", n)),
+        }
+        line
+    }
+}
+
+/// Summary of work session `id`, or of the latest one
+pub fn load_summary(db_path: &Path, id: Option<i64>) -> Result<PomodoroSummary> {
+    let storage = Storage::open(db_path)?;
+    let session = match id {
+        Some(id) => storage.get_pomodoro(id)?,
+        None => storage.get_last_work_pomodoro()?,
+    }
+    .ok_or_else(|| anyhow::anyhow!("no such pomodoro"))?;
+    PomodoroSummary::load(&storage, session, Local::now())
+}
+
+/// Restore today's completed count and any in-flight session after a restart.
+/// A session whose time ran out while the daemon was down is closed as interrupted.
+pub fn restore_from_db(timer: &PomodoroTimer, db_path: &Path) -> Result<()> {
+    let storage = Storage::open(db_path)?;
+    let now = Local::now();
+
+    let completed: u32 = storage
+        .get_pomodoro_days(&TimeRange::today())?
+        .iter()
+        .map(|d| d.completed)
+        .sum();
+    timer.set_completed_pomodoros(completed as u64);
+
+    let Some(session) = storage.get_open_pomodoro()? else {
+        return Ok(());
+    };
+    let remaining = session.remaining_at(now);
+    if remaining > 0 {
+        timer.restore(session.kind, remaining as u64, session.paused);
+        timer.set_label(session.label.clone());
+        tracing::info!(
+            "Restored {} pomodoro with {}s left{}",
+            session.kind.as_str(),
+            remaining,
+            if session.paused { " (paused)" } else { "" }
+        );
+    } else {
+        let deadline = session.updated_at + ChronoDuration::seconds(session.remaining_secs);
+        storage.finish_pomodoro(session.id, PomodoroOutcome::Interrupted, 0, deadline.min(now))?;
+    }
+    Ok(())
+}
+
+/// Restore the timer, then keep it ticking in the background: sessions are
+/// saved as they end, notifications go out and `config.toml` edits from
+/// `reloads` apply. With `notifications`, their buttons drive the timer.
+pub fn start(
+    timer: SharedPomodoro,
+    db_path: PathBuf,
+    notifications: Option<(Notifier, ActionReceiver)>,
+    mut reloads: watch::Receiver<Config>,
+) {
+    let config = reloads.borrow_and_update().clone();
+    apply_config(&timer, &config);
+
+    // Pick up a timer that was running before the restart
+    if let Err(e) = restore_from_db(&timer, &db_path) {
+        tracing::warn!("Failed to restore pomodoro timer: {}", e);
+    }
+
+    let notifier = notifications.as_ref().map(|(notifier, _)| notifier.clone());
+    if let Some((_, mut actions)) = notifications {
+        let timer = timer.clone();
+        let db_path = db_path.clone();
+        tokio::spawn(async move {
+            while let Some(action) = actions.recv().await {
+                if let Err(e) = handle_action(&timer, &db_path, action) {
+                    tracing::warn!("Failed to apply notification action: {}", e);
+                }
+            }
+        });
+    }
+    let mut reminder = BreakReminder::new(config.notifications.break_overdue_mins);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        // The timer reads the clock itself, so ticks missed while suspended need no catching up
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            // Re-apply [pomodoro] and the reminder interval when config.toml changes
+            if reloads.has_changed().unwrap_or(false) {
+                let config = reloads.borrow_and_update();
+                apply_config(&timer, &config);
+                reminder.set_interval(config.notifications.break_overdue_mins);
+            }
+            let finished = timer.snapshot().kind;
+            if let Some(outcome) = timer.tick() {
+                tracing::info!("Pomodoro session {}", outcome.as_str());
+                if let Err(e) = record_transition(&timer, &db_path, Some(outcome)) {
+                    tracing::warn!("Failed to save pomodoro session: {}", e);
+                }
+                let summary = match finished {
+                    Some(PomodoroKind::Work) => load_summary(&db_path, None).ok(),
+                    _ => None,
+                };
+                if let Some(summary) = &summary {
+                    tracing::info!("Pomodoro summary: {}", summary.headline());
+                }
+                if let (Some(notifier), Some(kind)) = (&notifier, finished) {
+                    let mut notification = transition_notification(&timer, kind);
+                    if let Some(summary) = &summary {
+                        notification.body = format!("{}\n{}", notification.body, summary.headline());
+                    }
+                    notifier.send(notification);
+                }
+            }
+            if let Some(notifier) = &notifier {
+                if let Some(notification) = reminder.check(&timer, Local::now()) {
+                    notifier.send(notification);
+                }
+            }
+        }
+    });
+}
+
+/// Current and longest streak of consecutive days with a completed pomodoro.
+/// `days` must be sorted ascending; today not being done yet keeps the streak alive.
+pub fn streaks(days: &[NaiveDate], today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for &day in days {
+        run = match prev {
+            Some(p) if day == p + ChronoDuration::days(1) => run + 1,
+            Some(p) if day == p => run,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        prev = Some(day);
+    }
+
+    let current = match prev {
+        Some(last) if last == today || last == today - ChronoDuration::days(1) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::{Importer, NewSession};
 
-    #[tokio::test]
-    async fn test_timer_start_work() {
+    #[test]
+    fn test_timer_start_work() {
         let timer = PomodoroTimer::new();
-        timer.start_work().await;
+        timer.start_work();
 
-        assert_eq!(timer.get_state().await, TimerState::Working);
+        assert_eq!(timer.get_state(), TimerState::Working);
         assert_eq!(timer.get_remaining_secs(), DEFAULT_WORK_MINS * 60);
     }
 
-    #[tokio::test]
-    async fn test_timer_pause_resume() {
+    #[test]
+    fn test_timer_pause_resume() {
         let timer = PomodoroTimer::new();
-        timer.start_work().await;
-        timer.pause().await;
+        timer.start_work();
+        timer.pause();
 
-        assert_eq!(timer.get_state().await, TimerState::Paused);
+        assert_eq!(timer.get_state(), TimerState::Paused);
 
-        timer.resume().await;
-        assert_eq!(timer.get_state().await, TimerState::Working);
+        timer.resume();
+        assert_eq!(timer.get_state(), TimerState::Working);
     }
 
-    #[tokio::test]
-    async fn test_timer_tick() {
-        let timer = PomodoroTimer::with_durations(1, 1, 1); // 1 minute each
-        timer.start_work().await;
+    #[test]
+    fn test_timer_tick() {
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::with_durations(1, 1, 1).with_clock(clock.clone()); // 1 minute each
+        timer.start_work();
 
-        // Tick 60 times
-        for _ in 0..60 {
-            timer.tick().await;
-        }
+        clock.advance(59);
+        assert_eq!(timer.tick(), None);
+        assert_eq!(timer.get_remaining_secs(), 1);
+
+        clock.advance(1);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
 
         // Should have switched to break
-        let state = timer.get_state().await;
+        let state = timer.get_state();
         assert!(state == TimerState::ShortBreak || state == TimerState::LongBreak);
         assert_eq!(timer.get_completed_pomodoros(), 1);
     }
+
+    #[test]
+    fn test_missed_ticks_do_not_drift() {
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::new().with_clock(clock.clone());
+        timer.start_work();
+
+        // A stalled runtime or suspend: no ticks for 10 minutes
+        clock.advance(600);
+        assert_eq!(timer.format_remaining(), "15:00");
+
+        // Paused time does not count
+        timer.pause();
+        clock.advance(3600);
+        timer.resume();
+        assert_eq!(timer.get_remaining_secs(), 900);
+    }
+
+    #[test]
+    fn test_full_cycle_reaches_long_break() {
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::new().with_clock(clock.clone());
+        timer.start_work();
+
+        for round in 1..=POMODOROS_UNTIL_LONG_BREAK {
+            clock.advance((DEFAULT_WORK_MINS * 60) as i64);
+            assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+            let expected = if round == POMODOROS_UNTIL_LONG_BREAK {
+                TimerState::LongBreak
+            } else {
+                TimerState::ShortBreak
+            };
+            assert_eq!(timer.get_state(), expected);
+
+            clock.advance(timer.get_remaining_secs() as i64);
+            assert!(timer.tick().is_some());
+            assert_eq!(timer.get_state(), TimerState::Working);
+        }
+        assert_eq!(timer.get_completed_pomodoros(), POMODOROS_UNTIL_LONG_BREAK as u64);
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let timer = PomodoroTimer::new();
+        timer.restore(PomodoroKind::ShortBreak, 90, true);
+
+        let snapshot = timer.snapshot();
+        assert_eq!(snapshot.kind, Some(PomodoroKind::ShortBreak));
+        assert!(snapshot.paused);
+        assert_eq!(snapshot.remaining_secs, 90);
+
+        timer.resume();
+        assert_eq!(timer.get_state(), TimerState::ShortBreak);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let d = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        let days = [d(1), d(2), d(3), d(5), d(6)];
+        assert_eq!(streaks(&days, d(6)), (2, 3));
+        assert_eq!(streaks(&days, d(7)), (2, 3));
+        assert_eq!(streaks(&days, d(8)), (0, 3));
+        assert_eq!(streaks(&[], d(8)), (0, 0));
+    }
+
+    #[test]
+    fn test_settings_and_mode_overrides() {
+        let config: Config = toml::from_str(
+            r#"
+            idle_timeout_secs = 300
+            poll_interval_secs = 5
+            apps = []
+
+            [pomodoro]
+            auto_start_breaks = false
+
+            [[modes]]
+            name = "Work"
+            [modes.focus]
+            pomodoro = true
+            work_duration = 50
+
+            [[modes]]
+            name = "Meeting"
+            [modes.focus]
+            pomodoro = false
+            "#,
+        )
+        .unwrap();
+
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::new().with_clock(clock.clone());
+        apply_config(&timer, &config);
+        assert_eq!(timer.get_remaining_secs(), 25 * 60);
+
+        activate_mode(&timer, &config, Some("work")).unwrap();
+        assert_eq!(timer.mode().as_deref(), Some("Work"));
+        assert_eq!(timer.get_remaining_secs(), 50 * 60);
+        assert!(activate_mode(&timer, &config, Some("Nap")).is_err());
+
+        // Without auto-start the break waits, paused, for a resume
+        timer.start_work();
+        clock.advance(50 * 60);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+        assert_eq!(timer.get_state(), TimerState::Paused);
+        assert_eq!(timer.snapshot().kind, Some(PomodoroKind::ShortBreak));
+        clock.advance(600);
+        assert_eq!(timer.get_remaining_secs(), 5 * 60);
+
+        activate_mode(&timer, &config, Some("Meeting")).unwrap();
+        assert!(!timer.is_enabled());
+    }
+
+    #[test]
+    fn test_extend_and_break_reminder() {
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::with_settings(PomodoroSettings {
+            auto_start_breaks: false,
+            ..PomodoroSettings::default()
+        })
+        .with_clock(clock.clone());
+
+        // Nothing to extend before a work session has finished
+        timer.start_work();
+        assert!(!timer.extend_work(EXTEND_SECS));
+
+        clock.advance(25 * 60);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+        let notification = transition_notification(&timer, PomodoroKind::Work);
+        assert_eq!(notification.actions[0], NotifyAction::StartBreak);
+        assert!(notification.body.starts_with("Time for a 5 min break. 1 of 8"));
+
+        let mut reminder = BreakReminder::new(5);
+        assert!(reminder.check(&timer, clock.now()).is_none());
+        clock.advance(5 * 60);
+        assert!(reminder.check(&timer, clock.now()).is_some());
+        assert!(reminder.check(&timer, clock.now()).is_none());
+
+        // A reloaded interval counts from the last reminder
+        reminder.set_interval(2);
+        clock.advance(2 * 60);
+        assert!(reminder.check(&timer, clock.now()).is_some());
+
+        assert!(timer.extend_work(EXTEND_SECS));
+        assert_eq!(timer.get_state(), TimerState::Working);
+        assert_eq!(timer.get_remaining_secs(), EXTEND_SECS);
+        assert_eq!(timer.get_completed_pomodoros(), 0);
+        assert!(reminder.check(&timer, clock.now()).is_none());
+
+        clock.advance(EXTEND_SECS as i64);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+        assert_eq!(timer.get_completed_pomodoros(), 1);
+    }
+
+    #[test]
+    fn test_idle_and_tracking_pauses() {
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::new().with_clock(clock.clone());
+        timer.start_work();
+        assert_eq!(timer.running_kind(), Some(PomodoroKind::Work));
+
+        // Idle detection notices 5 minutes in that the user left 2 minutes ago
+        clock.advance(300);
+        assert!(timer.pause_for_idle(120));
+        assert_eq!(timer.get_remaining_secs(), 22 * 60);
+        assert_eq!(timer.running_kind(), None);
+        clock.advance(600);
+        assert!(timer.resume_auto());
+        assert_eq!(timer.get_state(), TimerState::Working);
+
+        // A pause the user asked for is not undone by coming back
+        assert!(timer.pause_for_tracking());
+        timer.pause();
+        assert!(!timer.resume_auto());
+        timer.resume();
+
+        // A break at the desk is recorded as skipped, one spent away as completed
+        clock.advance(22 * 60);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+        assert!(!timer.pause_for_idle(600)); // breaks keep running
+        clock.advance(5 * 60);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Skipped));
+
+        clock.advance(25 * 60);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+        timer.note_idle(90);
+        clock.advance(5 * 60);
+        assert_eq!(timer.tick(), Some(PomodoroOutcome::Completed));
+
+        timer.apply_settings(PomodoroSettings {
+            pause_on_idle: false,
+            ..PomodoroSettings::default()
+        });
+        assert!(!timer.pause_for_idle(600));
+    }
+
+    #[test]
+    fn test_run_command_records_sessions() {
+        let db_path = std::env::temp_dir().join(format!("flowmode-pomodoro-{}.db", std::process::id()));
+        let timer = PomodoroTimer::new();
+
+        let start = PomodoroCommand::Start { label: Some(" Report ".to_string()) };
+        run_command(&timer, &db_path, start).unwrap();
+        run_command(&timer, &db_path, PomodoroCommand::Pause).unwrap();
+        let open = Storage::open(&db_path).unwrap().get_open_pomodoro().unwrap().unwrap();
+        assert_eq!((open.kind, open.paused), (PomodoroKind::Work, true));
+        assert_eq!(open.label.as_deref(), Some("Report"));
+
+        log_interruption(&db_path, InterruptionKind::External, Some("phone call")).unwrap();
+
+        run_command(&timer, &db_path, PomodoroCommand::Skip).unwrap();
+        let open = Storage::open(&db_path).unwrap().get_open_pomodoro().unwrap().unwrap();
+        assert_eq!(open.kind, PomodoroKind::ShortBreak);
+        assert!(log_interruption(&db_path, InterruptionKind::Internal, None).is_err());
+        let summary = load_summary(&db_path, None).unwrap();
+        assert_eq!(summary.session.label.as_deref(), Some("Report"));
+        assert_eq!(summary.interruptions.len(), 1);
+        assert_eq!(summary.interruptions[0].note.as_deref(), Some("phone call"));
+
+        run_command(&timer, &db_path, PomodoroCommand::Reset).unwrap();
+        assert!(Storage::open(&db_path).unwrap().get_open_pomodoro().unwrap().is_none());
+        assert_eq!(timer.get_state(), TimerState::Idle);
+
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[test]
+    fn test_summary_clips_activity_to_session() {
+        let storage = Storage::open_in_memory().unwrap();
+        let start = Local::now() - ChronoDuration::hours(2);
+        let minutes = |m: i64| start + ChronoDuration::minutes(m);
+        let session = |app: &str, title: &str, from: i64, to: i64| NewSession {
+            app_name: app.to_string(),
+            category: "Development".to_string(),
+            window_title: title.to_string(),
+            started_at: minutes(from),
+            ended_at: minutes(to),
+        };
+        storage
+            .import_sessions(
+                &[
+                    session("VS Code", "main.rs", -10, 15),
+                    session("Brave", "docs.rs", 15, 20),
+                    session("VS Code", "lib.rs", 20, 40),
+                ],
+                Importer::Timewarrior,
+            )
+            .unwrap();
+
+        let id = storage.start_pomodoro(PomodoroKind::Work, Some("Parser"), 1500, start).unwrap();
+        storage.add_pomodoro_interruption(id, InterruptionKind::Internal, None, minutes(16)).unwrap();
+        storage.finish_pomodoro(id, PomodoroOutcome::Completed, 0, minutes(25)).unwrap();
+
+        let summary = PomodoroSummary::load(&storage, storage.get_pomodoro(id).unwrap().unwrap(), Local::now()).unwrap();
+        assert_eq!(summary.apps, vec![("VS Code".to_string(), 20 * 60), ("Brave".to_string(), 5 * 60)]);
+        assert_eq!(summary.titles[0], ("VS Code".to_string(), "main.rs".to_string(), 15 * 60));
+        assert_eq!(summary.headline(), "VS Code 20m, Brave 5m · 1 interruption");
+    }
+
+    #[test]
+    fn test_status_templates() {
+        let clock = ManualClock::new();
+        let timer = PomodoroTimer::new().with_clock(clock.clone());
+        let idle = timer.status();
+        assert_eq!(idle.render("{icon} {phase} {remaining}", false), "Idle 25:00");
+        assert_eq!(idle.summary_line(), "No pomodoro running · 0/8 today");
+
+        timer.set_label(Some("Report".to_string()));
+        timer.start_work();
+        clock.advance(61);
+        timer.pause();
+        let status = timer.status();
+        assert_eq!(status.render("{icon} {remaining} ({minutes}m) {label}", false), "⏸ 23:59 (24m) Report");
+        assert_eq!(
+            status.render(r#"{"text": "{remaining}", "class": "{state}"}"#, true),
+            r#"{"text": "23:59", "class": "paused"}"#
+        );
+
+        // Labels are filled in as they are, and escaped for a JSON template
+        timer.set_label(Some(r#"Fix "{state}" in C:\src"#.to_string()));
+        let quoted = timer.status();
+        assert_eq!(quoted.render("{label} {unknown}", false), r#"Fix "{state}" in C:\src {unknown}"#);
+        let line = quoted.render(r#"{"text": "{label}", "class": "{state}"}"#, true);
+        assert_eq!(line, r#"{"text": "Fix \"{state}\" in C:\\src", "class": "paused"}"#);
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["text"], r#"Fix "{state}" in C:\src"#);
+        assert_eq!(status.summary_line(), "⏸ Work 23:59 left (paused) · Report · 0/8 today");
+
+        // What the CLI reads back from the daemon
+        let parsed: PomodoroStatus = serde_json::from_str(&serde_json::to_string(&status).unwrap()).unwrap();
+        assert_eq!(parsed.kind.as_deref(), Some("work"));
+    }
 }
@@ -0,0 +1,566 @@
+//! Projects: where a session's work belongs
+//!
+//! Window titles only say where a shell is when it bothers to set them. Instead,
+//! follow the terminal's process tree in `/proc` down to the foreground process
+//! of one of its shells, read its `cwd`, and walk up to the enclosing git
+//! repository. When that process is a tmux client, ask tmux for the path of the
+//! client's current pane, or else look at the panes of the tmux server. The
+//! `/proc` root is configurable so tests can use a fake one.
+//!
+//! `[[projects]]` rules then map titles, repos, browser domains and Teams
+//! channels to named projects (`ProjectMatcher`).
+
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::browser;
+use crate::config::{Config, ProjectRule};
+use crate::storage::ActivityRecord;
+use crate::title_parser::ParsedTitle;
+
+/// Name shown for sessions that belong to no project
+pub const UNASSIGNED: &str = "Unassigned";
+
+/// Git repository enclosing a directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitRepo {
+    pub root: PathBuf,
+    /// Directory name of the repository root
+    pub name: String,
+    /// Checked-out branch, or the short commit hash when detached
+    pub branch: Option<String>,
+}
+
+/// Where a terminal window's foreground process is working
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkingDir {
+    pub cwd: PathBuf,
+    pub repo: Option<GitRepo>,
+}
+
+impl WorkingDir {
+    /// Title context for the session: the repository, or else the folder
+    pub fn parsed_title(&self) -> ParsedTitle {
+        match &self.repo {
+            Some(repo) => ParsedTitle {
+                display: match &repo.branch {
+                    Some(branch) => format!("{} ({})", repo.name, branch),
+                    None => repo.name.clone(),
+                },
+                context_type: "project".to_string(),
+                context: repo.name.clone(),
+            },
+            None => {
+                let folder = folder_name(&self.cwd);
+                ParsedTitle {
+                    display: format!("Folder: {}", folder),
+                    context_type: "folder".to_string(),
+                    context: folder,
+                }
+            }
+        }
+    }
+}
+
+/// What project rules look at in a session
+#[derive(Debug, Clone, Copy)]
+pub struct SessionFacts<'a> {
+    pub category: &'a str,
+    pub window_title: &'a str,
+    pub parsed: Option<&'a ParsedTitle>,
+    /// Git repository of a terminal session
+    pub repo: Option<&'a str>,
+    /// Browser tab URL reported by the extension
+    pub url: Option<&'a str>,
+}
+
+impl SessionFacts<'_> {
+    pub fn is_browser(&self) -> bool {
+        self.category.eq_ignore_ascii_case("browser")
+    }
+
+    /// Whether a browser session is on `site` ("acme.com", "github.com/acme").
+    /// With a reported URL the domain is known; otherwise guess from the title.
+    pub fn on_site(&self, site: &str) -> bool {
+        match self.url {
+            Some(url) => browser::url_on_site(url, site),
+            None => {
+                let site = site.to_lowercase();
+                self.window_title.to_lowercase().contains(&site)
+                    || self
+                        .parsed
+                        .filter(|p| p.context_type == "website")
+                        .is_some_and(|p| p.context.to_lowercase().contains(&site))
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a ActivityRecord> for SessionFacts<'a> {
+    fn from(record: &'a ActivityRecord) -> Self {
+        Self {
+            category: &record.category,
+            window_title: &record.window_title,
+            parsed: record.parsed.as_ref(),
+            repo: record.repo.as_deref(),
+            url: record.url.as_deref(),
+        }
+    }
+}
+
+/// A `[[projects]]` entry with its title patterns compiled
+#[derive(Debug)]
+struct CompiledProject {
+    rule: ProjectRule,
+    patterns: Vec<Regex>,
+}
+
+impl CompiledProject {
+    fn matches(&self, facts: &SessionFacts) -> bool {
+        let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+        let context = |context_type: &str| {
+            facts
+                .parsed
+                .filter(|p| p.context_type == context_type)
+                .map(|p| p.context.as_str())
+        };
+
+        let repo = facts.repo.or_else(|| context("project"));
+        if let Some(repo) = repo {
+            if self.rule.repos.iter().any(|r| r.eq_ignore_ascii_case(repo)) {
+                return true;
+            }
+        }
+
+        if facts.is_browser() && self.rule.domains.iter().any(|domain| facts.on_site(domain)) {
+            return true;
+        }
+
+        if let Some(channel) = context("channel") {
+            if self.rule.teams_channels.iter().any(|c| contains(channel, c)) {
+                return true;
+            }
+        }
+
+        self.patterns.iter().any(|re| re.is_match(facts.window_title))
+    }
+}
+
+/// Assigns sessions to the first `[[projects]]` entry with a matching rule
+#[derive(Debug)]
+pub struct ProjectMatcher {
+    projects: Vec<CompiledProject>,
+}
+
+impl ProjectMatcher {
+    /// Compile the configured projects; invalid title patterns are skipped with a warning
+    pub fn from_config(config: &Config) -> Self {
+        let projects = config
+            .projects
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| CompiledProject {
+                patterns: rule
+                    .title_patterns
+                    .iter()
+                    .filter_map(|pattern| match Regex::new(pattern) {
+                        Ok(regex) => Some(regex),
+                        Err(e) => {
+                            tracing::warn!("Skipping pattern '{}' of projects[{}] ({}): {}", pattern, i, rule.name, e);
+                            None
+                        }
+                    })
+                    .collect(),
+                rule: rule.clone(),
+            })
+            .collect();
+        Self { projects }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty()
+    }
+
+    /// Name of the project `facts` belong to, if any
+    pub fn match_session(&self, facts: &SessionFacts) -> Option<&str> {
+        self.projects
+            .iter()
+            .find(|p| p.matches(facts))
+            .map(|p| p.rule.name.as_str())
+    }
+}
+
+/// Process information read from a `/proc`-like directory
+pub struct ProcFs {
+    root: PathBuf,
+    /// Ask a running tmux for its clients' pane paths (only for the real `/proc`)
+    query_tmux: bool,
+}
+
+impl ProcFs {
+    pub fn new() -> Self {
+        Self { query_tmux: true, ..Self::with_root("/proc") }
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), query_tmux: false }
+    }
+
+    /// Working directory of the terminal window owned by `terminal_pid`. With
+    /// several shells (tabs), prefer the one whose folder appears in the window
+    /// title, then the most recently started.
+    pub fn working_dir(&self, terminal_pid: u32, window_title: &str) -> Option<WorkingDir> {
+        let mut candidates: Vec<(u64, PathBuf)> = self
+            .children(terminal_pid)
+            .into_iter()
+            .flat_map(|shell| match self.tmux_client(shell) {
+                Some(client) => self.tmux_cwds(client),
+                None => self
+                    .foreground_cwd(shell)
+                    .map(|cwd| (self.stat(shell).map(|s| s.start_time).unwrap_or(0), cwd))
+                    .into_iter()
+                    .collect(),
+            })
+            .collect();
+        candidates.sort_by_key(|(start, _)| std::cmp::Reverse(*start));
+
+        let cwd = candidates
+            .iter()
+            .find(|(_, cwd)| {
+                let folder = folder_name(cwd);
+                !folder.is_empty() && window_title.contains(&folder)
+            })
+            .or(candidates.first())
+            .map(|(_, cwd)| cwd.clone())?;
+
+        Some(WorkingDir { repo: find_repo(&cwd), cwd })
+    }
+
+    /// `cwd` of the foreground process on `shell`'s terminal, or of the shell itself
+    fn foreground_cwd(&self, shell: u32) -> Option<PathBuf> {
+        let stat = self.stat(shell)?;
+        let foreground = (stat.tpgid > 0).then_some(stat.tpgid as u32);
+        foreground
+            .and_then(|pid| self.cwd(pid))
+            .or_else(|| self.cwd(shell))
+    }
+
+    /// The tmux client running in the foreground on `shell`'s terminal, or
+    /// `shell` itself when the terminal runs tmux directly
+    fn tmux_client(&self, shell: u32) -> Option<u32> {
+        let stat = self.stat(shell)?;
+        let foreground = (stat.tpgid > 0).then_some(stat.tpgid as u32);
+        foreground.into_iter().chain([shell]).find(|pid| self.is_tmux(*pid))
+    }
+
+    /// Working directories behind a tmux client: its current pane's path as
+    /// tmux reports it, else the foreground `cwd` of every pane of the tmux
+    /// server (with the pane shells' start times)
+    fn tmux_cwds(&self, client: u32) -> Vec<(u64, PathBuf)> {
+        let start = self.stat(client).map(|s| s.start_time).unwrap_or(0);
+        if let Some(path) = self.tmux_pane_path(client) {
+            return vec![(start, path)];
+        }
+        self.pids()
+            .into_iter()
+            .filter(|pid| *pid != client && self.is_tmux(*pid))
+            .flat_map(|server| self.children(server))
+            .filter_map(|pane| {
+                let cwd = self.foreground_cwd(pane)?;
+                Some((self.stat(pane).map(|s| s.start_time).unwrap_or(0), cwd))
+            })
+            .collect()
+    }
+
+    /// `#{pane_current_path}` of the pane shown by the tmux client on `client`'s terminal
+    fn tmux_pane_path(&self, client: u32) -> Option<PathBuf> {
+        if !self.query_tmux {
+            return None;
+        }
+        let tty = fs::read_link(self.root.join(client.to_string()).join("fd/0")).ok()?;
+        let output = Command::new("tmux")
+            .arg("display-message")
+            .arg("-p")
+            .arg("-c")
+            .arg(&tty)
+            .arg("#{pane_current_path}")
+            .output()
+            .ok()?;
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !path.is_empty()).then(|| PathBuf::from(path))
+    }
+
+    fn is_tmux(&self, pid: u32) -> bool {
+        self.stat(pid).is_some_and(|s| s.comm.starts_with("tmux"))
+    }
+
+    /// Every process id under the root
+    fn pids(&self) -> Vec<u32> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        entries.flatten().filter_map(|e| e.file_name().to_str()?.parse().ok()).collect()
+    }
+
+    /// Child pids of every thread of `pid`
+    fn children(&self, pid: u32) -> Vec<u32> {
+        let Ok(tasks) = fs::read_dir(self.root.join(pid.to_string()).join("task")) else {
+            return Vec::new();
+        };
+        let mut children: Vec<u32> = tasks
+            .flatten()
+            .filter_map(|task| fs::read_to_string(task.path().join("children")).ok())
+            .flat_map(|list| {
+                list.split_whitespace()
+                    .filter_map(|pid| pid.parse().ok())
+                    .collect::<Vec<u32>>()
+            })
+            .collect();
+        children.sort_unstable();
+        children.dedup();
+        children
+    }
+
+    fn cwd(&self, pid: u32) -> Option<PathBuf> {
+        fs::read_link(self.root.join(pid.to_string()).join("cwd")).ok()
+    }
+
+    fn stat(&self, pid: u32) -> Option<ProcStat> {
+        let stat = fs::read_to_string(self.root.join(pid.to_string()).join("stat")).ok()?;
+        ProcStat::parse(&stat)
+    }
+}
+
+impl Default for ProcFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The fields of `/proc/<pid>/stat` we use
+#[derive(Debug, Clone, PartialEq)]
+struct ProcStat {
+    /// Command name, e.g. "zsh" or "tmux: server"
+    comm: String,
+    /// Foreground process group of the controlling terminal (-1 without one)
+    tpgid: i64,
+    /// Start time in clock ticks after boot
+    start_time: u64,
+}
+
+impl ProcStat {
+    fn parse(stat: &str) -> Option<Self> {
+        // The command name is in parentheses and may contain spaces
+        let end = stat.rfind(')')?;
+        let fields: Vec<&str> = stat[end + 1..].split_whitespace().collect();
+        // After the name: state(3) ppid pgrp session tty_nr tpgid(8) ... starttime(22)
+        Some(Self {
+            comm: stat[stat.find('(')? + 1..end].to_string(),
+            tpgid: fields.get(5)?.parse().ok()?,
+            start_time: fields.get(19)?.parse().ok()?,
+        })
+    }
+}
+
+/// The git repository containing `dir`, if any
+pub fn find_repo(dir: &Path) -> Option<GitRepo> {
+    let root = dir.ancestors().find(|d| d.join(".git").exists())?;
+    let git_dir = resolve_git_dir(&root.join(".git"))?;
+    let branch = fs::read_to_string(git_dir.join("HEAD")).ok().and_then(|head| {
+        let head = head.trim();
+        match head.strip_prefix("ref: ") {
+            Some(reference) => Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string()),
+            None => head.get(..7).map(str::to_string),
+        }
+    });
+    Some(GitRepo {
+        root: root.to_path_buf(),
+        name: folder_name(root),
+        branch,
+    })
+}
+
+/// `.git` is a directory, or for worktrees and submodules a file pointing to one
+fn resolve_git_dir(dot_git: &Path) -> Option<PathBuf> {
+    if dot_git.is_dir() {
+        return Some(dot_git.to_path_buf());
+    }
+    let contents = fs::read_to_string(dot_git).ok()?;
+    let target = PathBuf::from(contents.trim().strip_prefix("gitdir:")?.trim());
+    Some(if target.is_absolute() { target } else { dot_git.parent()?.join(target) })
+}
+
+fn folder_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// A scratch directory with a fake `/proc` and some working directories
+    struct Fixture {
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("flowmode-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self { dir }
+        }
+
+        fn mkdir(&self, path: &str) -> PathBuf {
+            let path = self.dir.join(path);
+            fs::create_dir_all(&path).unwrap();
+            path
+        }
+
+        /// Add process `pid` with its children, foreground group and cwd
+        fn process(&self, pid: u32, comm: &str, children: &[u32], tpgid: i64, start: u64, cwd: Option<&Path>) {
+            let proc_dir = self.mkdir(&format!("proc/{}", pid));
+            let task = self.mkdir(&format!("proc/{}/task/{}", pid, pid));
+            let list: Vec<String> = children.iter().map(u32::to_string).collect();
+            fs::write(task.join("children"), list.join(" ")).unwrap();
+            let stat = format!("{} ({}) S 1 {} {} 34816 {} 4194304 0 0 0 0 0 0 0 0 20 0 1 0 {} 0 0", pid, comm, pid, pid, tpgid, start);
+            fs::write(proc_dir.join("stat"), stat).unwrap();
+            if let Some(cwd) = cwd {
+                symlink(cwd, proc_dir.join("cwd")).unwrap();
+            }
+        }
+
+        fn proc(&self) -> ProcFs {
+            ProcFs::with_root(self.dir.join("proc"))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn test_foreground_process_in_git_repo() {
+        let fx = Fixture::new("proc-git");
+        let repo = fx.mkdir("src/flowmode");
+        fx.mkdir("src/flowmode/.git");
+        fs::write(repo.join(".git/HEAD"), "ref: refs/heads/feature/projects\n").unwrap();
+        let nested = fx.mkdir("src/flowmode/src/web");
+        let home = fx.mkdir("home");
+
+        // Terminal 100 with two tabs: shell 200 running vim (300) in the repo, idle shell 201 at home
+        fx.process(100, "ghostty", &[200, 201], -1, 10, None);
+        fx.process(200, "zsh", &[300], 300, 20, Some(&home));
+        fx.process(300, "nvim", &[], 300, 30, Some(&nested));
+        fx.process(201, "zsh", &[], 201, 40, Some(&home));
+
+        // The title names the repo tab, so it wins over the newer shell
+        let dir = fx.proc().working_dir(100, "nvim — web").unwrap();
+        assert_eq!(dir.cwd, nested);
+        let repo_info = dir.repo.clone().unwrap();
+        assert_eq!((repo_info.name.as_str(), repo_info.branch.as_deref()), ("flowmode", Some("feature/projects")));
+        assert_eq!(dir.parsed_title().display, "flowmode (feature/projects)");
+        assert_eq!(dir.parsed_title().context_type, "project");
+
+        // Without a hint the most recently started shell is used
+        let dir = fx.proc().working_dir(100, "Ghostty").unwrap();
+        assert_eq!((dir.cwd, dir.repo), (home.clone(), None));
+        assert!(fx.proc().working_dir(999, "").is_none());
+    }
+
+    #[test]
+    fn test_tmux_panes() {
+        let fx = Fixture::new("proc-tmux");
+        let repo = fx.mkdir("src/flowmode");
+        fx.mkdir("src/flowmode/.git");
+        fs::write(repo.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        let home = fx.mkdir("home");
+        let notes = fx.mkdir("notes");
+
+        // Terminal 100 runs a shell with a tmux client (300) in the foreground,
+        // terminal 110 runs a tmux client (310) directly; both sit at home
+        fx.process(100, "ghostty", &[200], -1, 10, None);
+        fx.process(200, "zsh", &[300], 300, 20, Some(&home));
+        fx.process(300, "tmux: client", &[], 300, 30, Some(&home));
+        fx.process(110, "alacritty", &[310], -1, 11, None);
+        fx.process(310, "tmux: client", &[], 310, 31, Some(&home));
+        // The server's panes: nvim in the repo, and a newer shell in ~/notes
+        fx.process(400, "tmux: server", &[500, 501], -1, 5, None);
+        fx.process(500, "zsh", &[600], 600, 40, Some(&home));
+        fx.process(600, "nvim", &[], 600, 41, Some(&repo));
+        fx.process(501, "zsh", &[], 501, 50, Some(&notes));
+
+        // The panes' directories count, not the client's
+        let dir = fx.proc().working_dir(100, "0:nvim - \"flowmode\"").unwrap();
+        assert_eq!(dir.cwd, repo);
+        assert_eq!(dir.repo.map(|r| r.name), Some("flowmode".to_string()));
+        assert_eq!(fx.proc().working_dir(100, "tmux").unwrap().cwd, notes);
+        assert_eq!(fx.proc().working_dir(110, "flowmode").unwrap().cwd, repo);
+    }
+
+    #[test]
+    fn test_project_rules() {
+        let rule = |name: &str| ProjectRule { name: name.to_string(), ..ProjectRule::default() };
+        let config = Config {
+            projects: vec![
+                ProjectRule { repos: vec!["flowmode".into()], title_patterns: vec!["(?i)flow ?mode".into(), "(".into()], ..rule("FlowMode") },
+                ProjectRule { domains: vec!["jira.acme.com".into()], teams_channels: vec!["Acme".into()], ..rule("Acme") },
+                ProjectRule { title_patterns: vec!["flowmode".into()], ..rule("Shadowed") },
+            ],
+            ..Config::default()
+        };
+        let matcher = ProjectMatcher::from_config(&config);
+        let parsed = |context_type: &str, context: &str| ParsedTitle {
+            display: context.to_string(),
+            context_type: context_type.to_string(),
+            context: context.to_string(),
+        };
+        let facts = |category, window_title, parsed| SessionFacts {
+            category,
+            window_title,
+            parsed,
+            repo: None,
+            url: None,
+        };
+
+        let channel = parsed("channel", "General (Acme Rollout)");
+        let site = parsed("website", "jira.acme.com");
+        let folder = parsed("project", "FlowMode");
+        let cases = [
+            (facts("Terminal", "zsh", Some(&folder)), Some("FlowMode")),
+            (SessionFacts { repo: Some("other"), ..facts("Terminal", "zsh", None) }, None),
+            (facts("Development", "main.rs - flowmode - Code", None), Some("FlowMode")),
+            (facts("Communication", "General (Acme Rollout) | Microsoft Teams", Some(&channel)), Some("Acme")),
+            (facts("Browser", "ACME-12 - Jira", Some(&site)), Some("Acme")),
+            // Domains only apply to browsers
+            (facts("Notes", "jira.acme.com notes", None), None),
+            (facts("Browser", "Weather - Brave", None), None),
+            // A reported URL is matched by domain, not by title
+            (SessionFacts { url: Some("https://jira.acme.com/browse/ACME-12"), ..facts("Browser", "Rollout plan - Brave", None) }, Some("Acme")),
+            (SessionFacts { url: Some("https://news.example/jira.acme.com"), ..facts("Browser", "jira.acme.com - Brave", None) }, None),
+        ];
+        for (facts, expected) in cases {
+            assert_eq!(matcher.match_session(&facts), expected, "{:?}", facts);
+        }
+    }
+
+    #[test]
+    fn test_worktree_and_detached_head() {
+        let fx = Fixture::new("proc-worktree");
+        let git_dir = fx.mkdir("main/.git/worktrees/hotfix");
+        fs::write(git_dir.join("HEAD"), "0123456789abcdef\n").unwrap();
+        let worktree = fx.mkdir("hotfix");
+        fs::write(worktree.join(".git"), format!("gitdir: {}\n", git_dir.display())).unwrap();
+
+        let repo = find_repo(&worktree).unwrap();
+        assert_eq!((repo.name.as_str(), repo.branch.as_deref()), ("hotfix", Some("0123456")));
+        assert_eq!(ProcStat::parse("5 (tmux: server) S 1 5 5 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 77 0").unwrap().start_time, 77);
+    }
+}
@@ -0,0 +1,143 @@
+//! Sites: what kind of browser time a session is
+//!
+//! All browser time shares the browser's category, so reading docs and watching
+//! videos look the same. `[[sites]]` rules map domains and parsed title contexts
+//! to a category and a productivity score from -2 (distracting) to +2
+//! (productive). Built-in defaults cover the sites `parse_browser_title`
+//! recognises and come after the configured rules.
+
+use crate::config::{Config, SiteRule};
+use crate::project::SessionFacts;
+
+/// Lowest and highest productivity score
+pub const SCORE_RANGE: std::ops::RangeInclusive<i8> = -2..=2;
+
+/// Category and productivity score of a browser session
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteMatch {
+    pub category: String,
+    pub score: i8,
+}
+
+/// Built-in rules: (category, score, domains, context types)
+const DEFAULT_SITES: &[(&str, i8, &[&str], &[&str])] = &[
+    ("Development", 2, &["github.com", "gitlab.com"], &["code"]),
+    ("Research", 1, &["stackoverflow.com", "stackexchange.com"], &["research"]),
+    ("Research", 1, &["chatgpt.com", "chat.openai.com", "claude.ai", "gemini.google.com"], &["ai"]),
+    ("Documents", 1, &["docs.google.com"], &["document"]),
+    ("Communication", 0, &["mail.google.com", "outlook.office.com", "outlook.live.com"], &["email"]),
+    ("Entertainment", -1, &["youtube.com", "youtu.be"], &["video"]),
+];
+
+/// Built-in rules for the sites the title parser knows
+fn default_rules() -> Vec<SiteRule> {
+    DEFAULT_SITES
+        .iter()
+        .map(|(category, score, domains, context_types)| SiteRule {
+            category: category.to_string(),
+            score: *score,
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            context_types: context_types.iter().map(|t| t.to_string()).collect(),
+            contexts: Vec::new(),
+        })
+        .collect()
+}
+
+/// Finds the first site rule matching a browser session
+#[derive(Debug)]
+pub struct SiteMatcher {
+    rules: Vec<SiteRule>,
+}
+
+impl SiteMatcher {
+    /// Configured rules (scores clamped to -2..=2, with a warning) then the defaults
+    pub fn from_config(config: &Config) -> Self {
+        let mut rules: Vec<SiteRule> = config
+            .sites
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rule)| {
+                if rule.category.trim().is_empty() {
+                    tracing::warn!("Skipping sites[{}]: no category", i);
+                    return None;
+                }
+                let mut rule = rule.clone();
+                if !SCORE_RANGE.contains(&rule.score) {
+                    tracing::warn!("sites[{}] ({}): score {} is outside -2..=2", i, rule.category, rule.score);
+                    rule.score = rule.score.clamp(*SCORE_RANGE.start(), *SCORE_RANGE.end());
+                }
+                Some(rule)
+            })
+            .collect();
+        rules.extend(default_rules());
+        Self { rules }
+    }
+
+    /// Category and score of a browser session; `None` for other apps and unknown sites
+    pub fn match_session(&self, facts: &SessionFacts) -> Option<SiteMatch> {
+        if !facts.is_browser() {
+            return None;
+        }
+        self.rules
+            .iter()
+            .find(|rule| matches(rule, facts))
+            .map(|rule| SiteMatch { category: rule.category.clone(), score: rule.score })
+    }
+}
+
+fn matches(rule: &SiteRule, facts: &SessionFacts) -> bool {
+    if rule.domains.iter().any(|domain| facts.on_site(domain)) {
+        return true;
+    }
+    let Some(parsed) = facts.parsed else {
+        return false;
+    };
+    rule.context_types.iter().any(|t| t.eq_ignore_ascii_case(&parsed.context_type))
+        || rule
+            .contexts
+            .iter()
+            .any(|c| parsed.context.to_lowercase().contains(&c.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::title_parser::{parse_browser_title, parse_browser_url};
+
+    #[test]
+    fn test_site_rules_and_defaults() {
+        let config = Config {
+            sites: vec![
+                SiteRule { category: "Learning".into(), score: 2, domains: vec!["youtube.com/@rustconf".into()], ..Default::default() },
+                SiteRule { category: "News".into(), score: -5, contexts: vec!["Hacker News".into()], ..Default::default() },
+            ],
+            ..Config::default()
+        };
+        let matcher = SiteMatcher::from_config(&config);
+        let site = |url: Option<&str>, title: &str| {
+            let parsed = match url {
+                Some(url) => parse_browser_url(url, title),
+                None => parse_browser_title(title),
+            };
+            let facts = SessionFacts { category: "Browser", window_title: title, parsed: Some(&parsed), repo: None, url };
+            matcher.match_session(&facts).map(|m| (m.category, m.score))
+        };
+        let expect = |category: &str, score| Some((category.to_string(), score));
+
+        // Configured rules come first, out-of-range scores are clamped
+        assert_eq!(site(Some("https://www.youtube.com/@rustconf/videos"), "RustConf - YouTube"), expect("Learning", 2));
+        assert_eq!(site(None, "Hacker News - Brave"), expect("News", -2));
+        // Defaults, from the URL or only the title
+        assert_eq!(site(Some("https://www.youtube.com/watch?v=1"), "Cats - YouTube - Brave"), expect("Entertainment", -1));
+        assert_eq!(site(None, "Cats - YouTube - Brave"), expect("Entertainment", -1));
+        assert_eq!(site(Some("https://github.com/acme/app"), "acme/app"), expect("Development", 2));
+        assert_eq!(site(None, "How to sort a Vec - Stack Overflow - Brave"), expect("Research", 1));
+        assert_eq!(site(None, "Inbox (3) - me@example.com - Gmail"), expect("Communication", 0));
+        assert_eq!(site(Some("https://docs.rs/regex"), "regex - Rust"), None);
+
+        // Only browser sessions get a site
+        let parsed = parse_browser_title("Cats - YouTube");
+        let facts = SessionFacts { category: "Development", window_title: "Cats - YouTube", parsed: Some(&parsed), repo: None, url: None };
+        assert_eq!(matcher.match_session(&facts), None);
+    }
+}
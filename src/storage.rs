@@ -1,8 +1,17 @@
-use anyhow::Result;
-use chrono::{DateTime, Local, NaiveDate, Duration, Timelike};
-use rusqlite::{Connection, params};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Duration, Timelike};
+use regex::Regex;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Row, params};
 use std::path::Path;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::pomodoro::{InterruptionKind, PomodoroKind, PomodoroOutcome};
+use crate::project::{WorkingDir, UNASSIGNED};
+use crate::config::TitlePolicy;
+use crate::privacy::Redacted;
+use crate::sites::SiteMatch;
+use crate::title_parser::ParsedTitle;
 
 /// Activity record
 #[derive(Debug, Clone)]
@@ -14,6 +23,238 @@ pub struct ActivityRecord {
     pub started_at: DateTime<Local>,
     pub ended_at: Option<DateTime<Local>>,
     pub duration_secs: i64,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    /// `tracked`, `manual` or `imported`
+    pub source: String,
+    /// Tracker an imported row came from, e.g. `activitywatch`
+    pub importer: Option<String>,
+    /// Pomodoro session running when this row started (`work`, `short_break`,
+    /// `long_break`), or `None` outside pomodoros
+    pub pomodoro: Option<String>,
+    /// Window title as parsed when the row was stored (`None` until backfilled)
+    pub parsed: Option<ParsedTitle>,
+    /// Working directory of a terminal's foreground process
+    pub cwd: Option<String>,
+    /// Git repository enclosing `cwd`, and its checked-out branch
+    pub repo: Option<String>,
+    pub branch: Option<String>,
+    /// Project the session belongs to (`None` = unassigned)
+    pub project: Option<String>,
+    /// Page shown in a browser session, as reported by the browser extension
+    pub url: Option<String>,
+    pub domain: Option<String>,
+    /// Category and productivity score (-2..=2) from the `[[sites]]` rules
+    pub site_category: Option<String>,
+    pub score: Option<i8>,
+    /// Title policy the stored title was redacted with (`None` = as shown, scrubbed)
+    pub redacted: Option<TitlePolicy>,
+    /// Free-text note, e.g. on a manual entry
+    pub note: Option<String>,
+}
+
+impl ActivityRecord {
+    /// End of the session, estimated from its duration while it is still open
+    pub fn end_time(&self) -> DateTime<Local> {
+        self.ended_at
+            .unwrap_or_else(|| self.started_at + Duration::seconds(self.duration_secs))
+    }
+}
+
+/// Where a session's content came from, besides its window title
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionOrigin<'a> {
+    /// Terminal foreground process's working directory
+    pub workdir: Option<&'a WorkingDir>,
+    /// Browser tab URL reported by the extension
+    pub url: Option<&'a str>,
+}
+
+/// Tracked time for one window title, with its stored parse (`get_range_titles`)
+#[derive(Debug, Clone)]
+pub struct TitleSummary {
+    pub app_name: String,
+    pub category: String,
+    pub window_title: String,
+    /// `None` for rows stored before titles were parsed on insert
+    pub parsed: Option<ParsedTitle>,
+    pub total_secs: i64,
+}
+
+/// Tracked time for one parsed context, e.g. a chat partner or project folder
+#[derive(Debug, Clone)]
+pub struct ContextSummary {
+    pub context_type: String,
+    pub context: String,
+    pub total_secs: i64,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub sessions: i64,
+    /// Distinct days the context was seen on
+    pub days: i64,
+}
+
+/// Filters for context aggregates; `None` matches everything (case-insensitive)
+#[derive(Debug, Clone, Default)]
+pub struct ContextFilter {
+    pub context_type: Option<String>,
+    pub app: Option<String>,
+}
+
+/// Where an activity row came from (the `source` column). Rows recorded by the
+/// tracker keep the column default, `tracked`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Tracked,
+    Manual,
+    Imported,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Tracked => "tracked",
+            Source::Manual => "manual",
+            Source::Imported => "imported",
+        }
+    }
+}
+
+/// Tracker an imported row came from (the `importer` column)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Importer {
+    ActivityWatch,
+    Timewarrior,
+}
+
+impl Importer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Importer::ActivityWatch => "activitywatch",
+            Importer::Timewarrior => "timewarrior",
+        }
+    }
+}
+
+/// A finished session with explicit start and end, e.g. from an import
+#[derive(Debug, Clone)]
+pub struct NewSession {
+    pub app_name: String,
+    pub category: String,
+    pub window_title: String,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+}
+
+/// A session entered by hand, e.g. a meeting away from the computer
+#[derive(Debug, Clone)]
+pub struct ManualSession {
+    pub app_name: String,
+    pub category: String,
+    pub window_title: String,
+    pub parsed: ParsedTitle,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub project: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Changes to a stored session; `None` leaves a field as it is
+#[derive(Debug, Clone, Default)]
+pub struct SessionEdit {
+    pub started_at: Option<DateTime<Local>>,
+    pub ended_at: Option<DateTime<Local>>,
+    pub app_name: Option<String>,
+    pub category: Option<String>,
+    pub window_title: Option<String>,
+    /// Parse of the new app, category or title
+    pub parsed: Option<ParsedTitle>,
+    /// `Some(None)` makes the session unassigned
+    pub project: Option<Option<String>>,
+    /// `Some(None)` removes the note
+    pub note: Option<Option<String>>,
+}
+
+impl SessionEdit {
+    pub fn is_empty(&self) -> bool {
+        self.started_at.is_none()
+            && self.ended_at.is_none()
+            && self.app_name.is_none()
+            && self.category.is_none()
+            && self.window_title.is_none()
+            && self.project.is_none()
+            && self.note.is_none()
+    }
+}
+
+/// Result of `Storage::import_sessions`
+#[derive(Debug, Clone, Default)]
+pub struct ImportStats {
+    pub inserted: usize,
+    pub duplicates: usize,
+    /// Sessions on days already rolled up by `compact`
+    pub rolled_up: usize,
+    /// The inserted rows, for passes that should only touch them
+    pub scope: Option<SessionScope>,
+}
+
+/// Optional app/category restriction for session queries (case-insensitive)
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    pub app: Option<String>,
+    pub category: Option<String>,
+}
+
+/// A row of the `pomodoro_session` table
+#[derive(Debug, Clone)]
+pub struct PomodoroSession {
+    pub id: i64,
+    pub kind: PomodoroKind,
+    pub label: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub ended_at: Option<DateTime<Local>>,
+    pub outcome: Option<PomodoroOutcome>,
+    pub planned_secs: i64,
+    /// Seconds left as of `updated_at`
+    pub remaining_secs: i64,
+    pub paused: bool,
+    pub updated_at: DateTime<Local>,
+}
+
+impl PomodoroSession {
+    /// When the session ended, or `now` while it is still in flight
+    pub fn end_or(&self, now: DateTime<Local>) -> DateTime<Local> {
+        self.ended_at.unwrap_or(now)
+    }
+
+    /// Seconds left at `now`, counting down from the last checkpoint unless paused
+    pub fn remaining_at(&self, now: DateTime<Local>) -> i64 {
+        if self.paused {
+            self.remaining_secs
+        } else {
+            (self.remaining_secs - (now - self.updated_at).num_seconds()).max(0)
+        }
+    }
+}
+
+/// Something that broke into a work session (`pomodoro_interruption` table)
+#[derive(Debug, Clone)]
+pub struct PomodoroInterruption {
+    pub kind: InterruptionKind,
+    pub note: Option<String>,
+    pub at: DateTime<Local>,
+}
+
+/// Pomodoro counts for one day (work sessions only, except `breaks`)
+#[derive(Debug, Clone, Default)]
+pub struct PomodoroDay {
+    pub date: NaiveDate,
+    pub completed: u32,
+    pub skipped: u32,
+    pub interrupted: u32,
+    pub breaks: u32,
+    /// Time spent in work sessions, excluding pauses
+    pub focus_secs: i64,
 }
 
 /// Summary of activity for an app
@@ -24,6 +265,8 @@ pub struct AppSummary {
     pub total_secs: i64,
     pub active_secs: i64,
     pub passive_secs: i64,
+    /// Time-weighted productivity score of the scored sessions (see `weighted_score`)
+    pub score: Option<f64>,
 }
 
 /// Hourly breakdown
@@ -41,6 +284,425 @@ pub struct HourlyActivityDetailed {
     pub passive_secs: i64,
 }
 
+/// Time per project; `id` is `None` for unassigned sessions
+#[derive(Debug, Clone)]
+pub struct ProjectSummary {
+    pub id: Option<i64>,
+    pub name: String,
+    pub total_secs: i64,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub sessions: i64,
+}
+
+/// Sessions picked for a manual project assignment. Every criterion given
+/// must match; `ids` (if any) are matched one by one.
+#[derive(Debug, Clone, Default)]
+pub struct SessionSelection {
+    pub ids: Vec<i64>,
+    pub range: Option<TimeRange>,
+    pub app: Option<String>,
+    /// Parsed context or display name, e.g. a chat partner or repo
+    pub context: Option<String>,
+    /// Substring of the window title
+    pub title: Option<String>,
+    /// Only sessions without a project
+    pub unassigned_only: bool,
+}
+
+/// Sessions to forget. Every criterion given must match; `ids` (if any) are
+/// matched one by one.
+#[derive(Debug, Clone, Default)]
+pub struct ForgetSelection {
+    pub ids: Vec<i64>,
+    pub range: Option<TimeRange>,
+    pub app: Option<String>,
+    /// Regex on the window title
+    pub title_regex: Option<Regex>,
+}
+
+impl ForgetSelection {
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.range.is_none() && self.app.is_none() && self.title_regex.is_none()
+    }
+
+    /// Rolled-up days have no ids or titles, so only range and app selections reach them
+    fn covers_rollups(&self) -> bool {
+        self.ids.is_empty() && self.title_regex.is_none()
+    }
+}
+
+/// What `forget` removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForgetStats {
+    pub sessions: usize,
+    pub secs: i64,
+    /// Rolled-up per-day totals
+    pub rollup_rows: usize,
+}
+
+/// Time per category
+#[derive(Debug, Clone)]
+pub struct CategorySummary {
+    pub category: String,
+    pub total_secs: i64,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub score: Option<f64>,
+}
+
+/// Tracked time split by the pomodoro phase it happened in. Rolled-up days
+/// carry no phase, so they only count towards the range total.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PomodoroSplit {
+    pub work_secs: i64,
+    pub break_secs: i64,
+}
+
+/// Totals for one day, week or month bucket of a range
+#[derive(Debug, Clone)]
+pub struct PeriodSummary {
+    pub start: NaiveDate,
+    pub label: String,
+    pub total_secs: i64,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub score: Option<f64>,
+}
+
+/// Result of rolling raw sessions up into `daily_rollup`
+#[derive(Debug, Clone, Default)]
+pub struct CompactionStats {
+    pub sessions_removed: usize,
+    pub rollup_rows: usize,
+    pub days: usize,
+}
+
+type RollupKey = (NaiveDate, String, String);
+
+/// Sessions a maintenance pass (re-parsing, redaction, site and project rules)
+/// goes over
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SessionScope {
+    #[default]
+    All,
+    /// Sessions started within the range
+    Range(TimeRange),
+    /// Sessions with ids in `first..=last`, such as the rows of one import
+    Ids(i64, i64),
+}
+
+impl From<Option<TimeRange>> for SessionScope {
+    fn from(range: Option<TimeRange>) -> Self {
+        range.map_or(Self::All, Self::Range)
+    }
+}
+
+impl SessionScope {
+    /// Range bounds and first/last id, matching `SCOPE_FILTER` (NULL where unbounded)
+    fn params(&self) -> (Option<String>, Option<String>, Option<i64>, Option<i64>) {
+        match self {
+            Self::All => (None, None, None, None),
+            Self::Range(range) => {
+                let (start, end) = range_bounds(Some(range));
+                (start, end, None, None)
+            }
+            Self::Ids(first, last) => (None, None, Some(*first), Some(*last)),
+        }
+    }
+}
+
+/// Sessions within a `SessionScope`. Binds ?1-?4 to `SessionScope::params`.
+const SCOPE_FILTER: &str = "(?1 IS NULL OR started_at >= ?1) AND (?2 IS NULL OR started_at < ?2)
+     AND (?3 IS NULL OR id BETWEEN ?3 AND ?4)";
+
+/// Raw sessions plus rolled-up days for aggregate queries.
+/// Binds ?1/?2 to the range bounds and ?3/?4 to its first/last day.
+const AGGREGATE_SOURCE: &str =
+    "(SELECT app_name, COALESCE(site_category, category) AS category, duration_secs, active_secs, passive_secs,
+             CASE WHEN score IS NULL THEN 0 ELSE duration_secs END AS scored_secs,
+             COALESCE(score, 0) * duration_secs AS score_secs
+      FROM activity WHERE started_at >= ?1 AND started_at < ?2
+      UNION ALL
+      SELECT app_name, category, total_secs, active_secs, passive_secs, scored_secs, score_secs
+      FROM daily_rollup WHERE day >= ?3 AND day <= ?4)";
+
+/// Average productivity score of the scored time: `score_secs` is the sum of
+/// score × duration over the `scored_secs` that had a score. `None` if nothing was scored.
+pub fn weighted_score(score_secs: i64, scored_secs: i64) -> Option<f64> {
+    (scored_secs > 0).then(|| (score_secs as f64 / scored_secs as f64 * 10.0).round() / 10.0)
+}
+
+/// Half-open local time range `[start, end)` used by all range queries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeRange {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl TimeRange {
+    /// A single calendar day
+    pub fn day(date: NaiveDate) -> Self {
+        Self::days(date, date)
+    }
+
+    /// Today, midnight to midnight
+    pub fn today() -> Self {
+        Self::day(Local::now().date_naive())
+    }
+
+    /// Calendar days from `from` to `to`, both inclusive
+    pub fn days(from: NaiveDate, to: NaiveDate) -> Self {
+        Self {
+            start: local_midnight(from),
+            end: local_midnight(to + Duration::days(1)),
+        }
+    }
+
+    /// The last `n` days, ending with today
+    pub fn last_days(n: i64) -> Self {
+        let today = Local::now().date_naive();
+        Self::days(today - Duration::days(n.max(1) - 1), today)
+    }
+
+    /// The Monday-to-Sunday week containing `date`
+    pub fn week_of(date: NaiveDate) -> Self {
+        let monday = GroupBy::Week.bucket(date);
+        Self::days(monday, monday + Duration::days(6))
+    }
+
+    /// First day covered by the range
+    pub fn first_day(&self) -> NaiveDate {
+        self.start.date_naive()
+    }
+
+    /// Last day covered by the range (inclusive)
+    pub fn last_day(&self) -> NaiveDate {
+        (self.end - Duration::seconds(1)).date_naive()
+    }
+
+    /// Every calendar day covered by the range
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        self.first_day()
+            .iter_days()
+            .take_while(|d| *d <= self.last_day())
+            .collect()
+    }
+
+    pub fn is_single_day(&self) -> bool {
+        self.first_day() == self.last_day()
+    }
+
+    fn bounds(&self) -> (String, String) {
+        (self.start.to_rfc3339(), self.end.to_rfc3339())
+    }
+
+    /// Range bounds followed by first/last day, matching `AGGREGATE_SOURCE`
+    fn aggregate_params(&self) -> [String; 4] {
+        let (start, end) = self.bounds();
+        [start, end, self.first_day().to_string(), self.last_day().to_string()]
+    }
+}
+
+/// Bounds of an optional range, for `(?1 IS NULL OR started_at >= ?1)` style
+/// filters: NULL when there is no range
+fn range_bounds(range: Option<&TimeRange>) -> (Option<String>, Option<String>) {
+    range.map(TimeRange::bounds).unzip()
+}
+
+/// Bucket size for grouped range queries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Day,
+    Week,
+    Month,
+}
+
+impl GroupBy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupBy::Day => "day",
+            GroupBy::Week => "week",
+            GroupBy::Month => "month",
+        }
+    }
+
+    /// First day of the bucket containing `date` (weeks start on Monday)
+    pub fn bucket(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            GroupBy::Day => date,
+            GroupBy::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            GroupBy::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Human-readable bucket label: 2024-03-05, 2024-W10, 2024-03
+    pub fn label(&self, bucket: NaiveDate) -> String {
+        match self {
+            GroupBy::Day => bucket.to_string(),
+            GroupBy::Week => {
+                let week = bucket.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            GroupBy::Month => bucket.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" | "daily" => Ok(GroupBy::Day),
+            "week" | "weekly" => Ok(GroupBy::Week),
+            "month" | "monthly" => Ok(GroupBy::Month),
+            other => Err(format!("unknown grouping '{}' (expected day, week or month)", other)),
+        }
+    }
+}
+
+/// What `flowmode stats` breaks totals down by
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Breakdown {
+    #[default]
+    App,
+    Category,
+    Project,
+}
+
+impl FromStr for Breakdown {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "app" | "apps" => Ok(Breakdown::App),
+            "category" | "categories" => Ok(Breakdown::Category),
+            "project" | "projects" => Ok(Breakdown::Project),
+            other => Err(format!("unknown breakdown '{}' (expected app, category or project)", other)),
+        }
+    }
+}
+
+/// Parse a date argument: `YYYY-MM-DD`, `today` or `yesterday`
+pub fn parse_date(s: &str) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+    match s.trim().to_lowercase().as_str() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Duration::days(1)),
+        other => NaiveDate::parse_from_str(other, "%Y-%m-%d")
+            .map_err(|_| anyhow!("invalid date '{}' (expected YYYY-MM-DD)", s)),
+    }
+}
+
+fn local_midnight(date: NaiveDate) -> DateTime<Local> {
+    let naive = date.and_hms_opt(0, 0, 0).unwrap();
+    // Midnight may not exist on DST transition days; fall back to the earliest valid instant
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(|| (naive + Duration::hours(1)).and_local_timezone(Local).unwrap())
+}
+
+/// Share `secs` of tracked time between active and passive as a session split them
+fn share_secs(active_secs: i64, passive_secs: i64, secs: i64) -> (i64, i64) {
+    let total = active_secs + passive_secs;
+    if total <= 0 {
+        return (secs, 0);
+    }
+    let active = (secs as f64 * active_secs as f64 / total as f64).round() as i64;
+    (active, secs - active)
+}
+
+fn parse_local(s: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Local))
+        .ok()
+}
+
+/// Columns read by `row_to_pomodoro`, in order
+const POMODORO_COLUMNS: &str =
+    "id, kind, started_at, outcome, planned_secs, remaining_secs, paused, updated_at, ended_at, label";
+
+fn row_to_pomodoro(row: &Row) -> rusqlite::Result<PomodoroSession> {
+    let kind: String = row.get(1)?;
+    let started_str: String = row.get(2)?;
+    let outcome: Option<String> = row.get(3)?;
+    let updated_str: String = row.get(7)?;
+    let ended_str: Option<String> = row.get(8)?;
+
+    Ok(PomodoroSession {
+        id: row.get(0)?,
+        kind: kind.parse().unwrap_or(PomodoroKind::Work),
+        label: row.get(9)?,
+        started_at: parse_local(&started_str).unwrap_or_else(Local::now),
+        ended_at: ended_str.and_then(|s| parse_local(&s)),
+        outcome: outcome.and_then(|s| s.parse().ok()),
+        planned_secs: row.get(4)?,
+        remaining_secs: row.get(5)?,
+        paused: row.get(6)?,
+        updated_at: parse_local(&updated_str).unwrap_or_else(Local::now),
+    })
+}
+
+/// Columns read by `row_to_record`, in order
+const RECORD_COLUMNS: &str =
+    "id, app_name, category, window_title, started_at, ended_at, duration_secs, active_secs, passive_secs, source, pomodoro,
+     title_display, context_type, context, cwd, repo, branch,
+     (SELECT name FROM projects WHERE projects.id = project_id), url, domain, site_category, score, redacted, note, importer";
+
+/// Columns copied to the second half of a split session
+const COPY_COLUMNS: &str =
+    "app_name, category, window_title, started_at, ended_at, duration_secs, active_secs, passive_secs, source, pomodoro,
+     title_display, context_type, context, cwd, repo, branch, project_id, project_manual, url, domain,
+     site_category, score, redacted, note, importer";
+
+fn row_to_record(row: &Row) -> rusqlite::Result<ActivityRecord> {
+    let started_str: String = row.get(4)?;
+    let ended_str: Option<String> = row.get(5)?;
+
+    Ok(ActivityRecord {
+        id: row.get(0)?,
+        app_name: row.get(1)?,
+        category: row.get(2)?,
+        window_title: row.get(3)?,
+        started_at: parse_local(&started_str).unwrap_or_else(Local::now),
+        ended_at: ended_str.and_then(|s| parse_local(&s)),
+        duration_secs: row.get(6)?,
+        active_secs: row.get(7)?,
+        passive_secs: row.get(8)?,
+        source: row.get(9)?,
+        pomodoro: row.get(10)?,
+        parsed: parsed_from_row(row, 11)?,
+        cwd: row.get(14)?,
+        repo: row.get(15)?,
+        branch: row.get(16)?,
+        project: row.get(17)?,
+        url: row.get(18)?,
+        domain: row.get(19)?,
+        site_category: row.get(20)?,
+        score: row.get(21)?,
+        redacted: row.get::<_, Option<String>>(22)?.and_then(|p| p.parse().ok()),
+        note: row.get(23)?,
+        importer: row.get(24)?,
+    })
+}
+
+/// Stored parse from `title_display, context_type, context` starting at column `first`
+fn parsed_from_row(row: &Row, first: usize) -> rusqlite::Result<Option<ParsedTitle>> {
+    let display: Option<String> = row.get(first)?;
+    let context_type: Option<String> = row.get(first + 1)?;
+    let context: Option<String> = row.get(first + 2)?;
+    Ok(match (display, context_type) {
+        (Some(display), Some(context_type)) => Some(ParsedTitle {
+            display,
+            context_type,
+            context: context.unwrap_or_default(),
+        }),
+        _ => None,
+    })
+}
+
 /// Database for storing activity
 pub struct Storage {
     conn: Connection,
@@ -50,7 +712,20 @@ impl Storage {
     pub fn open(path: &Path) -> Result<Self> {
         std::fs::create_dir_all(path.parent().unwrap())?;
         let conn = Connection::open(path)?;
+        if crate::encryption::is_encrypted(path)? {
+            crate::encryption::unlock(&conn, path)?;
+        }
+        Self::init(conn)
+    }
+
+    /// Open a throwaway in-memory database
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
 
+    /// Create tables and run migrations
+    fn init(conn: Connection) -> Result<Self> {
         // Create tables
         conn.execute(
             "CREATE TABLE IF NOT EXISTS activity (
@@ -90,82 +765,295 @@ impl Storage {
             conn.execute("UPDATE activity SET active_secs = duration_secs WHERE active_secs = 0 AND duration_secs > 0", [])?;
         }
 
-        Ok(Self { conn })
-    }
+        // v0.6.0 Migration: Record where each row came from (tracked, imported, ...)
+        let has_source: bool = conn
+            .prepare("SELECT source FROM activity LIMIT 1")
+            .is_ok();
 
-    /// Start a new activity session
-    pub fn start_activity(&self, app_name: &str, category: &str, window_title: &str) -> Result<i64> {
-        let now = Local::now();
-        self.conn.execute(
-            "INSERT INTO activity (app_name, category, window_title, started_at)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![app_name, category, window_title, now.to_rfc3339()],
-        )?;
-        Ok(self.conn.last_insert_rowid())
-    }
+        if !has_source {
+            conn.execute("ALTER TABLE activity ADD COLUMN source TEXT NOT NULL DEFAULT 'tracked'", [])?;
+        }
 
-    /// End an activity session
-    pub fn end_activity(&self, id: i64) -> Result<()> {
-        let now = Local::now();
-        self.conn.execute(
-            "UPDATE activity
-             SET ended_at = ?1,
-                 duration_secs = CAST((julianday(?1) - julianday(started_at)) * 86400 AS INTEGER)
-             WHERE id = ?2",
-            params![now.to_rfc3339(), id],
-        )?;
-        Ok(())
-    }
+        // v0.6.0 Migration: Pomodoro phase each row was tracked in (NULL outside pomodoros)
+        let has_pomodoro: bool = conn
+            .prepare("SELECT pomodoro FROM activity LIMIT 1")
+            .is_ok();
 
-    /// Update activity time counters (active_secs or passive_secs)
-    /// Called periodically during tracking to increment the appropriate counter
-    pub fn update_activity_time(&self, id: i64, active_delta: i64, passive_delta: i64) -> Result<()> {
-        self.conn.execute(
-            "UPDATE activity
-             SET active_secs = active_secs + ?1,
-                 passive_secs = passive_secs + ?2,
-                 duration_secs = duration_secs + ?1 + ?2
-             WHERE id = ?3",
-            params![active_delta, passive_delta, id],
-        )?;
-        Ok(())
-    }
+        if !has_pomodoro {
+            conn.execute("ALTER TABLE activity ADD COLUMN pomodoro TEXT", [])?;
+        }
 
-    /// Get current active session (if any)
-    pub fn get_active_session(&self) -> Result<Option<ActivityRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, app_name, category, window_title, started_at, ended_at, duration_secs
-             FROM activity WHERE ended_at IS NULL
-             ORDER BY started_at DESC LIMIT 1"
-        )?;
+        // v0.6.0 Migration: Parsed window title (NULL until parsed, see `reparse_titles`)
+        let has_context: bool = conn
+            .prepare("SELECT context FROM activity LIMIT 1")
+            .is_ok();
 
-        let result = stmt.query_row([], |row| {
-            let started_str: String = row.get(4)?;
-            let ended_str: Option<String> = row.get(5)?;
+        if !has_context {
+            conn.execute("ALTER TABLE activity ADD COLUMN title_display TEXT", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN context_type TEXT", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN context TEXT", [])?;
+        }
 
-            Ok(ActivityRecord {
-                id: row.get(0)?,
-                app_name: row.get(1)?,
-                category: row.get(2)?,
-                window_title: row.get(3)?,
-                started_at: DateTime::parse_from_rfc3339(&started_str)
-                    .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|_| Local::now()),
-                ended_at: ended_str.and_then(|s|
-                    DateTime::parse_from_rfc3339(&s)
-                        .map(|dt| dt.with_timezone(&Local))
-                        .ok()
-                ),
-                duration_secs: row.get(6)?,
-            })
-        });
+        // v0.6.0 Migration: Terminal working directory and git project
+        let has_repo: bool = conn
+            .prepare("SELECT repo FROM activity LIMIT 1")
+            .is_ok();
 
-        match result {
-            Ok(record) => Ok(Some(record)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+        if !has_repo {
+            conn.execute("ALTER TABLE activity ADD COLUMN cwd TEXT", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN repo TEXT", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN branch TEXT", [])?;
         }
-    }
+
+        // Named projects; `activity.project_id` is NULL for unassigned sessions
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS projects (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE
+            )",
+            [],
+        )?;
+
+        // v0.6.0 Migration: Project of each session, and whether it was assigned by hand
+        let has_project: bool = conn
+            .prepare("SELECT project_id FROM activity LIMIT 1")
+            .is_ok();
+
+        if !has_project {
+            conn.execute("ALTER TABLE activity ADD COLUMN project_id INTEGER REFERENCES projects(id)", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN project_manual INTEGER NOT NULL DEFAULT 0", [])?;
+        }
+
+        // v0.6.0 Migration: Browser tab URL from the companion extension
+        let has_url: bool = conn
+            .prepare("SELECT url FROM activity LIMIT 1")
+            .is_ok();
+
+        if !has_url {
+            conn.execute("ALTER TABLE activity ADD COLUMN url TEXT", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN domain TEXT", [])?;
+        }
+
+        // v0.6.0 Migration: Site category and productivity score of browser sessions
+        let has_score: bool = conn
+            .prepare("SELECT score FROM activity LIMIT 1")
+            .is_ok();
+
+        if !has_score {
+            conn.execute("ALTER TABLE activity ADD COLUMN site_category TEXT", [])?;
+            conn.execute("ALTER TABLE activity ADD COLUMN score INTEGER", [])?;
+        }
+
+        // v0.6.0 Migration: Title policy each row was redacted with
+        let has_redacted: bool = conn
+            .prepare("SELECT redacted FROM activity LIMIT 1")
+            .is_ok();
+
+        if !has_redacted {
+            conn.execute("ALTER TABLE activity ADD COLUMN redacted TEXT", [])?;
+        }
+
+        // v0.6.0 Migration: Notes on sessions, e.g. manual entries
+        let has_note: bool = conn
+            .prepare("SELECT note FROM activity LIMIT 1")
+            .is_ok();
+
+        if !has_note {
+            conn.execute("ALTER TABLE activity ADD COLUMN note TEXT", [])?;
+        }
+
+        // v0.6.0 Migration: Imported rows are `source = imported`, with the tool in `importer`
+        let has_importer: bool = conn
+            .prepare("SELECT importer FROM activity LIMIT 1")
+            .is_ok();
+
+        if !has_importer {
+            conn.execute("ALTER TABLE activity ADD COLUMN importer TEXT", [])?;
+            conn.execute(
+                "UPDATE activity SET importer = source, source = 'imported'
+                 WHERE source NOT IN ('tracked', 'manual')",
+                [],
+            )?;
+        }
+
+        // Database-wide values, e.g. the title hash salt
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_activity_project
+             ON activity(project_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_activity_context
+             ON activity(context_type, context)",
+            [],
+        )?;
+
+        // Per-day, per-app totals for raw sessions past the retention window
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS daily_rollup (
+                day TEXT NOT NULL,
+                app_name TEXT NOT NULL,
+                category TEXT NOT NULL,
+                total_secs INTEGER NOT NULL DEFAULT 0,
+                active_secs INTEGER NOT NULL DEFAULT 0,
+                passive_secs INTEGER NOT NULL DEFAULT 0,
+                sessions INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, app_name, category)
+            )",
+            [],
+        )?;
+
+        // v0.6.0 Migration: Productivity score totals of rolled-up sessions
+        let has_rollup_score: bool = conn
+            .prepare("SELECT scored_secs FROM daily_rollup LIMIT 1")
+            .is_ok();
+
+        if !has_rollup_score {
+            conn.execute("ALTER TABLE daily_rollup ADD COLUMN scored_secs INTEGER NOT NULL DEFAULT 0", [])?;
+            conn.execute("ALTER TABLE daily_rollup ADD COLUMN score_secs INTEGER NOT NULL DEFAULT 0", [])?;
+        }
+
+        // Per-day, per-project totals of rolled-up sessions (project 0 = unassigned)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS project_rollup (
+                day TEXT NOT NULL,
+                project_id INTEGER NOT NULL DEFAULT 0,
+                total_secs INTEGER NOT NULL DEFAULT 0,
+                active_secs INTEGER NOT NULL DEFAULT 0,
+                passive_secs INTEGER NOT NULL DEFAULT 0,
+                sessions INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, project_id)
+            )",
+            [],
+        )?;
+
+        // One row per pomodoro work session or break; `ended_at IS NULL` while in flight
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pomodoro_session (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                ended_at TEXT,
+                outcome TEXT,
+                label TEXT,
+                planned_secs INTEGER NOT NULL,
+                remaining_secs INTEGER NOT NULL,
+                paused INTEGER NOT NULL DEFAULT 0,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_pomodoro_started
+             ON pomodoro_session(started_at)",
+            [],
+        )?;
+
+        // Interruptions logged during a work session
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pomodoro_interruption (
+                id INTEGER PRIMARY KEY,
+                pomodoro_id INTEGER NOT NULL REFERENCES pomodoro_session(id),
+                kind TEXT NOT NULL,
+                note TEXT,
+                at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    /// Start a new activity session, tagged with the running pomodoro phase,
+    /// the parsed window title and where the window's content came from
+    pub fn start_activity(
+        &self,
+        app_name: &str,
+        category: &str,
+        window_title: &str,
+        pomodoro: Option<PomodoroKind>,
+        parsed: &ParsedTitle,
+        origin: &SessionOrigin,
+    ) -> Result<i64> {
+        let now = Local::now();
+        let workdir = origin.workdir;
+        let repo = workdir.and_then(|w| w.repo.as_ref());
+        self.conn.execute(
+            "INSERT INTO activity (app_name, category, window_title, started_at, pomodoro,
+                                   title_display, context_type, context, cwd, repo, branch, url, domain)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                app_name,
+                category,
+                window_title,
+                now.to_rfc3339(),
+                pomodoro.map(|k| k.as_str()),
+                parsed.display,
+                parsed.context_type,
+                parsed.context,
+                workdir.map(|w| w.cwd.to_string_lossy().into_owned()),
+                repo.map(|r| r.name.as_str()),
+                repo.and_then(|r| r.branch.as_deref()),
+                origin.url,
+                origin.url.and_then(crate::browser::domain)
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// End an activity session
+    pub fn end_activity(&self, id: i64) -> Result<()> {
+        let now = Local::now();
+        self.conn.execute(
+            "UPDATE activity
+             SET ended_at = ?1,
+                 duration_secs = CAST((julianday(?1) - julianday(started_at)) * 86400 AS INTEGER)
+             WHERE id = ?2",
+            params![now.to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Update activity time counters (active_secs or passive_secs)
+    /// Called periodically during tracking to increment the appropriate counter
+    pub fn update_activity_time(&self, id: i64, active_delta: i64, passive_delta: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE activity
+             SET active_secs = active_secs + ?1,
+                 passive_secs = passive_secs + ?2,
+                 duration_secs = duration_secs + ?1 + ?2
+             WHERE id = ?3",
+            params![active_delta, passive_delta, id],
+        )?;
+        Ok(())
+    }
+
+    /// Get current active session (if any)
+    pub fn get_active_session(&self) -> Result<Option<ActivityRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RECORD_COLUMNS}
+             FROM activity WHERE ended_at IS NULL
+             ORDER BY started_at DESC LIMIT 1"
+        ))?;
+
+        let result = stmt.query_row([], row_to_record);
+
+        match result {
+            Ok(record) => Ok(Some(record)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
 
     /// Get activity summary for today
     pub fn get_today_summary(&self) -> Result<Vec<AppSummary>> {
@@ -175,20 +1063,22 @@ impl Storage {
 
     /// Get activity summary for a specific date
     pub fn get_date_summary(&self, date: NaiveDate) -> Result<Vec<AppSummary>> {
-        let start = date.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = start + Duration::days(1);
+        self.get_range_summary(&TimeRange::day(date))
+    }
 
-        let mut stmt = self.conn.prepare(
+    /// Get per-app activity summary for a range
+    pub fn get_range_summary(&self, range: &TimeRange) -> Result<Vec<AppSummary>> {
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT app_name, category, SUM(duration_secs) as total,
-                    SUM(active_secs) as active, SUM(passive_secs) as passive
-             FROM activity
-             WHERE started_at >= ?1 AND started_at < ?2
+                    SUM(active_secs) as active, SUM(passive_secs) as passive,
+                    SUM(score_secs), SUM(scored_secs)
+             FROM {AGGREGATE_SOURCE}
              GROUP BY app_name, category
              ORDER BY total DESC"
-        )?;
+        ))?;
 
         let rows = stmt.query_map(
-            params![start.to_rfc3339(), end.to_rfc3339()],
+            range.aggregate_params(),
             |row| {
                 Ok(AppSummary {
                     app_name: row.get(0)?,
@@ -196,6 +1086,7 @@ impl Storage {
                     total_secs: row.get(2)?,
                     active_secs: row.get(3)?,
                     passive_secs: row.get(4)?,
+                    score: weighted_score(row.get(5)?, row.get(6)?),
                 })
             }
         )?;
@@ -209,65 +1100,56 @@ impl Storage {
 
     /// Get total tracked time for today
     pub fn get_today_total_secs(&self) -> Result<i64> {
-        let today = Local::now().date_naive();
-        let start = today.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = start + Duration::days(1);
+        self.get_range_total_secs(&TimeRange::today())
+    }
 
+    /// Get total tracked time for a range
+    pub fn get_range_total_secs(&self, range: &TimeRange) -> Result<i64> {
         let total: i64 = self.conn.query_row(
-            "SELECT COALESCE(SUM(duration_secs), 0)
-             FROM activity
-             WHERE started_at >= ?1 AND started_at < ?2",
-            params![start.to_rfc3339(), end.to_rfc3339()],
+            &format!("SELECT COALESCE(SUM(duration_secs), 0) FROM {AGGREGATE_SOURCE}"),
+            range.aggregate_params(),
             |row| row.get(0),
         )?;
 
         Ok(total)
     }
 
-    /// Get hourly breakdown for today
-    pub fn get_today_hourly(&self) -> Result<Vec<HourlyActivity>> {
-        let today = Local::now().date_naive();
-        let start = today.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = start + Duration::days(1);
-
-        let mut stmt = self.conn.prepare(
-            "SELECT started_at, duration_secs
-             FROM activity
-             WHERE started_at >= ?1 AND started_at < ?2"
-        )?;
-
-        let rows = stmt.query_map(
-            params![start.to_rfc3339(), end.to_rfc3339()],
-            |row| {
-                let started_str: String = row.get(0)?;
-                let duration: i64 = row.get(1)?;
-                Ok((started_str, duration))
-            }
+    /// Productivity score of a range (see `weighted_score`)
+    pub fn get_range_score(&self, range: &TimeRange) -> Result<Option<f64>> {
+        let (score_secs, scored_secs) = self.conn.query_row(
+            &format!("SELECT COALESCE(SUM(score_secs), 0), COALESCE(SUM(scored_secs), 0) FROM {AGGREGATE_SOURCE}"),
+            range.aggregate_params(),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
+        Ok(weighted_score(score_secs, scored_secs))
+    }
 
-        let mut hourly: HashMap<u32, i64> = HashMap::new();
-        for row in rows {
-            let (started_str, duration) = row?;
-            if let Ok(dt) = DateTime::parse_from_rfc3339(&started_str) {
-                let hour = dt.hour();
-                *hourly.entry(hour).or_insert(0) += duration;
-            }
-        }
+    /// Get hourly breakdown for today
+    pub fn get_today_hourly(&self) -> Result<Vec<HourlyActivity>> {
+        self.get_range_hourly(&TimeRange::today())
+    }
 
-        let mut result: Vec<HourlyActivity> = hourly
+    /// Get hourly breakdown for a range (hours of the day summed across all days)
+    pub fn get_range_hourly(&self, range: &TimeRange) -> Result<Vec<HourlyActivity>> {
+        Ok(self
+            .get_range_hourly_detailed(range)?
             .into_iter()
-            .map(|(hour, total_secs)| HourlyActivity { hour, total_secs })
-            .collect();
-        result.sort_by_key(|h| h.hour);
-
-        Ok(result)
+            .map(|h| HourlyActivity {
+                hour: h.hour,
+                total_secs: h.active_secs + h.passive_secs,
+            })
+            .collect())
     }
 
     /// Get hourly breakdown with active/passive detail for today
     pub fn get_today_hourly_detailed(&self) -> Result<Vec<HourlyActivityDetailed>> {
-        let today = Local::now().date_naive();
-        let start = today.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = start + Duration::days(1);
+        self.get_range_hourly_detailed(&TimeRange::today())
+    }
+
+    /// Get hourly breakdown with active/passive detail for a range
+    /// (raw sessions only; rolled-up days have no time of day)
+    pub fn get_range_hourly_detailed(&self, range: &TimeRange) -> Result<Vec<HourlyActivityDetailed>> {
+        let (start, end) = range.bounds();
 
         let mut stmt = self.conn.prepare(
             "SELECT started_at, active_secs, passive_secs
@@ -276,7 +1158,7 @@ impl Storage {
         )?;
 
         let rows = stmt.query_map(
-            params![start.to_rfc3339(), end.to_rfc3339()],
+            params![start, end],
             |row| {
                 let started_str: String = row.get(0)?;
                 let active: i64 = row.get(1)?;
@@ -285,68 +1167,197 @@ impl Storage {
             }
         )?;
 
-        let mut hourly_active: HashMap<u32, i64> = HashMap::new();
-        let mut hourly_passive: HashMap<u32, i64> = HashMap::new();
+        let mut hourly: BTreeMap<u32, (i64, i64)> = BTreeMap::new();
 
         for row in rows {
             let (started_str, active, passive) = row?;
-            if let Ok(dt) = DateTime::parse_from_rfc3339(&started_str) {
-                let hour = dt.hour();
-                *hourly_active.entry(hour).or_insert(0) += active;
-                *hourly_passive.entry(hour).or_insert(0) += passive;
+            if let Some(dt) = parse_local(&started_str) {
+                let entry = hourly.entry(dt.hour()).or_insert((0, 0));
+                entry.0 += active;
+                entry.1 += passive;
             }
         }
 
-        // Combine into result for all hours that have data
-        let mut hours: std::collections::HashSet<u32> = hourly_active.keys().copied().collect();
-        hours.extend(hourly_passive.keys().copied());
-
-        let mut result: Vec<HourlyActivityDetailed> = hours
+        Ok(hourly
             .into_iter()
-            .map(|hour| HourlyActivityDetailed {
+            .map(|(hour, (active_secs, passive_secs))| HourlyActivityDetailed {
                 hour,
-                active_secs: *hourly_active.get(&hour).unwrap_or(&0),
-                passive_secs: *hourly_passive.get(&hour).unwrap_or(&0),
+                active_secs,
+                passive_secs,
             })
-            .collect();
-        result.sort_by_key(|h| h.hour);
+            .collect())
+    }
+
+    /// Get per-category totals for a range
+    pub fn get_range_categories(&self, range: &TimeRange) -> Result<Vec<CategorySummary>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT category, SUM(duration_secs) as total,
+                    SUM(active_secs) as active, SUM(passive_secs) as passive,
+                    SUM(score_secs), SUM(scored_secs)
+             FROM {AGGREGATE_SOURCE}
+             GROUP BY category
+             ORDER BY total DESC"
+        ))?;
+
+        let rows = stmt.query_map(
+            range.aggregate_params(),
+            |row| {
+                Ok(CategorySummary {
+                    category: row.get(0)?,
+                    total_secs: row.get(1)?,
+                    active_secs: row.get(2)?,
+                    passive_secs: row.get(3)?,
+                    score: weighted_score(row.get(4)?, row.get(5)?),
+                })
+            }
+        )?;
 
-        Ok(result)
+        let mut categories = Vec::new();
+        for row in rows {
+            categories.push(row?);
+        }
+        Ok(categories)
     }
 
-    /// Get week summary (last 7 days)
-    pub fn get_week_summary(&self) -> Result<HashMap<NaiveDate, i64>> {
-        let today = Local::now().date_naive();
-        let week_ago = today - Duration::days(7);
-        let start = week_ago.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = today.and_hms_opt(23, 59, 59).unwrap().and_local_timezone(Local).unwrap();
+    /// Get tracked time during pomodoro work sessions and breaks for a range
+    pub fn get_range_pomodoro_split(&self, range: &TimeRange) -> Result<PomodoroSplit> {
+        let (start, end) = range.bounds();
+        let mut stmt = self.conn.prepare(
+            "SELECT pomodoro, SUM(duration_secs)
+             FROM activity
+             WHERE started_at >= ?1 AND started_at < ?2 AND pomodoro IS NOT NULL
+             GROUP BY pomodoro",
+        )?;
+
+        let mut split = PomodoroSplit::default();
+        let rows = stmt.query_map(params![start, end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (phase, secs) = row?;
+            match phase.parse::<PomodoroKind>() {
+                Ok(PomodoroKind::Work) => split.work_secs += secs,
+                Ok(PomodoroKind::ShortBreak | PomodoroKind::LongBreak) => split.break_secs += secs,
+                Err(_) => {}
+            }
+        }
+        Ok(split)
+    }
 
+    /// Get totals for a range bucketed by day, week or month (local time)
+    pub fn get_range_grouped(&self, range: &TimeRange, group_by: GroupBy) -> Result<Vec<PeriodSummary>> {
+        let (start, end) = range.bounds();
+
+        // Per day: [total, active, passive, score_secs, scored_secs]
         let mut stmt = self.conn.prepare(
-            "SELECT date(started_at) as day, SUM(duration_secs) as total
+            "SELECT started_at, duration_secs, active_secs, passive_secs,
+                    COALESCE(score, 0) * duration_secs, CASE WHEN score IS NULL THEN 0 ELSE duration_secs END
              FROM activity
-             WHERE started_at >= ?1 AND started_at <= ?2
-             GROUP BY day
-             ORDER BY day"
+             WHERE started_at >= ?1 AND started_at < ?2"
         )?;
 
         let rows = stmt.query_map(
-            params![start.to_rfc3339(), end.to_rfc3339()],
+            params![start, end],
             |row| {
-                let day_str: String = row.get(0)?;
-                let total: i64 = row.get(1)?;
-                Ok((day_str, total))
+                let started_str: String = row.get(0)?;
+                Ok((started_str, [row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?]))
             }
         )?;
 
-        let mut summary = HashMap::new();
+        let mut days: Vec<(NaiveDate, [i64; 5])> = Vec::new();
         for row in rows {
-            let (day_str, total) = row?;
+            let (started_str, totals) = row?;
+            if let Some(dt) = parse_local(&started_str) {
+                days.push((dt.date_naive(), totals));
+            }
+        }
+
+        // Rolled-up days carry no time of day, only the date
+        let [_, _, first_day, last_day] = range.aggregate_params();
+        let mut stmt = self.conn.prepare(
+            "SELECT day, SUM(total_secs), SUM(active_secs), SUM(passive_secs), SUM(score_secs), SUM(scored_secs)
+             FROM daily_rollup
+             WHERE day >= ?1 AND day <= ?2
+             GROUP BY day"
+        )?;
+        let rollups = stmt.query_map(
+            params![first_day, last_day],
+            |row| {
+                let day_str: String = row.get(0)?;
+                Ok((day_str, [row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?]))
+            }
+        )?;
+        for row in rollups {
+            let (day_str, totals) = row?;
             if let Ok(date) = NaiveDate::parse_from_str(&day_str, "%Y-%m-%d") {
-                summary.insert(date, total);
+                days.push((date, totals));
+            }
+        }
+
+        let mut buckets: BTreeMap<NaiveDate, [i64; 5]> = BTreeMap::new();
+        for (date, totals) in days {
+            let entry = buckets.entry(group_by.bucket(date)).or_default();
+            for (sum, value) in entry.iter_mut().zip(totals) {
+                *sum += value;
             }
         }
 
-        Ok(summary)
+        Ok(buckets
+            .into_iter()
+            .map(|(start, [total_secs, active_secs, passive_secs, score_secs, scored_secs])| PeriodSummary {
+                start,
+                label: group_by.label(start),
+                total_secs,
+                active_secs,
+                passive_secs,
+                score: weighted_score(score_secs, scored_secs),
+            })
+            .collect())
+    }
+
+    /// Get individual sessions in a range, oldest first
+    pub fn get_range_timeline(&self, range: &TimeRange) -> Result<Vec<ActivityRecord>> {
+        let mut records = Vec::new();
+        self.for_each_session(range, &SessionFilter::default(), |record| {
+            records.push(record);
+            Ok(())
+        })?;
+        Ok(records)
+    }
+
+    /// Visit sessions in a range, oldest first, without loading them all into memory.
+    /// Returns the number of sessions visited.
+    pub fn for_each_session<F>(&self, range: &TimeRange, filter: &SessionFilter, mut f: F) -> Result<usize>
+    where
+        F: FnMut(ActivityRecord) -> Result<()>,
+    {
+        let (start, end) = range.bounds();
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RECORD_COLUMNS}
+             FROM activity
+             WHERE started_at >= ?1 AND started_at < ?2
+               AND (?3 IS NULL OR app_name = ?3 COLLATE NOCASE)
+               AND (?4 IS NULL OR category = ?4 COLLATE NOCASE)
+             ORDER BY started_at"
+        ))?;
+
+        let mut rows = stmt.query(params![start, end, filter.app, filter.category])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            f(row_to_record(row)?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Get week summary (last 7 days)
+    pub fn get_week_summary(&self) -> Result<HashMap<NaiveDate, i64>> {
+        Ok(self
+            .get_range_grouped(&TimeRange::last_days(8), GroupBy::Day)?
+            .into_iter()
+            .map(|p| (p.start, p.total_secs))
+            .collect())
     }
 
     /// Close any open sessions (cleanup on shutdown)
@@ -364,81 +1375,1895 @@ impl Storage {
 
     /// Reset today's data (delete all entries from today)
     pub fn reset_today(&self) -> Result<()> {
-        let today = Local::now().date_naive();
-        let start = today.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = start + Duration::days(1);
+        self.delete_range(&TimeRange::today())?;
+        Ok(())
+    }
 
-        self.conn.execute(
+    /// Delete all sessions (and rolled-up days) within a range, returning the number of sessions removed
+    pub fn delete_range(&self, range: &TimeRange) -> Result<usize> {
+        let [start, end, first_day, last_day] = range.aggregate_params();
+
+        let deleted = self.conn.execute(
             "DELETE FROM activity WHERE started_at >= ?1 AND started_at < ?2",
-            params![start.to_rfc3339(), end.to_rfc3339()],
+            params![start, end],
+        )?;
+        self.conn.execute(
+            "DELETE FROM daily_rollup WHERE day >= ?1 AND day <= ?2",
+            params![first_day, last_day],
+        )?;
+        self.conn.execute(
+            "DELETE FROM project_rollup WHERE day >= ?1 AND day <= ?2",
+            params![first_day, last_day],
         )?;
 
-        Ok(())
+        Ok(deleted)
     }
 
-    /// Get detailed activity (with window titles) for today
-    pub fn get_today_detailed(&self) -> Result<Vec<(String, String, String, i64)>> {
-        let today = Local::now().date_naive();
-        let start = today.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap();
-        let end = start + Duration::days(1);
+    /// Sessions matching `selection`, oldest first
+    pub fn get_forget_sessions(&self, selection: &ForgetSelection) -> Result<Vec<ActivityRecord>> {
+        if selection.is_empty() {
+            bail!("Pick sessions to forget by id, app, title or date range");
+        }
+        let (start, end) = range_bounds(selection.range.as_ref());
+        let ids: Vec<Option<i64>> = if selection.ids.is_empty() {
+            vec![None]
+        } else {
+            selection.ids.iter().copied().map(Some).collect()
+        };
 
-        let mut stmt = self.conn.prepare(
-            "SELECT app_name, category, window_title, SUM(duration_secs) as total
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RECORD_COLUMNS}
              FROM activity
-             WHERE started_at >= ?1 AND started_at < ?2
-             GROUP BY app_name, window_title
-             HAVING total >= 5
-             ORDER BY app_name, total DESC"
-        )?;
-
-        let rows = stmt.query_map(
-            params![start.to_rfc3339(), end.to_rfc3339()],
-            |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, i64>(3)?,
-                ))
+             WHERE (?1 IS NULL OR id = ?1)
+               AND (?2 IS NULL OR started_at >= ?2) AND (?3 IS NULL OR started_at < ?3)
+               AND (?4 IS NULL OR app_name = ?4 COLLATE NOCASE)
+             ORDER BY started_at"
+        ))?;
+        let mut sessions = Vec::new();
+        for id in ids {
+            let rows = stmt.query_map(params![id, start, end, selection.app], row_to_record)?;
+            for record in rows {
+                let record = record?;
+                if selection.title_regex.as_ref().is_none_or(|r| r.is_match(&record.window_title)) {
+                    sessions.push(record);
+                }
             }
-        )?;
-
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
         }
-        Ok(results)
+        sessions.sort_by_key(|r| r.started_at);
+        Ok(sessions)
     }
 
-    /// Get history for the last N days
-    pub fn get_history_days(&self, days: i64) -> Result<Vec<(NaiveDate, i64)>> {
-        let today = Local::now().date_naive();
-        let start_date = today - Duration::days(days);
+    /// Delete the sessions matching `selection`, and the rolled-up days of its
+    /// range and app. With `secure`, deleted content is overwritten and the
+    /// file rebuilt (`VACUUM`) so nothing is left in free pages.
+    pub fn forget(&self, selection: &ForgetSelection, secure: bool) -> Result<ForgetStats> {
+        let sessions = self.get_forget_sessions(selection)?;
+        if secure {
+            self.conn.pragma_update(None, "secure_delete", true)?;
+        }
 
-        let mut stmt = self.conn.prepare(
-            "SELECT date(started_at) as day, SUM(duration_secs) as total
-             FROM activity
-             WHERE date(started_at) >= date(?1)
-             GROUP BY day
-             ORDER BY day DESC"
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stats = ForgetStats::default();
+        for record in &sessions {
+            stats.sessions += tx.execute("DELETE FROM activity WHERE id = ?1", params![record.id])?;
+            stats.secs += record.duration_secs;
+        }
+        if selection.covers_rollups() {
+            let (first_day, last_day) = selection
+                .range
+                .map(|range| (range.first_day().to_string(), range.last_day().to_string()))
+                .unzip();
+            let days = "(?1 IS NULL OR day >= ?1) AND (?2 IS NULL OR day <= ?2)";
+            stats.rollup_rows += tx.execute(
+                &format!("DELETE FROM daily_rollup WHERE {days} AND (?3 IS NULL OR app_name = ?3 COLLATE NOCASE)"),
+                params![first_day, last_day, selection.app],
+            )?;
+            // Project totals aren't split by app, so they only go with a whole range
+            if selection.app.is_none() {
+                stats.rollup_rows += tx.execute(
+                    &format!("DELETE FROM project_rollup WHERE {days}"),
+                    params![first_day, last_day],
+                )?;
+            }
+        }
+        tx.commit()?;
+
+        if secure {
+            self.conn.execute_batch("VACUUM")?;
+            self.conn.pragma_update(None, "secure_delete", false)?;
+        }
+        Ok(stats)
+    }
+
+    /// Insert finished sessions from another tracker, skipping any that overlap
+    /// an existing row of any app or fall on a rolled-up day (so re-running an
+    /// import is harmless)
+    pub fn import_sessions(&self, sessions: &[NewSession], importer: Importer) -> Result<ImportStats> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        // Time covered by existing rows, as disjoint (start, end) intervals sorted
+        // by start. Tracked and imported rows of different apps, or a long row and
+        // a short one inside it, can overlap, so they are merged first.
+        let mut covered: Vec<(i64, i64)> = Vec::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT started_at, COALESCE(ended_at, started_at), duration_secs FROM activity"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+            })?;
+            for row in rows {
+                let (started, ended, duration) = row?;
+                if let (Some(start), Some(end)) = (parse_local(&started), parse_local(&ended)) {
+                    covered.push((start.timestamp(), end.timestamp().max(start.timestamp() + duration)));
+                }
+            }
+        }
+        covered.sort_unstable();
+        let mut merged: Vec<(i64, i64)> = Vec::with_capacity(covered.len());
+        for (start, end) in covered {
+            match merged.last_mut() {
+                Some(last) if start < last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        let mut covered = merged;
+
+        // Days whose sessions are already rolled up into totals
+        let rolled_up: HashSet<NaiveDate> = {
+            let mut stmt = tx.prepare("SELECT DISTINCT day FROM daily_rollup")?;
+            let days = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            days.filter_map(|day| NaiveDate::parse_from_str(&day.ok()?, "%Y-%m-%d").ok()).collect()
+        };
+
+        let mut stats = ImportStats::default();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO activity (app_name, category, window_title, started_at, ended_at,
+                                       duration_secs, active_secs, passive_secs, source, importer)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, 0, ?7, ?8)"
+            )?;
+
+            for session in sessions {
+                let start = session.started_at.timestamp();
+                let end = session.ended_at.timestamp();
+                if end <= start {
+                    continue;
+                }
+
+                // Rolled-up days keep no intervals to compare with, only totals
+                let last_day = (session.ended_at - Duration::seconds(1)).date_naive();
+                let mut days = session.started_at.date_naive().iter_days().take_while(|day| *day <= last_day);
+                if days.any(|day| rolled_up.contains(&day)) {
+                    stats.rolled_up += 1;
+                    continue;
+                }
+
+                // Only the last interval starting before our end can overlap, since
+                // the covered intervals are disjoint
+                let idx = covered.partition_point(|(s, _)| *s < end);
+                if idx > 0 && covered[idx - 1].1 > start {
+                    stats.duplicates += 1;
+                    continue;
+                }
+
+                insert.execute(params![
+                    session.app_name,
+                    session.category,
+                    session.window_title,
+                    session.started_at.to_rfc3339(),
+                    session.ended_at.to_rfc3339(),
+                    end - start,
+                    Source::Imported.as_str(),
+                    importer.as_str(),
+                ])?;
+                covered.insert(idx, (start, end));
+                stats.inserted += 1;
+                // Nothing else writes during the transaction, so the new ids are consecutive
+                let id = tx.last_insert_rowid();
+                stats.scope = Some(match stats.scope {
+                    Some(SessionScope::Ids(first, _)) => SessionScope::Ids(first, id),
+                    _ => SessionScope::Ids(id, id),
+                });
+            }
+        }
+
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    /// A single session by id
+    pub fn get_session(&self, id: i64) -> Result<Option<ActivityRecord>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT {RECORD_COLUMNS} FROM activity WHERE id = ?1"))?;
+        match stmt.query_row(params![id], row_to_record) {
+            Ok(record) => Ok(Some(record)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn require_session(&self, id: i64) -> Result<ActivityRecord> {
+        self.get_session(id)?.ok_or_else(|| anyhow!("No session with id {}", id))
+    }
+
+    /// Sessions overlapping `start..end` (running ones up to now), oldest first
+    pub fn get_overlapping_sessions(
+        &self,
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+        except: Option<i64>,
+    ) -> Result<Vec<ActivityRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RECORD_COLUMNS}
+             FROM activity
+             WHERE julianday(started_at) < julianday(?2)
+               AND julianday(COALESCE(ended_at, ?3)) > julianday(?1)
+               AND (?4 IS NULL OR id != ?4)
+             ORDER BY started_at"
+        ))?;
+        let rows = stmt.query_map(
+            params![start.to_rfc3339(), end.to_rfc3339(), Local::now().to_rfc3339(), except],
+            row_to_record,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Insert a session entered by hand (`source = manual`), counted as active
+    /// time. Returns its id.
+    pub fn add_manual_session(&self, session: &ManualSession) -> Result<i64> {
+        let secs = (session.ended_at - session.started_at).num_seconds();
+        if secs <= 0 {
+            bail!("A session has to end after it starts");
+        }
+        let project_id = session.project.as_deref().map(|name| self.project_id(name)).transpose()?;
+        self.conn.execute(
+            "INSERT INTO activity (app_name, category, window_title, started_at, ended_at,
+                                   duration_secs, active_secs, passive_secs, source,
+                                   title_display, context_type, context, project_id, project_manual, note)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, 0, ?7, ?8, ?9, ?10, ?11, ?11 IS NOT NULL, ?12)",
+            params![
+                session.app_name,
+                session.category,
+                session.window_title,
+                session.started_at.to_rfc3339(),
+                session.ended_at.to_rfc3339(),
+                secs,
+                Source::Manual.as_str(),
+                session.parsed.display,
+                session.parsed.context_type,
+                session.parsed.context,
+                project_id,
+                session.note
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Change a stored session and return it. New start or end times recompute
+    /// its duration, keeping the share of active and passive time; the session
+    /// must have ended. A new project counts as a manual assignment.
+    pub fn edit_session(&self, id: i64, edit: &SessionEdit) -> Result<ActivityRecord> {
+        let record = self.require_session(id)?;
+        let tx = self.conn.unchecked_transaction()?;
+
+        if edit.started_at.is_some() || edit.ended_at.is_some() {
+            let Some(ended_at) = record.ended_at else {
+                bail!("Session {} is still being tracked; change its times once it has ended", id);
+            };
+            let start = edit.started_at.unwrap_or(record.started_at);
+            let end = edit.ended_at.unwrap_or(ended_at);
+            let secs = (end - start).num_seconds();
+            if secs <= 0 {
+                bail!("Session {} would end ({}) before it starts ({})", id, end.format("%Y-%m-%d %H:%M"), start.format("%Y-%m-%d %H:%M"));
+            }
+            let (active, passive) = share_secs(record.active_secs, record.passive_secs, secs);
+            tx.execute(
+                "UPDATE activity
+                 SET started_at = ?1, ended_at = ?2, duration_secs = ?3, active_secs = ?4, passive_secs = ?5
+                 WHERE id = ?6",
+                params![start.to_rfc3339(), end.to_rfc3339(), secs, active, passive, id],
+            )?;
+        }
+
+        tx.execute(
+            "UPDATE activity
+             SET app_name = COALESCE(?1, app_name),
+                 category = COALESCE(?2, category),
+                 window_title = COALESCE(?3, window_title)
+             WHERE id = ?4",
+            params![edit.app_name, edit.category, edit.window_title, id],
+        )?;
+        if let Some(parsed) = &edit.parsed {
+            tx.execute(
+                "UPDATE activity SET title_display = ?1, context_type = ?2, context = ?3 WHERE id = ?4",
+                params![parsed.display, parsed.context_type, parsed.context, id],
+            )?;
+        }
+        if let Some(project) = &edit.project {
+            let project_id = project.as_deref().map(|name| self.project_id(name)).transpose()?;
+            tx.execute(
+                "UPDATE activity SET project_id = ?1, project_manual = 1 WHERE id = ?2",
+                params![project_id, id],
+            )?;
+        }
+        if let Some(note) = &edit.note {
+            tx.execute("UPDATE activity SET note = ?1 WHERE id = ?2", params![note, id])?;
+        }
+        tx.commit()?;
+
+        self.require_session(id)
+    }
+
+    /// Split an ended session in two at `at`; the second part is a copy with a
+    /// new id, which is returned. Active and passive time are shared by length.
+    pub fn split_session(&self, id: i64, at: DateTime<Local>) -> Result<i64> {
+        let record = self.require_session(id)?;
+        let Some(ended_at) = record.ended_at else {
+            bail!("Session {} is still being tracked; split it once it has ended", id);
+        };
+        if at <= record.started_at || at >= ended_at {
+            bail!(
+                "{} is outside session {} ({} - {})",
+                at.format("%Y-%m-%d %H:%M:%S"),
+                id,
+                record.started_at.format("%Y-%m-%d %H:%M:%S"),
+                ended_at.format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        let first_secs = (at - record.started_at).num_seconds();
+        let second_secs = (ended_at - at).num_seconds();
+        let (first_active, first_passive) = share_secs(record.active_secs, record.passive_secs, first_secs);
+        let (second_active, second_passive) = share_secs(record.active_secs, record.passive_secs, second_secs);
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            &format!("INSERT INTO activity ({COPY_COLUMNS}) SELECT {COPY_COLUMNS} FROM activity WHERE id = ?1"),
+            params![id],
+        )?;
+        let second = tx.last_insert_rowid();
+        let mut update = tx.prepare(
+            "UPDATE activity
+             SET started_at = ?1, ended_at = ?2, duration_secs = ?3, active_secs = ?4, passive_secs = ?5
+             WHERE id = ?6",
+        )?;
+        let at = at.to_rfc3339();
+        update.execute(params![record.started_at.to_rfc3339(), at, first_secs, first_active, first_passive, id])?;
+        update.execute(params![at, ended_at.to_rfc3339(), second_secs, second_active, second_passive, second])?;
+        drop(update);
+        tx.commit()?;
+        Ok(second)
+    }
+
+    /// Merge ended sessions into the earliest one, which keeps its app, title and
+    /// project and runs from the first start to the last end. Tracked time is
+    /// summed, so gaps between the sessions stay untracked; notes are joined.
+    pub fn merge_sessions(&self, ids: &[i64]) -> Result<ActivityRecord> {
+        let mut records = Vec::new();
+        for &id in ids {
+            if records.iter().any(|r: &ActivityRecord| r.id == id) {
+                continue;
+            }
+            let record = self.require_session(id)?;
+            if record.ended_at.is_none() {
+                bail!("Session {} is still being tracked; merge it once it has ended", id);
+            }
+            records.push(record);
+        }
+        if records.len() < 2 {
+            bail!("Pick at least two sessions to merge");
+        }
+        records.sort_by_key(|r| r.started_at);
+
+        let first = &records[0];
+        let end = records.iter().filter_map(|r| r.ended_at).max().unwrap_or(first.end_time());
+        let mut notes: Vec<&str> = Vec::new();
+        for note in records.iter().filter_map(|r| r.note.as_deref()) {
+            if !notes.contains(&note) {
+                notes.push(note);
+            }
+        }
+        let note = Some(notes.join("; ")).filter(|n| !n.is_empty());
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE activity
+             SET ended_at = ?1, duration_secs = ?2, active_secs = ?3, passive_secs = ?4, note = ?5
+             WHERE id = ?6",
+            params![
+                end.to_rfc3339(),
+                records.iter().map(|r| r.duration_secs).sum::<i64>(),
+                records.iter().map(|r| r.active_secs).sum::<i64>(),
+                records.iter().map(|r| r.passive_secs).sum::<i64>(),
+                note,
+                first.id
+            ],
+        )?;
+        for record in &records[1..] {
+            tx.execute("DELETE FROM activity WHERE id = ?1", params![record.id])?;
+        }
+        tx.commit()?;
+
+        self.require_session(first.id)
+    }
+
+    /// Roll finished sessions that started before `before` up into `daily_rollup`
+    /// and delete the raw rows (and their window titles).
+    pub fn compact_before(&self, before: NaiveDate) -> Result<CompactionStats> {
+        let cutoff = local_midnight(before).to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+
+        // (day, app, category) -> (total, active, passive, sessions, score_secs, scored_secs);
+        // browser sessions count under their site's category
+        let mut rollups: BTreeMap<RollupKey, (i64, i64, i64, i64, i64, i64)> = BTreeMap::new();
+        // (day, project or 0) -> (total, active, passive, sessions)
+        let mut project_rollups: BTreeMap<(NaiveDate, i64), (i64, i64, i64, i64)> = BTreeMap::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT started_at, app_name, COALESCE(site_category, category), duration_secs, active_secs,
+                        passive_secs, COALESCE(project_id, 0), score
+                 FROM activity
+                 WHERE started_at < ?1 AND ended_at IS NOT NULL"
+            )?;
+            let rows = stmt.query_map(params![cutoff], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, i64>(5)?,
+                    row.get::<_, i64>(6)?,
+                    row.get::<_, Option<i64>>(7)?,
+                ))
+            })?;
+
+            for row in rows {
+                let (started_str, app, category, total, active, passive, project, score) = row?;
+                let Some(dt) = parse_local(&started_str) else { continue };
+                let entry = rollups.entry((dt.date_naive(), app, category)).or_insert((0, 0, 0, 0, 0, 0));
+                entry.0 += total;
+                entry.1 += active;
+                entry.2 += passive;
+                entry.3 += 1;
+                if let Some(score) = score {
+                    entry.4 += score * total;
+                    entry.5 += total;
+                }
+                let entry = project_rollups.entry((dt.date_naive(), project)).or_insert((0, 0, 0, 0));
+                entry.0 += total;
+                entry.1 += active;
+                entry.2 += passive;
+                entry.3 += 1;
+            }
+        }
+
+        {
+            let mut upsert = tx.prepare(
+                "INSERT INTO daily_rollup (day, app_name, category, total_secs, active_secs, passive_secs, sessions,
+                                           score_secs, scored_secs)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(day, app_name, category) DO UPDATE SET
+                     total_secs = total_secs + excluded.total_secs,
+                     active_secs = active_secs + excluded.active_secs,
+                     passive_secs = passive_secs + excluded.passive_secs,
+                     sessions = sessions + excluded.sessions,
+                     score_secs = score_secs + excluded.score_secs,
+                     scored_secs = scored_secs + excluded.scored_secs"
+            )?;
+            for ((day, app, category), (total, active, passive, sessions, score_secs, scored_secs)) in &rollups {
+                upsert.execute(params![
+                    day.to_string(),
+                    app,
+                    category,
+                    total,
+                    active,
+                    passive,
+                    sessions,
+                    score_secs,
+                    scored_secs
+                ])?;
+            }
+
+            let mut upsert = tx.prepare(
+                "INSERT INTO project_rollup (day, project_id, total_secs, active_secs, passive_secs, sessions)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(day, project_id) DO UPDATE SET
+                     total_secs = total_secs + excluded.total_secs,
+                     active_secs = active_secs + excluded.active_secs,
+                     passive_secs = passive_secs + excluded.passive_secs,
+                     sessions = sessions + excluded.sessions"
+            )?;
+            for ((day, project), (total, active, passive, sessions)) in &project_rollups {
+                upsert.execute(params![day.to_string(), project, total, active, passive, sessions])?;
+            }
+        }
+
+        let sessions_removed = tx.execute(
+            "DELETE FROM activity WHERE started_at < ?1 AND ended_at IS NOT NULL",
+            params![cutoff],
+        )?;
+        tx.commit()?;
+
+        let mut days: Vec<NaiveDate> = rollups.keys().map(|(day, _, _)| *day).collect();
+        days.dedup();
+
+        Ok(CompactionStats {
+            sessions_removed,
+            rollup_rows: rollups.len(),
+            days: days.len(),
+        })
+    }
+
+    /// Record the start of a pomodoro session
+    pub fn start_pomodoro(
+        &self,
+        kind: PomodoroKind,
+        label: Option<&str>,
+        planned_secs: i64,
+        at: DateTime<Local>,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO pomodoro_session (kind, started_at, label, planned_secs, remaining_secs, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4, ?2)",
+            params![kind.as_str(), at.to_rfc3339(), label, planned_secs],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Save remaining time and pause state so a restart can pick the session up
+    pub fn checkpoint_pomodoro(&self, id: i64, remaining_secs: i64, paused: bool, at: DateTime<Local>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE pomodoro_session SET remaining_secs = ?1, paused = ?2, updated_at = ?3 WHERE id = ?4",
+            params![remaining_secs, paused, at.to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Close a pomodoro session
+    pub fn finish_pomodoro(
+        &self,
+        id: i64,
+        outcome: PomodoroOutcome,
+        remaining_secs: i64,
+        at: DateTime<Local>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE pomodoro_session
+             SET ended_at = ?1, outcome = ?2, remaining_secs = ?3, paused = 0, updated_at = ?1
+             WHERE id = ?4",
+            params![at.to_rfc3339(), outcome.as_str(), remaining_secs, id],
+        )?;
+        Ok(())
+    }
+
+    /// Reopen the last completed work session for `extra_secs` more, dropping
+    /// the break that had started after it
+    pub fn extend_last_work_pomodoro(&self, extra_secs: i64) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM pomodoro_session WHERE ended_at IS NULL AND kind != 'work'", [])?;
+        tx.execute(
+            "UPDATE pomodoro_session
+             SET ended_at = NULL, outcome = NULL, planned_secs = planned_secs + ?1
+             WHERE id = (SELECT MAX(id) FROM pomodoro_session WHERE kind = 'work' AND outcome = 'completed')",
+            params![extra_secs],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The pomodoro session still in flight, if any
+    pub fn get_open_pomodoro(&self) -> Result<Option<PomodoroSession>> {
+        let sql = format!(
+            "SELECT {} FROM pomodoro_session WHERE ended_at IS NULL ORDER BY id DESC LIMIT 1",
+            POMODORO_COLUMNS
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([], row_to_pomodoro)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// A pomodoro session by id
+    pub fn get_pomodoro(&self, id: i64) -> Result<Option<PomodoroSession>> {
+        let sql = format!("SELECT {} FROM pomodoro_session WHERE id = ?1", POMODORO_COLUMNS);
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([id], row_to_pomodoro)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// The most recent work session, finished or not
+    pub fn get_last_work_pomodoro(&self) -> Result<Option<PomodoroSession>> {
+        let sql = format!(
+            "SELECT {} FROM pomodoro_session WHERE kind = 'work' ORDER BY id DESC LIMIT 1",
+            POMODORO_COLUMNS
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([], row_to_pomodoro)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Log an interruption against a pomodoro session
+    pub fn add_pomodoro_interruption(
+        &self,
+        pomodoro_id: i64,
+        kind: InterruptionKind,
+        note: Option<&str>,
+        at: DateTime<Local>,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO pomodoro_interruption (pomodoro_id, kind, note, at) VALUES (?1, ?2, ?3, ?4)",
+            params![pomodoro_id, kind.as_str(), note, at.to_rfc3339()],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Interruptions of a pomodoro session, oldest first
+    pub fn get_pomodoro_interruptions(&self, pomodoro_id: i64) -> Result<Vec<PomodoroInterruption>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, note, at FROM pomodoro_interruption WHERE pomodoro_id = ?1 ORDER BY at",
+        )?;
+        let rows = stmt.query_map([pomodoro_id], |row| {
+            let kind: String = row.get(0)?;
+            let at: String = row.get(2)?;
+            Ok(PomodoroInterruption {
+                kind: kind.parse().unwrap_or(InterruptionKind::Internal),
+                note: row.get(1)?,
+                at: parse_local(&at).unwrap_or_else(Local::now),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Per-day pomodoro counts for every day in a range, oldest first
+    pub fn get_pomodoro_days(&self, range: &TimeRange) -> Result<Vec<PomodoroDay>> {
+        let (start, end) = range.bounds();
+        let sql = format!(
+            "SELECT {} FROM pomodoro_session
+             WHERE started_at >= ?1 AND started_at < ?2 AND ended_at IS NOT NULL",
+            POMODORO_COLUMNS
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![start, end], row_to_pomodoro)?;
+
+        let mut days: BTreeMap<NaiveDate, PomodoroDay> = range
+            .dates()
+            .into_iter()
+            .map(|date| (date, PomodoroDay { date, ..Default::default() }))
+            .collect();
+
+        for row in rows {
+            let session = row?;
+            let Some(day) = days.get_mut(&session.started_at.date_naive()) else {
+                continue;
+            };
+            if session.kind != PomodoroKind::Work {
+                day.breaks += 1;
+                continue;
+            }
+            match session.outcome {
+                Some(PomodoroOutcome::Completed) => day.completed += 1,
+                Some(PomodoroOutcome::Skipped) => day.skipped += 1,
+                Some(PomodoroOutcome::Interrupted) | None => day.interrupted += 1,
+            }
+            day.focus_secs += (session.planned_secs - session.remaining_secs).max(0);
+        }
+
+        Ok(days.into_values().collect())
+    }
+
+    /// Every day with at least one completed work session, oldest first
+    pub fn get_pomodoro_streak_days(&self) -> Result<Vec<NaiveDate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT started_at FROM pomodoro_session
+             WHERE kind = 'work' AND outcome = 'completed'"
+        )?;
+        let days: std::collections::BTreeSet<NaiveDate> = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|s| s.ok().and_then(|s| parse_local(&s)))
+            .map(|dt| dt.date_naive())
+            .collect();
+        Ok(days.into_iter().collect())
+    }
+
+    /// Get detailed activity (with window titles) for today
+    pub fn get_today_detailed(&self) -> Result<Vec<(String, String, String, i64)>> {
+        self.get_range_detailed(&TimeRange::today())
+    }
+
+    /// Get detailed activity (with window titles) for a range
+    /// (raw sessions only; titles are dropped when days are rolled up)
+    pub fn get_range_detailed(&self, range: &TimeRange) -> Result<Vec<(String, String, String, i64)>> {
+        let (start, end) = range.bounds();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT app_name, category, window_title, SUM(duration_secs) as total
+             FROM activity
+             WHERE started_at >= ?1 AND started_at < ?2
+             GROUP BY app_name, window_title
+             HAVING total >= 5
+             ORDER BY app_name, total DESC"
         )?;
 
         let rows = stmt.query_map(
-            params![start_date.to_string()],
+            params![start, end],
             |row| {
-                let date_str: String = row.get(0)?;
-                let total: i64 = row.get(1)?;
-                Ok((date_str, total))
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
             }
         )?;
 
         let mut results = Vec::new();
         for row in rows {
-            let (date_str, total) = row?;
-            if let Ok(date) = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d") {
-                results.push((date, total));
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Get tracked time per window title for a range, with the stored parse
+    /// (raw sessions only; titles are dropped when days are rolled up)
+    pub fn get_range_titles(&self, range: &TimeRange) -> Result<Vec<TitleSummary>> {
+        let (start, end) = range.bounds();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT app_name, category, window_title, MAX(title_display), MAX(context_type), MAX(context),
+                    SUM(duration_secs) as total
+             FROM activity
+             WHERE started_at >= ?1 AND started_at < ?2
+             GROUP BY app_name, window_title
+             HAVING total >= 5
+             ORDER BY app_name, total DESC"
+        )?;
+
+        let rows = stmt.query_map(params![start, end], |row| {
+            Ok(TitleSummary {
+                app_name: row.get(0)?,
+                category: row.get(1)?,
+                window_title: row.get(2)?,
+                parsed: parsed_from_row(row, 3)?,
+                total_secs: row.get(6)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Get per-context totals for a range, most time first
+    pub fn get_range_contexts(&self, range: &TimeRange, filter: &ContextFilter) -> Result<Vec<ContextSummary>> {
+        let (start, end) = range.bounds();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT context_type, context, SUM(duration_secs) as total,
+                    SUM(active_secs), SUM(passive_secs), COUNT(*), COUNT(DISTINCT substr(started_at, 1, 10))
+             FROM activity
+             WHERE started_at >= ?1 AND started_at < ?2
+               AND context_type IS NOT NULL
+               AND (?3 IS NULL OR context_type = ?3 COLLATE NOCASE)
+               AND (?4 IS NULL OR app_name = ?4 COLLATE NOCASE)
+             GROUP BY context_type, context
+             ORDER BY total DESC"
+        )?;
+
+        let rows = stmt.query_map(params![start, end, filter.context_type, filter.app], |row| {
+            Ok(ContextSummary {
+                context_type: row.get(0)?,
+                context: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                total_secs: row.get(2)?,
+                active_secs: row.get(3)?,
+                passive_secs: row.get(4)?,
+                sessions: row.get(5)?,
+                days: row.get(6)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Parse the stored window titles again with `parse` (all rows, or only those
+    /// never parsed), within `scope`. Returns the rows updated.
+    /// Terminal rows with a recorded working directory, browser rows with a
+    /// reported URL and redacted rows keep the context they were stored with.
+    pub fn reparse_titles<F>(&self, scope: SessionScope, missing_only: bool, parse: F) -> Result<usize>
+    where
+        F: Fn(&str, &str, &str) -> ParsedTitle,
+    {
+        let (start, end, first_id, last_id) = scope.params();
+        let scope = format!(
+            "{SCOPE_FILTER} AND (?5 = 0 OR context_type IS NULL) AND cwd IS NULL AND url IS NULL
+             AND redacted IS NULL"
+        );
+
+        let tx = self.conn.unchecked_transaction()?;
+        let titles: Vec<(String, String, String)> = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT DISTINCT app_name, category, window_title FROM activity WHERE {scope}"
+            ))?;
+            let rows = stmt.query_map(params![start, end, first_id, last_id, missing_only], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "UPDATE activity SET title_display = ?6, context_type = ?7, context = ?8
+                 WHERE {scope} AND app_name = ?9 AND category = ?10 AND window_title = ?11"
+            ))?;
+            for (app, category, title) in &titles {
+                let parsed = parse(app, category, title);
+                updated += stmt.execute(params![
+                    start,
+                    end,
+                    first_id,
+                    last_id,
+                    missing_only,
+                    parsed.display,
+                    parsed.context_type,
+                    parsed.context,
+                    app,
+                    category,
+                    title
+                ])?;
             }
         }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Salt for hashed titles, generated on first use and kept with the database
+    pub fn title_hash_salt(&self) -> Result<String> {
+        self.random_setting("title_hash_salt", 16)
+    }
+
+    /// The title hash salt of the database at `path`, read without creating,
+    /// migrating or unlocking anything. `None` if the file is missing or
+    /// encrypted, or has no salt yet.
+    pub fn peek_title_hash_salt(path: &Path) -> Result<Option<String>> {
+        if !path.exists() || crate::encryption::is_encrypted(path)? {
+            return Ok(None);
+        }
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        // Databases from before redaction have no settings table
+        let has_settings: bool = conn.query_row(
+            "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'settings'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_settings {
+            return Ok(None);
+        }
+        let salt = conn
+            .query_row("SELECT value FROM settings WHERE key = 'title_hash_salt'", [], |row| row.get(0))
+            .optional()?;
+        Ok(salt)
+    }
+
+    /// Token for the web API's destructive endpoints, generated on first use
+    pub fn api_token(&self) -> Result<String> {
+        self.random_setting("api_token", 24)
+    }
+
+    /// Setting `key`, set to `len` random bytes (hex) if missing
+    fn random_setting(&self, key: &str, len: usize) -> Result<String> {
+        let mut bytes = vec![0u8; len];
+        getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("No randomness for {}: {}", key, e))?;
+        let value: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO NOTHING",
+            params![key, value],
+        )?;
+        let value = self.conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )?;
+        Ok(value)
+    }
+
+    /// Record the title policy a session was stored with
+    pub fn set_activity_redacted(&self, id: i64, policy: TitlePolicy) -> Result<()> {
+        let policy = Some(policy).filter(|p| *p != TitlePolicy::Keep);
+        self.conn.execute(
+            "UPDATE activity SET redacted = ?1 WHERE id = ?2",
+            params![policy.map(|p| p.as_str()), id],
+        )?;
+        Ok(())
+    }
+
+    /// Redact stored titles with `redact`, within `scope`. Returns
+    /// the sessions whose title, parse or URL changed; with `dry_run` nothing is
+    /// written.
+    pub fn redact_titles<F>(&self, scope: SessionScope, dry_run: bool, redact: F) -> Result<usize>
+    where
+        F: Fn(&ActivityRecord) -> Option<Redacted>,
+    {
+        let (start, end, first_id, last_id) = scope.params();
+
+        let records: Vec<ActivityRecord> = {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {RECORD_COLUMNS}
+                 FROM activity
+                 WHERE {SCOPE_FILTER}"
+            ))?;
+            let rows = stmt.query_map(params![start, end, first_id, last_id], row_to_record)?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changed = 0;
+        for record in &records {
+            let Some(stored) = redact(record) else {
+                continue;
+            };
+            let policy = Some(stored.policy).filter(|p| *p != TitlePolicy::Keep);
+            if stored.title == record.window_title
+                && Some(&stored.parsed) == record.parsed.as_ref()
+                && stored.url == record.url
+                && policy == record.redacted
+            {
+                continue;
+            }
+            // The working directory is as telling as the title, so it goes with it
+            changed += tx.execute(
+                "UPDATE activity
+                 SET window_title = ?1, title_display = ?2, context_type = ?3, context = ?4,
+                     url = ?5, domain = ?6, redacted = ?7,
+                     cwd = CASE WHEN ?7 IS NULL THEN cwd END, repo = CASE WHEN ?7 IS NULL THEN repo END,
+                     branch = CASE WHEN ?7 IS NULL THEN branch END
+                 WHERE id = ?8",
+                params![
+                    stored.title,
+                    stored.parsed.display,
+                    stored.parsed.context_type,
+                    stored.parsed.context,
+                    stored.url,
+                    stored.url.as_deref().and_then(crate::browser::domain),
+                    policy.map(|p| p.as_str()),
+                    record.id
+                ],
+            )?;
+        }
+        // Sessions redacted before working directories were dropped with their titles
+        changed += tx.execute(
+            &format!(
+                "UPDATE activity SET cwd = NULL, repo = NULL, branch = NULL
+                 WHERE redacted IS NOT NULL AND (cwd IS NOT NULL OR repo IS NOT NULL OR branch IS NOT NULL)
+                   AND {SCOPE_FILTER}"
+            ),
+            params![start, end, first_id, last_id],
+        )?;
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+        Ok(changed)
+    }
+
+    /// Id of the project called `name` (case-insensitive), creating it if needed
+    pub fn project_id(&self, name: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO projects (name) VALUES (?1) ON CONFLICT(name) DO NOTHING",
+            params![name.trim()],
+        )?;
+        let id = self.conn.query_row(
+            "SELECT id FROM projects WHERE name = ?1",
+            params![name.trim()],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// Set the project of a session as found by the project rules
+    pub fn set_activity_project(&self, id: i64, project: Option<&str>) -> Result<()> {
+        let project_id = project.map(|name| self.project_id(name)).transpose()?;
+        self.conn.execute(
+            "UPDATE activity SET project_id = ?1, project_manual = 0 WHERE id = ?2",
+            params![project_id, id],
+        )?;
+        Ok(())
+    }
+
+    /// Set the site category and productivity score of a browser session
+    pub fn set_activity_site(&self, id: i64, site: Option<&SiteMatch>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE activity SET site_category = ?1, score = ?2 WHERE id = ?3",
+            params![site.map(|s| s.category.as_str()), site.map(|s| s.score), id],
+        )?;
+        Ok(())
+    }
+
+    /// Run the site rules (`classify`) over stored browser sessions, within
+    /// `scope`. Returns the sessions whose category or score changed.
+    pub fn apply_sites<F>(&self, scope: SessionScope, classify: F) -> Result<usize>
+    where
+        F: Fn(&ActivityRecord) -> Option<SiteMatch>,
+    {
+        let (start, end, first_id, last_id) = scope.params();
+
+        let records: Vec<ActivityRecord> = {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {RECORD_COLUMNS}
+                 FROM activity
+                 WHERE {SCOPE_FILTER} AND category = 'Browser' COLLATE NOCASE"
+            ))?;
+            let rows = stmt.query_map(params![start, end, first_id, last_id], row_to_record)?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changed = 0;
+        for record in &records {
+            let site = classify(record);
+            if site.as_ref().map(|s| (s.category.as_str(), s.score))
+                == record.site_category.as_deref().zip(record.score)
+            {
+                continue;
+            }
+            changed += tx.execute(
+                "UPDATE activity SET site_category = ?1, score = ?2 WHERE id = ?3",
+                params![site.as_ref().map(|s| s.category.as_str()), site.as_ref().map(|s| s.score), record.id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Run the project rules (`assign`) over stored sessions: all of them, or
+    /// only unassigned ones, within `scope`. Sessions assigned
+    /// by hand are left alone. Returns the sessions whose project changed.
+    pub fn apply_projects<F>(&self, scope: SessionScope, unassigned_only: bool, assign: F) -> Result<usize>
+    where
+        F: Fn(&ActivityRecord) -> Option<String>,
+    {
+        let (start, end, first_id, last_id) = scope.params();
+
+        let records: Vec<ActivityRecord> = {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {RECORD_COLUMNS}
+                 FROM activity
+                 WHERE {SCOPE_FILTER} AND project_manual = 0 AND (?5 = 0 OR project_id IS NULL)"
+            ))?;
+            let rows = stmt.query_map(params![start, end, first_id, last_id, unassigned_only], row_to_record)?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut ids: HashMap<String, i64> = HashMap::new();
+        let mut changed = 0;
+        for record in &records {
+            let project = assign(record);
+            if project.as_deref().map(str::to_lowercase) == record.project.as_deref().map(str::to_lowercase) {
+                continue;
+            }
+            let project_id = match project {
+                Some(name) => match ids.get(&name.to_lowercase()) {
+                    Some(id) => Some(*id),
+                    None => {
+                        let id = self.project_id(&name)?;
+                        ids.insert(name.to_lowercase(), id);
+                        Some(id)
+                    }
+                },
+                None => None,
+            };
+            changed += tx.execute(
+                "UPDATE activity SET project_id = ?1 WHERE id = ?2",
+                params![project_id, record.id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Assign the selected sessions to `project` by hand (`None` = unassigned).
+    /// Manual assignments survive later runs of the project rules.
+    pub fn assign_project(&self, selection: &SessionSelection, project: Option<&str>) -> Result<usize> {
+        let project_id = project.map(|name| self.project_id(name)).transpose()?;
+        let (start, end) = range_bounds(selection.range.as_ref());
+
+        let ids: Vec<Option<i64>> = if selection.ids.is_empty() {
+            vec![None]
+        } else {
+            selection.ids.iter().copied().map(Some).collect()
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut assigned = 0;
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE activity SET project_id = ?1, project_manual = 1
+                 WHERE (?2 IS NULL OR id = ?2)
+                   AND (?3 IS NULL OR started_at >= ?3) AND (?4 IS NULL OR started_at < ?4)
+                   AND (?5 IS NULL OR app_name = ?5 COLLATE NOCASE)
+                   AND (?6 IS NULL OR context = ?6 COLLATE NOCASE OR title_display = ?6 COLLATE NOCASE)
+                   AND (?7 IS NULL OR instr(lower(window_title), lower(?7)) > 0)
+                   AND (?8 = 0 OR project_id IS NULL)"
+            )?;
+            for id in ids {
+                assigned += stmt.execute(params![
+                    project_id,
+                    id,
+                    start,
+                    end,
+                    selection.app,
+                    selection.context,
+                    selection.title,
+                    selection.unassigned_only
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(assigned)
+    }
+
+    /// Tracked time per project in a range, unassigned sessions included
+    pub fn get_range_projects(&self, range: &TimeRange) -> Result<Vec<ProjectSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.project_id, p.name, SUM(s.total), SUM(s.active), SUM(s.passive), SUM(s.sessions) AS sessions
+             FROM (SELECT project_id, duration_secs AS total, active_secs AS active,
+                          passive_secs AS passive, 1 AS sessions
+                   FROM activity WHERE started_at >= ?1 AND started_at < ?2
+                   UNION ALL
+                   SELECT NULLIF(project_id, 0), total_secs, active_secs, passive_secs, sessions
+                   FROM project_rollup WHERE day >= ?3 AND day <= ?4) s
+             LEFT JOIN projects p ON p.id = s.project_id
+             GROUP BY s.project_id
+             ORDER BY SUM(s.total) DESC"
+        )?;
+
+        let rows = stmt.query_map(range.aggregate_params(), |row| {
+            let name: Option<String> = row.get(1)?;
+            Ok(ProjectSummary {
+                id: row.get(0)?,
+                name: name.unwrap_or_else(|| UNASSIGNED.to_string()),
+                total_secs: row.get(2)?,
+                active_secs: row.get(3)?,
+                passive_secs: row.get(4)?,
+                sessions: row.get(5)?,
+            })
+        })?;
+
+        let mut projects = Vec::new();
+        for row in rows {
+            projects.push(row?);
+        }
+        Ok(projects)
+    }
+
+    /// Get history for the last N days, newest first
+    pub fn get_history_days(&self, days: i64) -> Result<Vec<PeriodSummary>> {
+        let mut results = self.get_range_grouped(&TimeRange::last_days(days + 1), GroupBy::Day)?;
+        results.reverse();
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::title_parser::parse_title;
+
+    fn insert(storage: &Storage, app: &str, category: &str, started: DateTime<Local>, secs: i64) {
+        storage.conn.execute(
+            "INSERT INTO activity (app_name, category, window_title, started_at, ended_at,
+                                   duration_secs, active_secs, passive_secs)
+             VALUES (?1, ?2, ?1, ?3, ?4, ?5, ?5, 0)",
+            params![app, category, started.to_rfc3339(), (started + Duration::seconds(secs)).to_rfc3339(), secs],
+        ).unwrap();
+    }
+
+    fn at(date: NaiveDate, hour: u32) -> DateTime<Local> {
+        local_midnight(date) + Duration::hours(hour as i64)
+    }
+
+    #[test]
+    fn test_range_is_half_open() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "VS Code", "Development", at(day, 10), 600);
+        insert(&storage, "VS Code", "Development", at(day + Duration::days(1), 10), 300);
+
+        let range = TimeRange::day(day);
+        assert_eq!(range.last_day(), day);
+        assert_eq!(storage.get_range_total_secs(&range).unwrap(), 600);
+        assert_eq!(storage.get_range_total_secs(&TimeRange::days(day, day + Duration::days(1))).unwrap(), 900);
+    }
+
+    #[test]
+    fn test_range_summary_and_categories() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "VS Code", "Development", at(day, 9), 1200);
+        insert(&storage, "Ghostty", "Terminal", at(day, 10), 300);
+        insert(&storage, "VS Code", "Development", at(day, 14), 600);
+
+        let range = TimeRange::day(day);
+        let apps = storage.get_range_summary(&range).unwrap();
+        assert_eq!(apps[0].app_name, "VS Code");
+        assert_eq!(apps[0].total_secs, 1800);
+
+        let categories = storage.get_range_categories(&range).unwrap();
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[1].category, "Terminal");
+
+        let hourly = storage.get_range_hourly(&range).unwrap();
+        assert_eq!(hourly.iter().map(|h| h.hour).collect::<Vec<_>>(), vec![9, 10, 14]);
+
+        let timeline = storage.get_range_timeline(&range).unwrap();
+        assert_eq!(timeline.len(), 3);
+        assert!(timeline.windows(2).all(|w| w[0].started_at <= w[1].started_at));
+    }
+
+    #[test]
+    fn test_range_grouped_by_week_and_month() {
+        let storage = Storage::open_in_memory().unwrap();
+        // Fri 2024-03-29, Mon 2024-04-01, Tue 2024-04-02
+        let fri = NaiveDate::from_ymd_opt(2024, 3, 29).unwrap();
+        insert(&storage, "VS Code", "Development", at(fri, 10), 100);
+        insert(&storage, "VS Code", "Development", at(fri + Duration::days(3), 10), 200);
+        insert(&storage, "VS Code", "Development", at(fri + Duration::days(4), 10), 300);
+
+        let range = TimeRange::days(fri, fri + Duration::days(4));
+
+        let weeks = storage.get_range_grouped(&range, GroupBy::Week).unwrap();
+        assert_eq!(weeks.iter().map(|w| w.total_secs).collect::<Vec<_>>(), vec![100, 500]);
+        assert_eq!(weeks[1].label, "2024-W14");
+
+        let months = storage.get_range_grouped(&range, GroupBy::Month).unwrap();
+        assert_eq!(months.iter().map(|m| m.label.as_str()).collect::<Vec<_>>(), vec!["2024-03", "2024-04"]);
+
+        let days = storage.get_range_grouped(&range, GroupBy::Day).unwrap();
+        assert_eq!(days.len(), 3);
+    }
+
+    #[test]
+    fn test_compaction_keeps_aggregates() {
+        let storage = Storage::open_in_memory().unwrap();
+        let old = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let recent = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "VS Code", "Development", at(old, 9), 1200);
+        insert(&storage, "VS Code", "Development", at(old, 15), 600);
+        insert(&storage, "Ghostty", "Terminal", at(old, 11), 300);
+        insert(&storage, "VS Code", "Development", at(recent, 9), 900);
+
+        let range = TimeRange::days(old, recent);
+        let before_apps = storage.get_range_summary(&range).unwrap();
+
+        let stats = storage.compact_before(old + Duration::days(1)).unwrap();
+        assert_eq!(stats.sessions_removed, 3);
+        assert_eq!(stats.rollup_rows, 2);
+        assert_eq!(stats.days, 1);
+
+        // Aggregates are unchanged, but titles for the old day are gone
+        let after_apps = storage.get_range_summary(&range).unwrap();
+        assert_eq!(
+            before_apps.iter().map(|a| (&a.app_name, a.total_secs)).collect::<Vec<_>>(),
+            after_apps.iter().map(|a| (&a.app_name, a.total_secs)).collect::<Vec<_>>(),
+        );
+        assert_eq!(storage.get_range_total_secs(&TimeRange::day(old)).unwrap(), 2100);
+        assert!(storage.get_range_detailed(&TimeRange::day(old)).unwrap().is_empty());
+
+        let days = storage.get_range_grouped(&range, GroupBy::Day).unwrap();
+        assert_eq!(days.iter().map(|d| d.total_secs).collect::<Vec<_>>(), vec![2100, 900]);
+
+        // Compacting again merges into the existing rollup rows
+        insert(&storage, "VS Code", "Development", at(old, 20), 60);
+        storage.compact_before(old + Duration::days(1)).unwrap();
+        assert_eq!(storage.get_range_total_secs(&TimeRange::day(old)).unwrap(), 2160);
+    }
+
+    #[test]
+    fn test_site_categories_and_scores() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "Brave", "Browser", at(day, 9), 3600);
+        insert(&storage, "Brave", "Browser", at(day, 10), 1200);
+        insert(&storage, "Brave", "Browser", at(day, 11), 600);
+        insert(&storage, "VS Code", "Development", at(day, 12), 1800);
+
+        // First browser session is docs (+2), second is video (-1), third unknown
+        let classify = |record: &ActivityRecord| {
+            let site = |category: &str, score| Some(SiteMatch { category: category.to_string(), score });
+            match record.started_at.hour() {
+                9 => site("Development", 2),
+                10 => site("Entertainment", -1),
+                _ => None,
+            }
+        };
+        assert_eq!(storage.apply_sites(SessionScope::All, classify).unwrap(), 2);
+        assert_eq!(storage.apply_sites(SessionScope::All, classify).unwrap(), 0);
+
+        let range = TimeRange::day(day);
+        let check = |storage: &Storage| {
+            let apps = storage.get_range_summary(&range).unwrap();
+            let apps: Vec<(&str, &str, i64, Option<f64>)> =
+                apps.iter().map(|a| (a.app_name.as_str(), a.category.as_str(), a.total_secs, a.score)).collect();
+            assert_eq!(
+                apps,
+                [
+                    ("Brave", "Development", 3600, Some(2.0)),
+                    ("VS Code", "Development", 1800, None),
+                    ("Brave", "Entertainment", 1200, Some(-1.0)),
+                    ("Brave", "Browser", 600, None),
+                ]
+            );
+            // (2 × 3600 - 1200) / 4800 scored seconds
+            assert_eq!(storage.get_range_score(&range).unwrap(), Some(1.3));
+            assert_eq!(storage.get_range_grouped(&range, GroupBy::Day).unwrap()[0].score, Some(1.3));
+        };
+        check(&storage);
+
+        // Categories and scores survive rolling the sessions up
+        storage.compact_before(day + Duration::days(1)).unwrap();
+        check(&storage);
+    }
+
+    #[test]
+    fn test_import_skips_overlapping_sessions() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "VS Code", "Development", at(day, 9), 3600);
+
+        let session = |app: &str, hour: u32, mins: i64| NewSession {
+            app_name: app.to_string(),
+            category: "Development".to_string(),
+            window_title: "imported".to_string(),
+            started_at: at(day, hour),
+            ended_at: at(day, hour) + Duration::minutes(mins),
+        };
+        let sessions = vec![
+            session("VS Code", 9, 30),  // overlaps the tracked row
+            session("VS Code", 10, 30), // starts exactly when the tracked row ends
+            session("Ghostty", 9, 30),  // another app's tracked time counts too
+            session("Ghostty", 11, 30),
+        ];
+
+        let stats = storage.import_sessions(&sessions, Importer::ActivityWatch).unwrap();
+        assert_eq!((stats.inserted, stats.duplicates), (2, 2));
+
+        // Importing the same file again inserts nothing
+        let stats = storage.import_sessions(&sessions, Importer::ActivityWatch).unwrap();
+        assert_eq!((stats.inserted, stats.duplicates), (0, 4));
+
+        let timeline = storage.get_range_timeline(&TimeRange::day(day)).unwrap();
+        let sources: Vec<(&str, Option<&str>)> =
+            timeline.iter().map(|r| (r.source.as_str(), r.importer.as_deref())).collect();
+        assert_eq!(sources, vec![("tracked", None), ("imported", Some("activitywatch")), ("imported", Some("activitywatch"))]);
+        assert_eq!(storage.get_range_total_secs(&TimeRange::day(day)).unwrap(), 3600 + 1800 + 1800);
+    }
+
+    #[test]
+    fn test_import_checks_nested_sessions_and_rolled_up_days() {
+        let storage = Storage::open_in_memory().unwrap();
+        let old_day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        // A long session with a short one of another app inside it: the later
+        // interval ends first, so the last one starting before 10:30 isn't the one
+        // that covers it
+        insert(&storage, "Brave", "Browser", at(day, 9), 3 * 3600);
+        insert(&storage, "Slack", "Communication", at(day, 9) + Duration::minutes(20), 600);
+        insert(&storage, "VS Code", "Development", at(old_day, 9), 3600);
+        storage.compact_before(day).unwrap();
+
+        let session = |started_at: DateTime<Local>, mins: i64| NewSession {
+            app_name: "VS Code".to_string(),
+            category: "Development".to_string(),
+            window_title: "imported".to_string(),
+            started_at,
+            ended_at: started_at + Duration::minutes(mins),
+        };
+        let sessions = vec![
+            session(at(day, 10) + Duration::minutes(30), 10), // inside Brave, after Slack
+            session(at(old_day, 14), 60),                     // on a rolled-up day, no raw rows left
+            session(at(old_day, 23) + Duration::minutes(30), 60), // runs into the raw day
+            session(at(day, 13), 60),
+        ];
+        let stats = storage.import_sessions(&sessions, Importer::Timewarrior).unwrap();
+        assert_eq!((stats.inserted, stats.duplicates, stats.rolled_up), (1, 1, 2));
+        assert_eq!(storage.get_range_total_secs(&TimeRange::day(old_day)).unwrap(), 3600);
+        assert_eq!(storage.get_range_total_secs(&TimeRange::day(day)).unwrap(), 3 * 3600 + 600 + 3600);
+    }
+
+    #[test]
+    fn test_import_scope_covers_only_new_rows() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "Brave", "Browser", at(day, 9), 3600);
+        insert(&storage, "Brave", "Browser", at(day, 13), 3600);
+        let session = |hour: u32| NewSession {
+            app_name: "Brave".to_string(),
+            category: "Browser".to_string(),
+            window_title: "imported".to_string(),
+            started_at: at(day, hour),
+            ended_at: at(day, hour) + Duration::minutes(30),
+        };
+
+        // The imported rows sit between the tracked ones, but only they are in scope
+        let stats = storage.import_sessions(&[session(11), session(12)], Importer::ActivityWatch).unwrap();
+        let scope = stats.scope.unwrap();
+        let site = |_: &ActivityRecord| Some(SiteMatch { category: "Reading".to_string(), score: 1 });
+        assert_eq!(storage.apply_sites(scope, site).unwrap(), 2);
+        let categories: Vec<Option<String>> = storage
+            .get_range_timeline(&TimeRange::day(day))
+            .unwrap()
+            .into_iter()
+            .map(|r| r.site_category)
+            .collect();
+        assert_eq!(categories, vec![None, Some("Reading".to_string()), Some("Reading".to_string()), None]);
+
+        // Nothing new, nothing to go over
+        let stats = storage.import_sessions(&[session(11)], Importer::ActivityWatch).unwrap();
+        assert_eq!(stats.scope, None);
+    }
+
+    #[test]
+    fn test_peek_title_hash_salt_reads_only() {
+        let dir = std::env::temp_dir().join(format!("flowmode-salt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("activity.db");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(Storage::peek_title_hash_salt(&path).unwrap(), None);
+        assert!(!path.exists());
+
+        let storage = Storage::open(&path).unwrap();
+        assert_eq!(Storage::peek_title_hash_salt(&path).unwrap(), None);
+        let salt = storage.title_hash_salt().unwrap();
+        assert_eq!(Storage::peek_title_hash_salt(&path).unwrap(), Some(salt));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sources_tracked_manual_and_imported() {
+        // Rows imported before the `importer` column move to `imported`
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE activity (id INTEGER PRIMARY KEY, app_name TEXT NOT NULL, category TEXT NOT NULL,
+                                    window_title TEXT NOT NULL, started_at TEXT NOT NULL, ended_at TEXT,
+                                    duration_secs INTEGER DEFAULT 0,
+                                    source TEXT NOT NULL DEFAULT 'tracked');
+             INSERT INTO activity (app_name, category, window_title, started_at, source)
+             VALUES ('Ghostty', 'Terminal', 'vim', '2024-03-04T09:00:00+00:00', 'timewarrior');",
+        )
+        .unwrap();
+        let storage = Storage::init(conn).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let parsed = parse_title("VS Code", "Development", "main.rs");
+        storage.start_activity("VS Code", "Development", "main.rs", None, &parsed, &SessionOrigin::default()).unwrap();
+        storage
+            .add_manual_session(&ManualSession {
+                app_name: "Meeting".into(),
+                category: "Manual".into(),
+                window_title: String::new(),
+                parsed: parse_title("Meeting", "Manual", ""),
+                started_at: at(day, 10),
+                ended_at: at(day, 11),
+                project: None,
+                note: None,
+            })
+            .unwrap();
+        let imported = NewSession {
+            app_name: "Brave".into(),
+            category: "Browser".into(),
+            window_title: "docs".into(),
+            started_at: at(day, 12),
+            ended_at: at(day, 13),
+        };
+        storage.import_sessions(&[imported], Importer::ActivityWatch).unwrap();
+
+        let mut stmt = storage.conn.prepare("SELECT source, importer FROM activity ORDER BY id").unwrap();
+        let rows: Vec<(String, Option<String>)> =
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|r| r.unwrap()).collect();
+        let rows: Vec<(&str, Option<&str>)> = rows.iter().map(|(s, i)| (s.as_str(), i.as_deref())).collect();
+        assert_eq!(
+            rows,
+            [
+                ("imported", Some("timewarrior")),
+                ("tracked", None),
+                ("manual", None),
+                ("imported", Some("activitywatch"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_activity_tagged_with_pomodoro_phase() {
+        let storage = Storage::open_in_memory().unwrap();
+        let tracked = [
+            (Some(PomodoroKind::Work), 1500),
+            (Some(PomodoroKind::ShortBreak), 120),
+            (None, 600),
+            (Some(PomodoroKind::Work), 300),
+        ];
+        for (phase, secs) in tracked {
+            let id = storage.start_activity("VS Code", "Development", "main.rs", phase, &parse_title("VS Code", "Development", "main.rs"), &SessionOrigin::default()).unwrap();
+            storage.update_activity_time(id, secs, 0).unwrap();
+        }
+
+        let active = storage.get_active_session().unwrap().unwrap();
+        assert_eq!(active.pomodoro.as_deref(), Some("work"));
+
+        let split = storage.get_range_pomodoro_split(&TimeRange::today()).unwrap();
+        assert_eq!(split, PomodoroSplit { work_secs: 1800, break_secs: 120 });
+    }
+
+    #[test]
+    fn test_context_aggregates_and_reparse() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let chat = "Chat | Ana Silva | Microsoft Teams";
+        let call = "Call with Ana Silva | Microsoft Teams";
+        insert(&storage, "Teams", "Communication", at(day, 9), 600);
+        insert(&storage, "Teams", "Communication", at(day + Duration::days(1), 9), 300);
+        storage.conn.execute("UPDATE activity SET window_title = ?1", params![chat]).unwrap();
+        insert(&storage, "Teams", "Communication", at(day, 11), 900);
+        storage.conn.execute("UPDATE activity SET window_title = ?1 WHERE started_at = ?2",
+            params![call, at(day, 11).to_rfc3339()]).unwrap();
+
+        // Rows from before parsing on insert are picked up by the backfill only once
+        assert_eq!(storage.reparse_titles(SessionScope::All, true, parse_title).unwrap(), 3);
+        assert_eq!(storage.reparse_titles(SessionScope::All, true, parse_title).unwrap(), 0);
+
+        let range = TimeRange::days(day, day + Duration::days(1));
+        let contexts = storage.get_range_contexts(&range, &ContextFilter::default()).unwrap();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!((contexts[0].context_type.as_str(), contexts[0].context.as_str()), ("call", "Ana Silva"));
+        assert_eq!((contexts[1].context_type.as_str(), contexts[1].total_secs, contexts[1].days), ("chat", 900, 2));
+
+        let filter = ContextFilter { context_type: Some("CHAT".to_string()), app: Some("teams".to_string()) };
+        assert_eq!(storage.get_range_contexts(&range, &filter).unwrap().len(), 1);
+
+        // A changed rule applies after a full re-parse, limited to the range given
+        let rename = |_: &str, _: &str, title: &str| ParsedTitle {
+            display: title.to_string(),
+            context_type: "person".to_string(),
+            context: "Ana".to_string(),
+        };
+        assert_eq!(storage.reparse_titles(SessionScope::Range(TimeRange::day(day)), false, rename).unwrap(), 2);
+        let contexts = storage.get_range_contexts(&range, &ContextFilter::default()).unwrap();
+        assert_eq!((contexts[0].context.as_str(), contexts[0].total_secs), ("Ana", 1500));
+
+        let titles = storage.get_range_titles(&TimeRange::day(day)).unwrap();
+        assert_eq!(titles[0].parsed.as_ref().unwrap().context_type, "person");
+    }
+
+    #[test]
+    fn test_projects_rules_manual_assignment_and_rollup() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "VS Code", "Development", at(day, 9), 3600);
+        insert(&storage, "Teams", "Communication", at(day, 10), 600);
+        insert(&storage, "Brave", "Browser", at(day, 11), 1200);
+        insert(&storage, "VS Code", "Development", at(day + Duration::days(1), 9), 1800);
+
+        // Rules: editor time belongs to FlowMode, the rest stays unassigned
+        let rules = |r: &ActivityRecord| (r.app_name == "VS Code").then(|| "FlowMode".to_string());
+        assert_eq!(storage.apply_projects(SessionScope::All, true, rules).unwrap(), 2);
+        assert_eq!(storage.apply_projects(SessionScope::All, true, rules).unwrap(), 0);
+
+        let range = TimeRange::day(day);
+        let projects = storage.get_range_projects(&range).unwrap();
+        let totals: Vec<(&str, i64)> = projects.iter().map(|p| (p.name.as_str(), p.total_secs)).collect();
+        assert_eq!(totals, [("FlowMode", 3600), (UNASSIGNED, 1800)]);
+        assert!(projects[1].id.is_none());
+
+        // Move the Teams call by hand; "flowmode" finds the existing project
+        let teams = SessionSelection { range: Some(range), app: Some("teams".to_string()), ..Default::default() };
+        assert_eq!(storage.assign_project(&teams, Some("flowmode")).unwrap(), 1);
+        let by_id = SessionSelection { ids: vec![1, 3], unassigned_only: true, ..Default::default() };
+        assert_eq!(storage.assign_project(&by_id, Some("Acme")).unwrap(), 1);
+
+        // A full re-run of the rules keeps manual assignments
+        assert_eq!(storage.apply_projects(SessionScope::All, false, |_| None).unwrap(), 2);
+        let projects = storage.get_range_projects(&range).unwrap();
+        let totals: Vec<(&str, i64)> = projects.iter().map(|p| (p.name.as_str(), p.total_secs)).collect();
+        assert_eq!(totals, [(UNASSIGNED, 3600), ("Acme", 1200), ("FlowMode", 600)]);
+
+        // Project totals survive rolling the sessions up
+        storage.compact_before(day + Duration::days(2)).unwrap();
+        let rolled = storage.get_range_projects(&TimeRange::days(day, day + Duration::days(1))).unwrap();
+        let totals: Vec<(&str, i64, i64)> = rolled.iter().map(|p| (p.name.as_str(), p.total_secs, p.sessions)).collect();
+        assert_eq!(totals, [(UNASSIGNED, 5400, 2), ("Acme", 1200, 1), ("FlowMode", 600, 1)]);
+    }
+
+    #[test]
+    fn test_terminal_sessions_keep_their_project() {
+        let storage = Storage::open_in_memory().unwrap();
+        let workdir = WorkingDir {
+            cwd: "/home/me/src/flowmode/src".into(),
+            repo: Some(crate::project::GitRepo {
+                root: "/home/me/src/flowmode".into(),
+                name: "flowmode".to_string(),
+                branch: Some("main".to_string()),
+            }),
+        };
+        let origin = SessionOrigin { workdir: Some(&workdir), ..Default::default() };
+        let id = storage
+            .start_activity("Ghostty", "Terminal", "zsh", None, &workdir.parsed_title(), &origin)
+            .unwrap();
+
+        let record = storage.get_active_session().unwrap().unwrap();
+        assert_eq!(record.id, id);
+        assert_eq!(record.cwd.as_deref(), Some("/home/me/src/flowmode/src"));
+        assert_eq!((record.repo.as_deref(), record.branch.as_deref()), (Some("flowmode"), Some("main")));
+
+        // The working directory says more than the title, so re-parsing leaves it alone
+        assert_eq!(storage.reparse_titles(SessionScope::All, false, parse_title).unwrap(), 0);
+        let parsed = storage.get_active_session().unwrap().unwrap().parsed.unwrap();
+        assert_eq!((parsed.context_type.as_str(), parsed.display.as_str()), ("project", "flowmode (main)"));
+    }
+
+    #[test]
+    fn test_redacted_terminal_sessions_drop_their_working_directory() {
+        use crate::config::{PrivacyConfig, TitlePolicyRule};
+        use crate::privacy::Redactor;
+
+        let storage = Storage::open_in_memory().unwrap();
+        let workdir = WorkingDir {
+            cwd: "/home/me/clients/acme-merger".into(),
+            repo: Some(crate::project::GitRepo {
+                root: "/home/me/clients/acme-merger".into(),
+                name: "acme-merger".to_string(),
+                branch: Some("layoffs-q3".to_string()),
+            }),
+        };
+        let redactor = |policy| {
+            let config = PrivacyConfig {
+                policies: vec![TitlePolicyRule { app: None, category: Some("Terminal".into()), policy }],
+                ..PrivacyConfig::default()
+            };
+            Redactor::from_config(&config, "salt")
+        };
+        let origin = SessionOrigin { workdir: Some(&workdir), ..Default::default() };
+        let parsed = workdir.parsed_title();
+        let workdirs = |storage: &Storage| {
+            let mut stmt = storage.conn.prepare("SELECT cwd, repo, branch FROM activity ORDER BY id").unwrap();
+            let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))).unwrap();
+            rows.collect::<rusqlite::Result<Vec<(Option<String>, Option<String>, Option<String>)>>>().unwrap()
+        };
+
+        // Stricter policies store neither the path nor the repo and branch
+        for policy in [TitlePolicy::ParsedOnly, TitlePolicy::Hash, TitlePolicy::Drop] {
+            let stored = redactor(policy).redact(policy, "zsh", &parsed, None);
+            let id = storage
+                .start_activity("Ghostty", "Terminal", &stored.title, None, &stored.parsed, &stored.origin(origin))
+                .unwrap();
+            storage.set_activity_redacted(id, policy).unwrap();
+        }
+        assert_eq!(workdirs(&storage), vec![(None, None, None); 3]);
+
+        // Redacting stored sessions drops them too, including sessions redacted before
+        let keep = redactor(TitlePolicy::Keep);
+        let stored = keep.redact(TitlePolicy::Keep, "zsh", &parsed, None);
+        storage
+            .start_activity("Ghostty", "Terminal", &stored.title, None, &stored.parsed, &stored.origin(origin))
+            .unwrap();
+        storage.conn.execute("UPDATE activity SET cwd = 'old', repo = 'old' WHERE id = 3", []).unwrap();
+        assert_eq!(workdirs(&storage)[3].0.as_deref(), Some("/home/me/clients/acme-merger"));
+        let hash = redactor(TitlePolicy::Hash);
+        assert_eq!(storage.redact_titles(SessionScope::All, false, |r| hash.redact_stored(r)).unwrap(), 3);
+        assert_eq!(workdirs(&storage), vec![(None, None, None); 4]);
+    }
+
+    #[test]
+    fn test_browser_sessions_record_their_url() {
+        let storage = Storage::open_in_memory().unwrap();
+        let url = "https://www.github.com/acme/app/pull/42";
+        let title = "Fix login · Pull Request #42 - Brave";
+        let origin = SessionOrigin { url: Some(url), ..Default::default() };
+        storage
+            .start_activity("Brave", "Browser", title, None, &crate::title_parser::parse_browser_url(url, title), &origin)
+            .unwrap();
+
+        let record = storage.get_active_session().unwrap().unwrap();
+        assert_eq!((record.url.as_deref(), record.domain.as_deref()), (Some(url), Some("github.com")));
+
+        // The URL says more than the title, so re-parsing leaves it alone
+        assert_eq!(storage.reparse_titles(SessionScope::All, false, parse_title).unwrap(), 0);
+        let parsed = storage.get_active_session().unwrap().unwrap().parsed.unwrap();
+        assert_eq!(parsed.context, "github.com/acme/app");
+    }
+
+    #[test]
+    fn test_redact_stored_titles() {
+        use crate::config::{PrivacyConfig, TitlePolicyRule};
+        use crate::privacy::Redactor;
+
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "Teams", "Communication", at(day, 9), 600);
+        insert(&storage, "VS Code", "Development", at(day, 10), 600);
+        storage.conn.execute(
+            "UPDATE activity SET window_title = 'Chat | Jane Doe | Microsoft Teams' WHERE app_name = 'Teams'",
+            [],
+        ).unwrap();
+        storage.conn.execute(
+            "UPDATE activity SET window_title = 'ACME-7 fix.rs - mail me@acme.com' WHERE app_name = 'VS Code'",
+            [],
+        ).unwrap();
+        storage.reparse_titles(SessionScope::All, true, parse_title).unwrap();
+
+        let salt = storage.title_hash_salt().unwrap();
+        assert_eq!(storage.title_hash_salt().unwrap(), salt);
+        let redactor = |policy| {
+            let config = PrivacyConfig {
+                policies: vec![TitlePolicyRule { app: None, category: Some("Communication".into()), policy }],
+                ..PrivacyConfig::default()
+            };
+            Redactor::from_config(&config, &salt)
+        };
+        let titles = |storage: &Storage| {
+            let mut titles = Vec::new();
+            storage.for_each_session(&TimeRange::day(day), &SessionFilter::default(), |r| {
+                titles.push((r.window_title, r.redacted));
+                Ok(())
+            }).unwrap();
+            titles
+        };
+
+        // A dry run counts without writing
+        let parsed_only = redactor(TitlePolicy::ParsedOnly);
+        assert_eq!(storage.redact_titles(SessionScope::All, true, |r| parsed_only.redact_stored(r)).unwrap(), 2);
+        assert_eq!(titles(&storage)[0].0, "Chat | Jane Doe | Microsoft Teams");
+
+        assert_eq!(storage.redact_titles(SessionScope::All, false, |r| parsed_only.redact_stored(r)).unwrap(), 2);
+        assert_eq!(
+            titles(&storage),
+            [
+                ("Jane Doe".to_string(), Some(TitlePolicy::ParsedOnly)),
+                ("ACME-7 fix.rs - mail [email]".to_string(), None),
+            ]
+        );
+        assert_eq!(storage.redact_titles(SessionScope::All, false, |r| parsed_only.redact_stored(r)).unwrap(), 0);
+
+        // A looser policy can't bring titles back; a stricter one applies on top
+        let keep = redactor(TitlePolicy::Keep);
+        assert_eq!(storage.redact_titles(SessionScope::All, false, |r| keep.redact_stored(r)).unwrap(), 0);
+        let drop = redactor(TitlePolicy::Drop);
+        assert_eq!(storage.redact_titles(SessionScope::All, false, |r| drop.redact_stored(r)).unwrap(), 1);
+        assert_eq!(titles(&storage)[0], (String::new(), Some(TitlePolicy::Drop)));
+        assert_eq!(storage.reparse_titles(SessionScope::All, false, parse_title).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_forget_sessions() {
+        let storage = Storage::open_in_memory().unwrap();
+        let old_day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "Brave", "Browser", at(old_day, 9), 600);
+        insert(&storage, "VS Code", "Development", at(old_day, 10), 600);
+        storage.compact_before(day).unwrap();
+        insert(&storage, "Brave", "Browser", at(day, 9), 300);
+        insert(&storage, "Brave", "Browser", at(day, 10), 200);
+        insert(&storage, "VS Code", "Development", at(day, 11), 100);
+        storage.conn.execute("UPDATE activity SET window_title = 'Clinic results - Brave' WHERE duration_secs = 300", []).unwrap();
+
+        assert!(storage.forget(&ForgetSelection::default(), false).is_err());
+
+        // Titles only match raw sessions; the preview deletes nothing
+        let by_title = ForgetSelection { title_regex: Some(Regex::new("(?i)clinic").unwrap()), ..Default::default() };
+        assert_eq!(storage.get_forget_sessions(&by_title).unwrap().len(), 1);
+        assert_eq!(storage.get_range_timeline(&TimeRange::day(day)).unwrap().len(), 3);
+        assert_eq!(storage.forget(&by_title, true).unwrap(), ForgetStats { sessions: 1, secs: 300, rollup_rows: 0 });
+
+        // An app over a range takes its rolled-up days too, and leaves other apps alone
+        let range = TimeRange::days(old_day, day);
+        let brave = ForgetSelection { app: Some("brave".into()), range: Some(range), ..Default::default() };
+        assert_eq!(storage.forget(&brave, false).unwrap(), ForgetStats { sessions: 1, secs: 200, rollup_rows: 1 });
+        let apps: Vec<(String, i64)> =
+            storage.get_range_summary(&range).unwrap().into_iter().map(|a| (a.app_name, a.total_secs)).collect();
+        assert_eq!(apps, [("VS Code".to_string(), 700)]);
+    }
+
+    #[test]
+    fn test_manual_entries_edit_split_and_merge() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        insert(&storage, "VS Code", "Development", at(day, 9), 3600);
+        storage.conn.execute("UPDATE activity SET active_secs = 2700, passive_secs = 900", []).unwrap();
+
+        let meeting = ManualSession {
+            app_name: "Meeting".into(),
+            category: "Meeting".into(),
+            window_title: "Design review".into(),
+            parsed: parse_title("Meeting", "Meeting", "Design review"),
+            started_at: at(day, 10),
+            ended_at: at(day, 10) + Duration::minutes(45),
+            project: Some("Acme".into()),
+            note: Some("Design review".into()),
+        };
+        let id = storage.add_manual_session(&meeting).unwrap();
+        let record = storage.get_session(id).unwrap().unwrap();
+        assert_eq!((record.source.as_str(), record.duration_secs, record.active_secs), ("manual", 2700, 2700));
+        assert_eq!(record.project.as_deref(), Some("Acme"));
+        assert!(storage.add_manual_session(&ManualSession { ended_at: meeting.started_at, ..meeting.clone() }).is_err());
+        let overlaps = storage.get_overlapping_sessions(at(day, 9) + Duration::minutes(30), at(day, 10) + Duration::minutes(1), Some(id));
+        assert_eq!(overlaps.unwrap().iter().map(|r| r.id).collect::<Vec<_>>(), [1]);
+
+        // Shortening keeps the active/passive share; a new app doesn't touch the project
+        let edit = SessionEdit {
+            ended_at: Some(at(day, 9) + Duration::minutes(40)),
+            app_name: Some("Zoom".into()),
+            project: Some(Some("Acme".into())),
+            note: Some(Some("Standup".into())),
+            ..Default::default()
+        };
+        let edited = storage.edit_session(1, &edit).unwrap();
+        assert_eq!((edited.app_name.as_str(), edited.category.as_str()), ("Zoom", "Development"));
+        assert_eq!((edited.duration_secs, edited.active_secs, edited.passive_secs), (2400, 1800, 600));
+        assert_eq!((edited.project.as_deref(), edited.note.as_deref()), (Some("Acme"), Some("Standup")));
+        let backwards = SessionEdit { started_at: Some(at(day, 10)), ..Default::default() };
+        assert!(storage.edit_session(1, &backwards).is_err());
+        assert!(storage.edit_session(99, &edit).is_err());
+
+        // Splitting copies everything but the times
+        let second = storage.split_session(1, at(day, 9) + Duration::minutes(10)).unwrap();
+        assert!(storage.split_session(1, at(day, 11)).is_err());
+        let (a, b) = (storage.get_session(1).unwrap().unwrap(), storage.get_session(second).unwrap().unwrap());
+        assert_eq!((a.duration_secs, a.active_secs, b.duration_secs, b.active_secs), (600, 450, 1800, 1350));
+        assert_eq!((b.started_at, b.app_name.as_str(), b.note.as_deref()), (a.end_time(), "Zoom", Some("Standup")));
+
+        // Merging sums tracked time over the whole span and joins notes
+        assert!(storage.merge_sessions(&[1, 1]).is_err());
+        let merged = storage.merge_sessions(&[id, second, 1]).unwrap();
+        assert_eq!((merged.id, merged.started_at, merged.ended_at), (1, at(day, 9), Some(meeting.ended_at)));
+        assert_eq!((merged.duration_secs, merged.active_secs, merged.passive_secs), (5100, 4500, 600));
+        assert_eq!(merged.note.as_deref(), Some("Standup; Design review"));
+        assert_eq!(storage.get_range_total_secs(&TimeRange::day(day)).unwrap(), 5100);
+    }
+
+    #[test]
+    fn test_parse_group_by_and_date() {
+        assert_eq!("Week".parse::<GroupBy>().unwrap(), GroupBy::Week);
+        assert!("fortnight".parse::<GroupBy>().is_err());
+        assert_eq!(parse_date("2024-02-29").unwrap(), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert!(parse_date("2024-02-30").is_err());
+    }
+
+    #[test]
+    fn test_pomodoro_sessions_roundtrip() {
+        let storage = Storage::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+        let work = storage.start_pomodoro(PomodoroKind::Work, Some("review"), 1500, at(day, 9)).unwrap();
+        storage.finish_pomodoro(work, PomodoroOutcome::Completed, 0, at(day, 9) + Duration::minutes(25)).unwrap();
+        let skipped = storage.start_pomodoro(PomodoroKind::Work, None, 1500, at(day, 10)).unwrap();
+        storage.finish_pomodoro(skipped, PomodoroOutcome::Skipped, 900, at(day, 10) + Duration::minutes(10)).unwrap();
+        let brk = storage.start_pomodoro(PomodoroKind::ShortBreak, None, 300, at(day, 11)).unwrap();
+
+        // Paused in-flight sessions keep their remaining time
+        storage.checkpoint_pomodoro(brk, 120, true, at(day, 11) + Duration::minutes(3)).unwrap();
+        let open = storage.get_open_pomodoro().unwrap().unwrap();
+        assert_eq!(open.kind, PomodoroKind::ShortBreak);
+        assert_eq!(open.remaining_at(at(day, 12)), 120);
+        storage.checkpoint_pomodoro(brk, 120, false, at(day, 11) + Duration::minutes(3)).unwrap();
+        let open = storage.get_open_pomodoro().unwrap().unwrap();
+        assert_eq!(open.remaining_at(at(day, 11) + Duration::minutes(4)), 60);
+
+        let days = storage.get_pomodoro_days(&TimeRange::days(day, day + Duration::days(1))).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].completed, days[0].skipped, days[0].breaks), (1, 1, 0));
+        assert_eq!(days[0].focus_secs, 1500 + 600);
+        assert_eq!(storage.get_pomodoro_streak_days().unwrap(), vec![day]);
+
+        // "+5 min" drops the running break and reopens the completed work session
+        storage.extend_last_work_pomodoro(300).unwrap();
+        let open = storage.get_open_pomodoro().unwrap().unwrap();
+        assert_eq!((open.id, open.planned_secs), (work, 1800));
+        assert!(storage.get_pomodoro_streak_days().unwrap().is_empty());
+    }
+}
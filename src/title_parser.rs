@@ -1,15 +1,25 @@
-/// Title Parser - Extract meaningful context from window titles
-///
-/// Parses application window titles to extract useful information like:
-/// - Teams: Chat partner, call participant, channel name
-/// - Terminal: Project folder, current directory
-/// - Browser: Website, page title
-
-use regex::Regex;
+//! Title Parser - Extract meaningful context from window titles
+//!
+//! Parses application window titles to extract useful information like:
+//! - Teams: Chat partner, call participant, channel name
+//! - Slack, Discord: Channel or direct message
+//! - Zoom: Meeting state (waiting, in a meeting, sharing the screen)
+//! - VS Code, JetBrains IDEs: Workspace (project) and open file
+//! - Terminal: Project folder, current directory
+//! - Browser: Website, page title (or the tab's URL, when the extension reports it)
+//!
+//! User-defined `[[title_rules]]` are tried first, so new apps and sites can be
+//! parsed without recompiling.
+
+use regex::{Captures, Regex};
 use std::sync::LazyLock;
 
+use crate::browser;
+use crate::config::{Config, TitleRule};
+use crate::storage::SessionOrigin;
+
 /// Parsed title with context
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParsedTitle {
     pub display: String,      // Cleaned display title
     pub context_type: String, // "call", "chat", "project", "website", etc.
@@ -29,6 +39,41 @@ static TEAMS_CHANNEL_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)^(?:\(\d+\)\s*)?(.+?)\s*\|\s*Microsoft Teams").unwrap()
 });
 
+/// Separator between title parts: " - ", " — " (VS Code) or " – " (JetBrains)
+static TITLE_SEPARATOR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+[-–—]\s+").unwrap());
+
+/// Product names VS Code and its forks append to window titles
+const VSCODE_SUFFIXES: &[&str] = &["Visual Studio Code - Insiders", "Visual Studio Code", "VSCodium", "Code - OSS", "Cursor"];
+
+/// JetBrains products, as used in app names and title suffixes
+const JETBRAINS_IDES: &[&str] = &[
+    "IntelliJ IDEA", "PyCharm", "WebStorm", "CLion", "GoLand", "RustRover", "Rider", "PhpStorm", "RubyMine",
+    "DataGrip", "Android Studio",
+];
+
+/// Slack 4.x: "general (Channel) - Acme - Slack", "Ana Silva (DM) - Acme - 2 new items - Slack"
+static SLACK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[!*]?\s*(?P<name>.+?) \((?P<kind>Channel|Private channel|DM|Group DM)\) - (?P<workspace>.+?)(?: - \d+ new items?)? - Slack$").unwrap()
+});
+
+/// Older Slack: "Slack | general | Acme", "Slack | Ana Silva | Acme"
+static SLACK_LEGACY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Slack \| (?P<name>.+?) \| (?P<workspace>.+)$").unwrap()
+});
+
+/// "#general | Rust Community - Discord", "Discord | #general | Rust Community"
+static DISCORD_CHANNEL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\(\d+\)\s*)?(?:Discord \| )?#(?P<channel>.+?) \| (?P<server>.+?)(?: - Discord)?$").unwrap()
+});
+
+/// "@ana - Discord", "Discord | @ana"
+static DISCORD_DM_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\(\d+\)\s*)?(?:Discord \| )?@(?P<user>.+?)(?: - Discord)?$").unwrap()
+});
+
+/// `{name}` placeholders in rule templates
+static PLACEHOLDER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
 static PATH_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?:^|/)([^/]+)$").unwrap()
 });
@@ -85,6 +130,233 @@ pub fn parse_teams_title(title: &str) -> ParsedTitle {
     }
 }
 
+/// Parse a Slack window title
+pub fn parse_slack_title(title: &str) -> ParsedTitle {
+    let title = title.trim();
+    let parsed = match SLACK_RE.captures(title) {
+        Some(caps) => Some((caps["name"].to_string(), caps["workspace"].to_string(), caps["kind"].ends_with("DM"))),
+        // The old title has no kind, but channel names are lowercase without spaces
+        None => SLACK_LEGACY_RE.captures(title).map(|caps| {
+            let name = &caps["name"];
+            let is_channel = name.starts_with('#') || !name.chars().any(|c| c.is_whitespace() || c.is_uppercase());
+            (name.to_string(), caps["workspace"].to_string(), !is_channel)
+        }),
+    };
+
+    if let Some((name, workspace, is_dm)) = parsed {
+        let name = name.trim();
+        if is_dm {
+            return ParsedTitle {
+                display: format!("Slack DM: {}", truncate(name, 30)),
+                context_type: "chat".to_string(),
+                context: name.to_string(),
+            };
+        }
+        let channel = format!("#{}", name.trim_start_matches('#'));
+        return ParsedTitle {
+            display: format!("Slack {} ({})", truncate(&channel, 25), truncate(workspace.trim(), 20)),
+            context_type: "channel".to_string(),
+            context: channel,
+        };
+    }
+
+    // Views like "Threads - Acme - Slack" or "Activity - Acme - Slack"
+    ParsedTitle {
+        display: "Slack".to_string(),
+        context_type: "app".to_string(),
+        context: "Slack".to_string(),
+    }
+}
+
+/// Parse a Discord window title
+pub fn parse_discord_title(title: &str) -> ParsedTitle {
+    let title = title.trim();
+    if let Some(caps) = DISCORD_CHANNEL_RE.captures(title) {
+        let channel = format!("#{}", caps["channel"].trim());
+        return ParsedTitle {
+            display: format!("Discord {} ({})", truncate(&channel, 25), truncate(caps["server"].trim(), 20)),
+            context_type: "channel".to_string(),
+            context: format!("{} {}", caps["server"].trim(), channel),
+        };
+    }
+
+    if let Some(caps) = DISCORD_DM_RE.captures(title) {
+        let user = caps["user"].trim();
+        return ParsedTitle {
+            display: format!("Discord DM: {}", truncate(user, 30)),
+            context_type: "chat".to_string(),
+            context: user.to_string(),
+        };
+    }
+
+    // Home, friends list, settings
+    ParsedTitle {
+        display: "Discord".to_string(),
+        context_type: "app".to_string(),
+        context: "Discord".to_string(),
+    }
+}
+
+/// Parse a Zoom window title into the meeting state
+pub fn parse_zoom_title(title: &str) -> ParsedTitle {
+    let lower = title.trim().to_lowercase();
+    let state = if lower.contains("waiting for host") || lower.contains("waiting room") {
+        Some("Waiting")
+    } else if lower.contains("sharing") || lower.contains("screen share") {
+        Some("Sharing screen")
+    } else if lower.contains("zoom meeting")
+        || lower.contains("zoom webinar")
+        || lower.starts_with("participants")
+        || lower.starts_with("meeting chat")
+        || lower.contains("breakout room")
+    {
+        Some("In meeting")
+    } else {
+        None
+    };
+
+    match state {
+        Some(state) => ParsedTitle {
+            display: format!("Zoom: {}", state),
+            context_type: "meeting".to_string(),
+            context: state.to_string(),
+        },
+        // Home window ("Zoom Workplace", "Zoom Cloud Meetings"), settings, ...
+        None => ParsedTitle {
+            display: "Zoom".to_string(),
+            context_type: "app".to_string(),
+            context: "Zoom".to_string(),
+        },
+    }
+}
+
+/// Parse a VS Code (or fork) title: "[●] file — workspace — Visual Studio Code"
+pub fn parse_vscode_title(title: &str) -> ParsedTitle {
+    let cleaned = title.trim().trim_start_matches(['●', '•', '*']).trim();
+    let cleaned = strip_product(cleaned, VSCODE_SUFFIXES).unwrap_or(cleaned);
+    let parts: Vec<&str> = TITLE_SEPARATOR_RE.split(cleaned).collect();
+
+    // Multi-root and remote workspaces: "flowmode (Workspace)", "flowmode [SSH: devbox]"
+    let workspace_name = |part: &str| {
+        let part = part.trim();
+        let part = part.split(" [").next().unwrap_or(part);
+        part.trim_end_matches(" (Workspace)").trim().to_string()
+    };
+    // Diff and preview editors: "main.rs (Working Tree)"
+    let file_name = |part: &str| {
+        let part = part.trim();
+        match part.rfind(" (") {
+            Some(i) if part.ends_with(')') => part[..i].to_string(),
+            _ => part.to_string(),
+        }
+    };
+
+    match parts.as_slice() {
+        [file, .., workspace] => {
+            let (file, workspace) = (file_name(file), workspace_name(workspace));
+            ParsedTitle {
+                display: format!("{}: {}", truncate(&workspace, 20), truncate(&file, 25)),
+                context_type: "project".to_string(),
+                context: workspace,
+            }
+        }
+        [only] if only.trim().is_empty() || is_untitled(only) || VSCODE_SUFFIXES.contains(&only.trim()) => {
+            editor_fallback(title)
+        }
+        [workspace] => {
+            let workspace = workspace_name(workspace);
+            ParsedTitle {
+                display: truncate(&workspace, 40),
+                context_type: "project".to_string(),
+                context: workspace,
+            }
+        }
+        [] => editor_fallback(title),
+    }
+}
+
+/// Parse a JetBrains IDE title: "project – file", "project [~/path] – …/src/file [module]"
+pub fn parse_jetbrains_title(title: &str) -> ParsedTitle {
+    let cleaned = title.trim();
+    let cleaned = strip_product(cleaned, JETBRAINS_IDES).unwrap_or(cleaned);
+    let parts: Vec<&str> = TITLE_SEPARATOR_RE.split(cleaned).collect();
+
+    if parts.len() == 1 && parts[0].starts_with("Welcome to ") {
+        return editor_fallback(parts[0]);
+    }
+
+    let project = parts[0].split(" [").next().unwrap_or(parts[0]).trim().to_string();
+    if project.is_empty() {
+        return editor_fallback(title);
+    }
+
+    // The file part may be a path with the module in brackets: "…/src/main/App.kt [app]"
+    let file = parts.get(1).map(|part| {
+        let part = part.split(" [").next().unwrap_or(part).trim();
+        part.rsplit('/').next().unwrap_or(part).to_string()
+    });
+
+    ParsedTitle {
+        display: match &file {
+            Some(file) => format!("{}: {}", truncate(&project, 20), truncate(file, 25)),
+            None => truncate(&project, 40),
+        },
+        context_type: "project".to_string(),
+        context: project,
+    }
+}
+
+/// `title` without a trailing " - <product>" part, if it has one of `products`
+fn strip_product<'a>(title: &'a str, products: &[&str]) -> Option<&'a str> {
+    products.iter().find_map(|product| {
+        let rest = title.strip_suffix(product)?.trim_end();
+        let rest = rest.strip_suffix(['-', '–', '—'])?;
+        // Only a whole part counts: "main.rs - Cursor", not "Ghost Rider" or "x-Cursor"
+        (rest.ends_with(char::is_whitespace)).then(|| rest.trim_end())
+    })
+}
+
+fn is_untitled(part: &str) -> bool {
+    let part = part.trim();
+    part.starts_with("Untitled-") || part == "Welcome"
+}
+
+/// Editor window without a workspace (welcome screen, untitled file)
+fn editor_fallback(title: &str) -> ParsedTitle {
+    ParsedTitle {
+        display: truncate(title.trim(), 40),
+        context_type: "app".to_string(),
+        context: title.trim().to_string(),
+    }
+}
+
+/// Built-in parser for a specific app, recognised by its name or title suffix
+fn app_parser(app_name: &str, title: &str) -> Option<fn(&str) -> ParsedTitle> {
+    let app = app_name.to_lowercase();
+    let title = title.trim();
+
+    if app.contains("teams") {
+        Some(parse_teams_title)
+    } else if app.contains("slack") {
+        Some(parse_slack_title)
+    } else if app.contains("discord") {
+        Some(parse_discord_title)
+    } else if app.contains("zoom") {
+        Some(parse_zoom_title)
+    } else if ["vs code", "vscode", "visual studio code", "vscodium", "code - oss", "cursor"].contains(&app.as_str())
+        || strip_product(title, VSCODE_SUFFIXES).is_some()
+    {
+        Some(parse_vscode_title)
+    } else if app.contains("jetbrains")
+        || JETBRAINS_IDES.iter().any(|ide| app.contains(&ide.to_lowercase()))
+        || strip_product(title, JETBRAINS_IDES).is_some()
+    {
+        Some(parse_jetbrains_title)
+    } else {
+        None
+    }
+}
+
 /// Parse a terminal window title (Ghostty, Konsole, etc.)
 pub fn parse_terminal_title(title: &str) -> ParsedTitle {
     let cleaned = title.trim();
@@ -144,15 +416,7 @@ pub fn parse_terminal_title(title: &str) -> ParsedTitle {
 
 /// Parse a browser window title
 pub fn parse_browser_title(title: &str) -> ParsedTitle {
-    let cleaned = title.trim();
-
-    // Remove browser suffix (Brave, Chrome, etc.)
-    let cleaned = cleaned
-        .trim_end_matches(" - Brave")
-        .trim_end_matches(" - Google Chrome")
-        .trim_end_matches(" - Firefox")
-        .trim_end_matches(" - Microsoft Edge")
-        .trim();
+    let cleaned = strip_browser_suffix(title);
 
     // Detect common sites
     let lower = cleaned.to_lowercase();
@@ -245,20 +509,69 @@ pub fn parse_browser_title(title: &str) -> ParsedTitle {
     }
 }
 
+/// Remove the browser's name (Brave, Chrome, etc.) from a window title
+fn strip_browser_suffix(title: &str) -> &str {
+    title
+        .trim()
+        .trim_end_matches(" - Brave")
+        .trim_end_matches(" - Google Chrome")
+        .trim_end_matches(" - Firefox")
+        .trim_end_matches(" - Microsoft Edge")
+        .trim()
+}
+
+/// Parse a browser tab from its URL, which names the site more reliably than
+/// the title. Falls back to `parse_browser_title` for non-web URLs.
+pub fn parse_browser_url(url: &str, title: &str) -> ParsedTitle {
+    let (Some(domain), Ok(parsed_url)) = (browser::domain(url), url::Url::parse(url)) else {
+        return parse_browser_title(title);
+    };
+    let page = strip_browser_suffix(title);
+    let segments: Vec<&str> = parsed_url.path_segments().map(|s| s.filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+    let parsed = |display: String, context_type: &str, context: &str| ParsedTitle {
+        display,
+        context_type: context_type.to_string(),
+        context: context.to_string(),
+    };
+
+    match domain.as_str() {
+        "youtube.com" | "m.youtube.com" | "youtu.be" if segments.first().is_some_and(|s| *s == "watch") || domain == "youtu.be" => {
+            let video = page.trim_end_matches("- YouTube").trim();
+            parsed(format!("YT: {}", truncate(video, 35)), "video", video)
+        }
+        "github.com" if !segments.is_empty() => {
+            let repo = segments.iter().take(2).copied().collect::<Vec<_>>().join("/");
+            parsed(format!("GitHub: {}", truncate(&repo, 30)), "code", &format!("github.com/{}", repo))
+        }
+        d if d == "stackoverflow.com" || d.ends_with(".stackexchange.com") => {
+            let question = page.trim_end_matches("- Stack Overflow").trim();
+            parsed(format!("SO: {}", truncate(question, 35)), "research", question)
+        }
+        // Subjects stay out of the context; the inbox is enough
+        "mail.google.com" | "outlook.office.com" | "outlook.live.com" => parsed("Email".to_string(), "email", &domain),
+        "chatgpt.com" | "chat.openai.com" | "claude.ai" | "gemini.google.com" => {
+            parsed("AI Assistant".to_string(), "ai", &domain)
+        }
+        "docs.google.com" => parsed(format!("Docs: {}", truncate(page, 30)), "document", page),
+        _ => {
+            let display = if page.is_empty() { domain.clone() } else { truncate(page, 40) };
+            parsed(display, "website", &domain)
+        }
+    }
+}
+
 /// Parse any window title based on app category
 pub fn parse_title(app_name: &str, category: &str, title: &str) -> ParsedTitle {
+    if let Some(parse) = app_parser(app_name, title) {
+        return parse(title);
+    }
+
     match category.to_lowercase().as_str() {
-        "communication" => {
-            if app_name.to_lowercase().contains("teams") {
-                parse_teams_title(title)
-            } else {
-                ParsedTitle {
-                    display: truncate(title, 40),
-                    context_type: "communication".to_string(),
-                    context: title.to_string(),
-                }
-            }
-        }
+        "communication" => ParsedTitle {
+            display: truncate(title, 40),
+            context_type: "communication".to_string(),
+            context: title.to_string(),
+        },
         "terminal" => parse_terminal_title(title),
         "browser" => parse_browser_title(title),
         _ => ParsedTitle {
@@ -269,6 +582,131 @@ pub fn parse_title(app_name: &str, category: &str, title: &str) -> ParsedTitle {
     }
 }
 
+/// A `[[title_rules]]` entry with its regex compiled
+#[derive(Debug)]
+struct CompiledRule {
+    /// Position in `[[title_rules]]`
+    index: usize,
+    rule: TitleRule,
+    regex: Regex,
+}
+
+impl CompiledRule {
+    fn applies_to(&self, app_name: &str, category: &str) -> bool {
+        let app_ok = self.rule.app.as_deref().is_none_or(|a| a.eq_ignore_ascii_case(app_name));
+        let category_ok = self.rule.category.as_deref().is_none_or(|c| c.eq_ignore_ascii_case(category));
+        app_ok && category_ok
+    }
+}
+
+/// How a user rule parsed a title, for `flowmode titles test`
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    /// Position of the rule in `[[title_rules]]`, from 0
+    pub index: usize,
+    pub pattern: String,
+    /// (capture name, value) for each named capture that matched
+    pub captures: Vec<(String, String)>,
+    pub parsed: ParsedTitle,
+}
+
+/// Window title parser: user rules first, then the built-in parsers
+#[derive(Debug)]
+pub struct TitleParser {
+    rules: Vec<CompiledRule>,
+}
+
+impl TitleParser {
+    /// Parser for the configured rules, skipping (and logging) invalid ones
+    pub fn from_config(config: &Config) -> Self {
+        let rules = config
+            .title_rules
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rule)| match Regex::new(&rule.pattern) {
+                Ok(regex) => Some(CompiledRule { index: i, rule: rule.clone(), regex }),
+                Err(e) => {
+                    tracing::warn!("Skipping title_rules[{}] ('{}'): {}", i, rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Parse a window title of `app_name` (in `category`)
+    pub fn parse(&self, app_name: &str, category: &str, title: &str) -> ParsedTitle {
+        match self.match_rule(app_name, category, title) {
+            Some(matched) => matched.parsed,
+            None => parse_title(app_name, category, title),
+        }
+    }
+
+    /// Like `parse`, but a terminal's working directory (its git project or
+    /// folder) or a browser tab's URL takes precedence over the built-in
+    /// parsers. User rules still win.
+    pub fn parse_with_origin(
+        &self,
+        app_name: &str,
+        category: &str,
+        title: &str,
+        origin: &SessionOrigin,
+    ) -> ParsedTitle {
+        if let Some(matched) = self.match_rule(app_name, category, title) {
+            return matched.parsed;
+        }
+        match (origin.workdir, origin.url) {
+            (Some(workdir), _) => workdir.parsed_title(),
+            (None, Some(url)) => parse_browser_url(url, title),
+            (None, None) => parse_title(app_name, category, title),
+        }
+    }
+
+    /// The first user rule that parses `title`, if any
+    pub fn match_rule(&self, app_name: &str, category: &str, title: &str) -> Option<RuleMatch> {
+        self.rules.iter().find_map(|compiled| {
+            if !compiled.applies_to(app_name, category) {
+                return None;
+            }
+            let caps = compiled.regex.captures(title)?;
+            let rule = &compiled.rule;
+            let parsed = ParsedTitle {
+                display: expand(&rule.display, &caps, app_name, title),
+                context_type: rule.context_type.clone(),
+                context: match &rule.context {
+                    Some(template) => expand(template, &caps, app_name, title),
+                    None => title.to_string(),
+                },
+            };
+            let captures = compiled
+                .regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| caps.name(name).map(|m| (name.to_string(), m.as_str().to_string())))
+                .collect();
+            Some(RuleMatch { index: compiled.index, pattern: rule.pattern.clone(), captures, parsed })
+        })
+    }
+}
+
+/// Fill `{name}` (named capture), `{1}` (numbered capture), `{title}` and `{app}`
+/// in a rule template; unknown placeholders are left as they are
+fn expand(template: &str, caps: &Captures, app_name: &str, title: &str) -> String {
+    let filled = PLACEHOLDER_RE.replace_all(template, |p: &Captures| {
+        let key = &p[1];
+        let value = match key.parse::<usize>() {
+            Ok(i) => caps.get(i).map(|m| m.as_str()),
+            Err(_) => caps.name(key).map(|m| m.as_str()).or(match key {
+                "title" => Some(title),
+                "app" => Some(app_name),
+                _ => None,
+            }),
+        };
+        value.map(|v| v.trim().to_string()).unwrap_or_else(|| p[0].to_string())
+    });
+    filled.trim().to_string()
+}
+
 /// Truncate string to max length, adding ellipsis if needed
 fn truncate(s: &str, max_len: usize) -> String {
     if s.chars().count() <= max_len {
@@ -307,6 +745,151 @@ mod tests {
         assert!(parsed.display.contains("FlowMode"));
     }
 
+    /// Parse each (app, title) through `parse_title` and compare (display, context type, context)
+    fn check_corpus(category: &str, corpus: &[(&str, &str, &str, &str, &str)]) {
+        for (app, title, display, context_type, context) in corpus {
+            let parsed = parse_title(app, category, title);
+            assert_eq!(
+                (parsed.display.as_str(), parsed.context_type.as_str(), parsed.context.as_str()),
+                (*display, *context_type, *context),
+                "{} title {:?}",
+                app,
+                title
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_vscode_titles() {
+        check_corpus("Development", &[
+            ("VS Code", "main.rs - flowmode - Visual Studio Code", "flowmode: main.rs", "project", "flowmode"),
+            ("VS Code", "● storage.rs — flowmode — Visual Studio Code", "flowmode: storage.rs", "project", "flowmode"),
+            ("VS Code", "flowmode - Visual Studio Code", "flowmode", "project", "flowmode"),
+            ("VS Code", "README.md - flowmode (Workspace) - Visual Studio Code", "flowmode: README.md", "project", "flowmode"),
+            ("VS Code", "api.py - backend [SSH: devbox] - Visual Studio Code", "backend: api.py", "project", "backend"),
+            ("VS Code", "web.rs (Working Tree) - flowmode - Visual Studio Code", "flowmode: web.rs", "project", "flowmode"),
+            ("VS Code", "settings.json - flowmode - Visual Studio Code - Insiders", "flowmode: settings.json", "project", "flowmode"),
+            ("VS Code", "Untitled-1 - Visual Studio Code", "Untitled-1 - Visual Studio Code", "app", "Untitled-1 - Visual Studio Code"),
+            ("VS Code", "Visual Studio Code", "Visual Studio Code", "app", "Visual Studio Code"),
+            // Forks, recognised by app name or by title
+            ("VSCodium", "lib.rs - parser - VSCodium", "parser: lib.rs", "project", "parser"),
+            ("Editor", "index.ts - webapp - Cursor", "webapp: index.ts", "project", "webapp"),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_jetbrains_titles() {
+        check_corpus("Development", &[
+            ("IntelliJ IDEA", "flowmode – Main.java", "flowmode: Main.java", "project", "flowmode"),
+            ("IntelliJ IDEA", "shop [~/IdeaProjects/shop] – …/src/main/java/Cart.java [shop.main]", "shop: Cart.java", "project", "shop"),
+            ("PyCharm", "backend – views.py", "backend: views.py", "project", "backend"),
+            ("RustRover", "flowmode", "flowmode", "project", "flowmode"),
+            ("GoLand", "Welcome to GoLand", "Welcome to GoLand", "app", "Welcome to GoLand"),
+            ("Android Studio", "MyApp – MainActivity.kt [MyApp.app.main]", "MyApp: MainActivity.kt", "project", "MyApp"),
+            // Older titles end with the product name
+            ("IDE", "shop - Cart.java - IntelliJ IDEA", "shop: Cart.java", "project", "shop"),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_chat_app_titles() {
+        check_corpus("Communication", &[
+            ("Slack", "general (Channel) - Acme - Slack", "Slack #general (Acme)", "channel", "#general"),
+            ("Slack", "! deploys (Private channel) - Acme - 3 new items - Slack", "Slack #deploys (Acme)", "channel", "#deploys"),
+            ("Slack", "Ana Silva (DM) - Acme - Slack", "Slack DM: Ana Silva", "chat", "Ana Silva"),
+            ("Slack", "Ana, Bo (Group DM) - Acme - 1 new item - Slack", "Slack DM: Ana, Bo", "chat", "Ana, Bo"),
+            ("Slack", "Slack | random | Acme", "Slack #random (Acme)", "channel", "#random"),
+            ("Slack", "Slack | Ana Silva | Acme", "Slack DM: Ana Silva", "chat", "Ana Silva"),
+            ("Slack", "Threads - Acme - Slack", "Slack", "app", "Slack"),
+            ("Discord", "#general | Rust Community - Discord", "Discord #general (Rust Community)", "channel", "Rust Community #general"),
+            ("Discord", "(3) Discord | #help | Rust Community", "Discord #help (Rust Community)", "channel", "Rust Community #help"),
+            ("Discord", "@ana - Discord", "Discord DM: ana", "chat", "ana"),
+            ("Discord", "Friends - Discord", "Discord", "app", "Discord"),
+            ("Zoom", "Zoom Meeting", "Zoom: In meeting", "meeting", "In meeting"),
+            ("Zoom", "Participants (12)", "Zoom: In meeting", "meeting", "In meeting"),
+            ("Zoom", "Waiting for Host", "Zoom: Waiting", "meeting", "Waiting"),
+            ("Zoom", "You are screen sharing", "Zoom: Sharing screen", "meeting", "Sharing screen"),
+            ("Zoom", "Zoom Workplace", "Zoom", "app", "Zoom"),
+            // Other chat apps keep the generic parse
+            ("Signal", "Signal", "Signal", "communication", "Signal"),
+        ]);
+    }
+
+    fn rule(app: Option<&str>, category: Option<&str>, pattern: &str, display: &str, context: Option<&str>) -> TitleRule {
+        TitleRule {
+            app: app.map(str::to_string),
+            category: category.map(str::to_string),
+            pattern: pattern.to_string(),
+            display: display.to_string(),
+            context_type: "ticket".to_string(),
+            context: context.map(str::to_string),
+        }
+    }
+
+    fn parser(title_rules: Vec<TitleRule>) -> TitleParser {
+        TitleParser::from_config(&Config { title_rules, ..Config::default() })
+    }
+
+    #[test]
+    fn test_rules_run_before_builtin_parsers() {
+        let parser = parser(vec![
+            rule(Some("slack"), None, r"^(?P<channel>#\S+)", "Slack {channel}", None),
+            rule(None, Some("Browser"), r"\[(?P<key>[A-Z]+-\d+)\] (?P<summary>.+?) - Jira", "{key}: {summary}", Some("{key}")),
+        ]);
+
+        let title = "[PROJ-42] Fix login - Jira - Brave";
+        let parsed = parser.parse("Brave", "Browser", title);
+        assert_eq!((parsed.display.as_str(), parsed.context.as_str()), ("PROJ-42: Fix login", "PROJ-42"));
+        let matched = parser.match_rule("Brave", "Browser", title).unwrap();
+        assert_eq!(matched.index, 1);
+        assert_eq!(matched.captures[0], ("key".to_string(), "PROJ-42".to_string()));
+
+        // Selectors limit a rule to its app or category
+        assert!(parser.match_rule("Teams", "Communication", "#general").is_none());
+        let parsed = parser.parse("Slack", "Communication", "#general | Acme");
+        assert_eq!((parsed.display.as_str(), parsed.context.as_str()), ("Slack #general", "#general | Acme"));
+
+        // No rule matches: the built-in parser takes over
+        assert_eq!(parser.parse("Brave", "Browser", "Amazing Video - YouTube").context_type, "video");
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        // The broken rule is skipped, the next one still applies
+        let parser = parser(vec![rule(None, None, "(unclosed", "x", None), rule(None, None, "^(.*)$", "{1} {missing}", None)]);
+        let matched = parser.match_rule("Any", "Other", "hello").unwrap();
+        assert_eq!((matched.index, matched.parsed.display.as_str()), (1, "hello {missing}"));
+    }
+
+    #[test]
+    fn test_parse_browser_urls() {
+        let cases = [
+            ("https://www.youtube.com/watch?v=abc", "Amazing Video - YouTube - Brave", "video", "YT: Amazing Video", "Amazing Video"),
+            ("https://www.youtube.com/", "(3) YouTube - Brave", "website", "(3) YouTube", "youtube.com"),
+            ("https://github.com/acme/app/pull/42", "Fix login by someone · Pull Request #42", "code", "GitHub: acme/app", "github.com/acme/app"),
+            ("https://mail.google.com/mail/u/0/#inbox/123", "Payslip for March - me@example.com - Gmail", "email", "Email", "mail.google.com"),
+            ("https://docs.rs/regex/latest/regex/", "regex - Rust - Google Chrome", "website", "regex - Rust", "docs.rs"),
+            // Not a web page: fall back to the title
+            ("chrome://settings/", "Settings - Google Chrome", "website", "Settings", "Settings"),
+        ];
+        for (url, title, context_type, display, context) in cases {
+            let parsed = parse_browser_url(url, title);
+            assert_eq!(
+                (parsed.context_type.as_str(), parsed.display.as_str(), parsed.context.as_str()),
+                (context_type, display, context),
+                "{}",
+                url
+            );
+        }
+
+        // A reported URL beats the title, a rule beats both
+        let origin = SessionOrigin { url: Some("https://github.com/acme/app"), ..Default::default() };
+        let parsed = parser(vec![]).parse_with_origin("Brave", "Browser", "Inbox (3) - Brave", &origin);
+        assert_eq!(parsed.context, "github.com/acme/app");
+        let parser = parser(vec![rule(None, Some("Browser"), r"^Inbox", "Mail", None)]);
+        assert_eq!(parser.parse_with_origin("Brave", "Browser", "Inbox (3) - Brave", &origin).display, "Mail");
+    }
+
     #[test]
     fn test_parse_browser_youtube() {
         let title = "Amazing Video - YouTube - Brave";
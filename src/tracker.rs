@@ -6,8 +6,13 @@ use tracing::{debug, warn};
 #[derive(Debug, Clone)]
 pub struct WindowInfo {
     pub window_id: String,
+    /// WM_CLASS instance, e.g. "Navigator" for Firefox
     pub window_class: String,
+    /// WM_CLASS class, e.g. "firefox"
+    pub window_class_name: String,
     pub window_title: String,
+    /// Process owning the window (`_NET_WM_PID`), when the client sets it
+    pub pid: Option<u32>,
 }
 
 /// Get the currently active window information
@@ -39,40 +44,51 @@ pub fn get_active_window() -> Result<WindowInfo> {
         .to_string();
 
     // Get window class using xprop
-    let window_class = get_window_class(&window_id)?;
+    let (window_class, window_class_name) = get_window_class(&window_id)?;
+
+    let pid = get_window_pid(&window_id);
 
     debug!("Active window: {} - {} ({})", window_class, window_title, window_id);
 
     Ok(WindowInfo {
         window_id,
         window_class,
+        window_class_name,
         window_title,
+        pid,
     })
 }
 
-/// Get window class using xprop
-fn get_window_class(window_id: &str) -> Result<String> {
+/// Get the pid of the window's owner using xdotool
+fn get_window_pid(window_id: &str) -> Option<u32> {
+    let output = Command::new("xdotool")
+        .args(["getwindowpid", window_id])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Get window class using xprop: (instance, class)
+fn get_window_class(window_id: &str) -> Result<(String, String)> {
     let output = Command::new("xprop")
         .args(["-id", window_id, "WM_CLASS"])
         .output()?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    // Parse: WM_CLASS(STRING) = "instance", "class"
-    if let Some(start) = stdout.find('"') {
-        let rest = &stdout[start + 1..];
-        if let Some(end) = rest.find('"') {
-            return Ok(rest[..end].to_string());
-        }
-    }
+    Ok(parse_wm_class(&String::from_utf8_lossy(&output.stdout)))
+}
 
-    // Fallback: try second quoted string (the actual class)
-    let parts: Vec<&str> = stdout.split('"').collect();
-    if parts.len() >= 4 {
-        return Ok(parts[3].to_string());
+/// Parse `WM_CLASS(STRING) = "instance", "class"`
+fn parse_wm_class(xprop: &str) -> (String, String) {
+    let mut quoted = xprop.split('"').skip(1).step_by(2);
+    match (quoted.next(), quoted.next()) {
+        (Some(instance), class) => (instance.to_string(), class.unwrap_or_default().to_string()),
+        (None, _) => ("unknown".to_string(), String::new()),
     }
-
-    Ok("unknown".to_string())
 }
 
 /// Get idle time in seconds using xprintidle
@@ -112,4 +128,11 @@ mod tests {
             println!("Active window: {:?}", result);
         }
     }
+
+    #[test]
+    fn test_parse_wm_class() {
+        assert_eq!(parse_wm_class("WM_CLASS(STRING) = \"Navigator\", \"firefox\"\n"), ("Navigator".into(), "firefox".into()));
+        assert_eq!(parse_wm_class("WM_CLASS(STRING) = \"code\"\n"), ("code".into(), String::new()));
+        assert_eq!(parse_wm_class("WM_CLASS:  not found.\n"), ("unknown".into(), String::new()));
+    }
 }
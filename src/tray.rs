@@ -3,12 +3,15 @@ use ksni::{Tray, TrayService};
 use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
 use tokio::sync::mpsc;
 
+use crate::pomodoro::{PomodoroCommand, PomodoroKind, SharedPomodoro};
+
 /// Commands from tray menu
 #[derive(Debug, Clone)]
 pub enum TrayCommand {
     OpenDashboard,
     Pause,
     Resume,
+    Pomodoro(PomodoroCommand),
     Quit,
 }
 
@@ -18,35 +21,22 @@ pub struct FlowModeTray {
     is_idle: Arc<AtomicBool>,
     idle_secs: Arc<AtomicU64>,
     today_time: Arc<std::sync::RwLock<String>>,
+    pomodoro: SharedPomodoro,
     tx: mpsc::Sender<TrayCommand>,
 }
 
 impl FlowModeTray {
-    pub fn new(tx: mpsc::Sender<TrayCommand>) -> Self {
+    pub fn new(tx: mpsc::Sender<TrayCommand>, pomodoro: SharedPomodoro) -> Self {
         Self {
             is_tracking: Arc::new(AtomicBool::new(true)),
             is_idle: Arc::new(AtomicBool::new(false)),
             idle_secs: Arc::new(AtomicU64::new(0)),
             today_time: Arc::new(std::sync::RwLock::new("0m".into())),
+            pomodoro,
             tx,
         }
     }
 
-    pub fn set_today_time(&self, time: &str) {
-        if let Ok(mut today) = self.today_time.write() {
-            *today = time.to_string();
-        }
-    }
-
-    pub fn set_idle(&self, idle: bool, secs: u64) {
-        self.is_idle.store(idle, Ordering::Relaxed);
-        self.idle_secs.store(secs, Ordering::Relaxed);
-    }
-
-    pub fn is_tracking(&self) -> bool {
-        self.is_tracking.load(Ordering::Relaxed)
-    }
-
     pub fn tracking_handle(&self) -> Arc<AtomicBool> {
         self.is_tracking.clone()
     }
@@ -62,6 +52,80 @@ impl FlowModeTray {
     pub fn today_time_handle(&self) -> Arc<std::sync::RwLock<String>> {
         self.today_time.clone()
     }
+
+    /// Pomodoro line for the tooltip and menu, e.g. "Report: Work 12:34 (2/8 today)"
+    fn pomodoro_status(&self) -> String {
+        let snapshot = self.pomodoro.snapshot();
+        let progress = format!(
+            "{}/{} today",
+            self.pomodoro.get_completed_pomodoros(),
+            self.pomodoro.settings().daily_target
+        );
+        let status = match snapshot.kind {
+            Some(kind) => format!(
+                "{} {}{} ({})",
+                kind.label(),
+                self.pomodoro.format_remaining(),
+                if snapshot.paused { ", paused" } else { "" },
+                progress
+            ),
+            None => format!("Not running ({})", progress),
+        };
+        match self.pomodoro.label() {
+            Some(label) => format!("{}: {}", label, status),
+            None => status,
+        }
+    }
+
+    /// Pomodoro submenu: Start always (it starts over), Pause or Resume, Skip, Reset
+    fn pomodoro_menu(&self) -> ksni::MenuItem<Self> {
+        use ksni::menu::*;
+
+        let snapshot = self.pomodoro.snapshot();
+        let active = snapshot.kind.is_some();
+        let item = |label: &str, enabled: bool, command: fn(&Self) -> PomodoroCommand| -> MenuItem<Self> {
+            StandardItem {
+                label: label.into(),
+                enabled,
+                activate: Box::new(move |tray: &mut Self| {
+                    let _ = tray.tx.blocking_send(TrayCommand::Pomodoro(command(tray)));
+                }),
+                ..Default::default()
+            }
+            .into()
+        };
+
+        let mut submenu = vec![
+            StandardItem {
+                label: self.pomodoro_status(),
+                enabled: false,
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            // Starting from the tray carries on with the current label
+            item("▶ Start", true, |tray| PomodoroCommand::Start { label: tray.pomodoro.label() }),
+        ];
+        if snapshot.paused {
+            submenu.push(item("▶ Resume", true, |_| PomodoroCommand::Resume));
+        } else {
+            submenu.push(item("⏸ Pause", active, |_| PomodoroCommand::Pause));
+        }
+        submenu.push(item("⏭ Skip", active, |_| PomodoroCommand::Skip));
+        submenu.push(item("↺ Reset", active, |_| PomodoroCommand::Reset));
+
+        let label = match snapshot.kind {
+            Some(PomodoroKind::Work) => format!("🍅 Pomodoro {}", self.pomodoro.format_remaining()),
+            Some(_) => format!("☕ Break {}", self.pomodoro.format_remaining()),
+            None => "🍅 Pomodoro".into(),
+        };
+        SubMenu {
+            label,
+            submenu,
+            ..Default::default()
+        }
+        .into()
+    }
 }
 
 impl Tray for FlowModeTray {
@@ -84,12 +148,14 @@ impl Tray for FlowModeTray {
             .map(|t| t.clone())
             .unwrap_or_else(|_| "0m".into());
 
+        let pomodoro = self.pomodoro.get_tray_status().unwrap_or_default();
+
         if self.is_idle.load(Ordering::Relaxed) {
-            format!("⏸ {}", time)
+            format!("⏸ {}{}", time, pomodoro)
         } else if self.is_tracking.load(Ordering::Relaxed) {
-            format!("▶ {}", time)
+            format!("▶ {}{}", time, pomodoro)
         } else {
-            format!("⏹ {}", time)
+            format!("⏹ {}{}", time, pomodoro)
         }
     }
 
@@ -108,15 +174,20 @@ impl Tray for FlowModeTray {
             "Paused".into()
         };
 
+        let mut description = format!(
+            "<b>{}</b><br/>\
+             <b>Status:</b> {}<br/>\
+             <b>Today:</b> {}",
+            date, status, time
+        );
+        if self.pomodoro.is_enabled() {
+            description.push_str(&format!("<br/><b>Pomodoro:</b> {}", self.pomodoro_status()));
+        }
+
         ksni::ToolTip {
             icon_name: "chronometer".into(),
             title: "FlowMode".into(),
-            description: format!(
-                "<b>{}</b><br/>\
-                 <b>Status:</b> {}<br/>\
-                 <b>Today:</b> {}",
-                date, status, time
-            ),
+            description,
             icon_pixmap: Vec::new(),
         }
     }
@@ -137,7 +208,7 @@ impl Tray for FlowModeTray {
             "⏹ Paused".into()
         };
 
-        vec![
+        let mut items = vec![
             // Date header
             StandardItem {
                 label: format!("📅 {}", date),
@@ -175,11 +246,18 @@ impl Tray for FlowModeTray {
             }.into(),
 
             MenuItem::Separator,
+        ];
+
+        if self.pomodoro.is_enabled() {
+            items.push(self.pomodoro_menu());
+            items.push(MenuItem::Separator);
+        }
 
+        items.extend([
             // Pause/Resume
             if is_tracking {
                 StandardItem {
-                    label: "⏸ Pause".into(),
+                    label: "⏸ Pause tracking".into(),
                     activate: Box::new(|tray: &mut Self| {
                         tray.is_tracking.store(false, Ordering::Relaxed);
                         let _ = tray.tx.blocking_send(TrayCommand::Pause);
@@ -188,7 +266,7 @@ impl Tray for FlowModeTray {
                 }.into()
             } else {
                 StandardItem {
-                    label: "▶ Resume".into(),
+                    label: "▶ Resume tracking".into(),
                     activate: Box::new(|tray: &mut Self| {
                         tray.is_tracking.store(true, Ordering::Relaxed);
                         let _ = tray.tx.blocking_send(TrayCommand::Resume);
@@ -208,7 +286,8 @@ impl Tray for FlowModeTray {
                 }),
                 ..Default::default()
             }.into(),
-        ]
+        ]);
+        items
     }
 }
 
@@ -221,13 +300,13 @@ pub struct TrayHandles {
 }
 
 /// Start the tray service
-pub fn start_tray_service() -> anyhow::Result<(
+pub fn start_tray_service(pomodoro: SharedPomodoro) -> anyhow::Result<(
     TrayService<FlowModeTray>,
     mpsc::Receiver<TrayCommand>,
     TrayHandles,
 )> {
     let (tx, rx) = mpsc::channel(100);
-    let tray = FlowModeTray::new(tx);
+    let tray = FlowModeTray::new(tx, pomodoro);
 
     let handles = TrayHandles {
         tracking: tray.tracking_handle(),
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use chrono::{Local, Timelike};
+use chrono::{Duration, Local, NaiveDate, Timelike};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
@@ -10,12 +10,17 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Sparkline, Tabs, Wrap},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Gauge, List, ListItem, Paragraph, Sparkline, Tabs, Wrap},
     Frame, Terminal,
 };
 use std::io;
 
-use crate::storage::{AppSummary, HourlyActivity, Storage};
+use crate::client::DaemonClient;
+use crate::pomodoro::{InterruptionKind, PomodoroKind};
+use crate::storage::{
+    AppSummary, Breakdown, CategorySummary, GroupBy, HourlyActivity, PeriodSummary, PomodoroSession,
+    ProjectSummary, Storage, TimeRange,
+};
 use crate::tray::format_duration;
 
 /// Available tabs in the TUI
@@ -24,11 +29,15 @@ enum Tab {
     Summary,
     Detailed,
     Timeline,
+    Week,
+    Projects,
 }
 
+const TAB_COUNT: usize = 5;
+
 impl Tab {
     fn titles() -> Vec<&'static str> {
-        vec!["[1] Summary", "[2] Detailed", "[3] Timeline"]
+        vec!["[1] Summary", "[2] Detailed", "[3] Timeline", "[4] Week", "[5] Projects"]
     }
 
     fn index(&self) -> usize {
@@ -36,6 +45,8 @@ impl Tab {
             Tab::Summary => 0,
             Tab::Detailed => 1,
             Tab::Timeline => 2,
+            Tab::Week => 3,
+            Tab::Projects => 4,
         }
     }
 
@@ -44,23 +55,55 @@ impl Tab {
             0 => Tab::Summary,
             1 => Tab::Detailed,
             2 => Tab::Timeline,
+            3 => Tab::Week,
+            4 => Tab::Projects,
             _ => Tab::Summary,
         }
     }
 
     fn next(&self) -> Self {
-        Tab::from_index((self.index() + 1) % 3)
+        Tab::from_index((self.index() + 1) % TAB_COUNT)
     }
 
     fn prev(&self) -> Self {
-        Tab::from_index((self.index() + 2) % 3)
+        Tab::from_index((self.index() + TAB_COUNT - 1) % TAB_COUNT)
     }
 }
 
+/// What the footer prompt's input is for
+#[derive(Clone, Copy, PartialEq)]
+enum PromptKind {
+    /// Label for a new work session
+    Label,
+    /// Note for an interruption of the running work session
+    Interruption(InterruptionKind),
+}
+
+impl PromptKind {
+    fn title(&self) -> &'static str {
+        match self {
+            PromptKind::Label => "Start pomodoro, label: ",
+            PromptKind::Interruption(InterruptionKind::Internal) => "Internal interruption, note: ",
+            PromptKind::Interruption(InterruptionKind::External) => "External interruption, note: ",
+        }
+    }
+}
+
+/// Single-line text input shown in the footer
+struct Prompt {
+    kind: PromptKind,
+    input: String,
+}
+
 /// App state for the TUI
 struct AppState {
     current_tab: Tab,
     scroll_offset: usize,
+    /// Day being viewed (the week tab shows the week containing it)
+    date: NaiveDate,
+    prompt: Option<Prompt>,
+    /// Result of the last pomodoro action, shown until the next key press
+    message: Option<String>,
 }
 
 impl Default for AppState {
@@ -68,12 +111,84 @@ impl Default for AppState {
         Self {
             current_tab: Tab::Summary,
             scroll_offset: 0,
+            date: Local::now().date_naive(),
+            prompt: None,
+            message: None,
         }
     }
 }
 
+impl AppState {
+    fn is_today(&self) -> bool {
+        self.date == Local::now().date_naive()
+    }
+
+    /// Move the viewed day, never past today
+    fn shift_date(&mut self, days: i64) {
+        let today = Local::now().date_naive();
+        self.date = (self.date + Duration::days(days)).min(today);
+        self.scroll_offset = 0;
+    }
+
+    fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt { kind, input: String::new() });
+    }
+
+    fn go_to_today(&mut self) {
+        self.date = Local::now().date_naive();
+        self.scroll_offset = 0;
+    }
+
+    /// Range the current tab reports on
+    fn range(&self) -> TimeRange {
+        match self.current_tab {
+            Tab::Week => TimeRange::week_of(self.date),
+            _ => TimeRange::day(self.date),
+        }
+    }
+}
+
+/// Data shown by the dashboard, loaded for the selected range
+#[derive(Default)]
+struct DashboardData {
+    summaries: Vec<AppSummary>,
+    total_secs: i64,
+    hourly: Vec<HourlyActivity>,
+    detailed: Vec<(String, String, String, i64)>,
+    days: Vec<PeriodSummary>,
+    categories: Vec<CategorySummary>,
+    projects: Vec<ProjectSummary>,
+    /// The daemon's in-flight pomodoro, as last saved
+    pomodoro: Option<PomodoroSession>,
+}
+
+impl DashboardData {
+    fn load(storage: &Storage, state: &AppState) -> Self {
+        let range = state.range();
+        let mut data = Self {
+            summaries: storage.get_range_summary(&range).unwrap_or_default(),
+            total_secs: storage.get_range_total_secs(&range).unwrap_or(0),
+            pomodoro: storage.get_open_pomodoro().unwrap_or_default(),
+            ..Default::default()
+        };
+
+        match state.current_tab {
+            Tab::Summary => {}
+            Tab::Detailed => data.detailed = storage.get_range_detailed(&range).unwrap_or_default(),
+            Tab::Timeline => data.hourly = storage.get_range_hourly(&range).unwrap_or_default(),
+            Tab::Week => {
+                data.days = storage.get_range_grouped(&range, GroupBy::Day).unwrap_or_default();
+                data.categories = storage.get_range_categories(&range).unwrap_or_default();
+            }
+            Tab::Projects => data.projects = storage.get_range_projects(&range).unwrap_or_default(),
+        }
+
+        data
+    }
+}
+
 /// Run the TUI application
-pub fn run_tui(storage: &Storage) -> Result<()> {
+pub fn run_tui(storage: &Storage, client: &DaemonClient) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -82,7 +197,7 @@ pub fn run_tui(storage: &Storage) -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Run app
-    let result = run_app(&mut terminal, storage);
+    let result = run_app(&mut terminal, storage, client);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -99,29 +214,52 @@ pub fn run_tui(storage: &Storage) -> Result<()> {
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     storage: &Storage,
+    client: &DaemonClient,
 ) -> Result<()> {
     let mut state = AppState::default();
 
     loop {
-        // Get data
-        let summaries = storage.get_today_summary().unwrap_or_default();
-        let total_secs = storage.get_today_total_secs().unwrap_or(0);
-        let hourly = storage.get_today_hourly().unwrap_or_default();
-        let detailed = storage.get_today_detailed().unwrap_or_default();
+        // Get data for the selected day or week
+        let data = DashboardData::load(storage, &state);
 
         terminal.draw(|f| {
-            ui(f, &state, &summaries, total_secs, &hourly, &detailed);
+            ui(f, &state, &data);
         })?;
 
         // Handle input
         if event::poll(std::time::Duration::from_millis(250))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
+                    if let Some(prompt) = state.prompt.as_mut() {
+                        match key.code {
+                            KeyCode::Enter => {
+                                let prompt = state.prompt.take().unwrap();
+                                state.message = Some(submit_prompt(client, prompt));
+                            }
+                            KeyCode::Esc => state.prompt = None,
+                            KeyCode::Backspace => {
+                                prompt.input.pop();
+                            }
+                            KeyCode::Char(c) => prompt.input.push(c),
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    state.message = None;
+
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('p') => state.open_prompt(PromptKind::Label),
+                        KeyCode::Char('i') => state.open_prompt(PromptKind::Interruption(InterruptionKind::Internal)),
+                        KeyCode::Char('x') => state.open_prompt(PromptKind::Interruption(InterruptionKind::External)),
                         KeyCode::Char('1') => state.current_tab = Tab::Summary,
                         KeyCode::Char('2') => state.current_tab = Tab::Detailed,
                         KeyCode::Char('3') => state.current_tab = Tab::Timeline,
+                        KeyCode::Char('4') => state.current_tab = Tab::Week,
+                        KeyCode::Char('5') => state.current_tab = Tab::Projects,
+                        KeyCode::Char('[') => state.shift_date(-1),
+                        KeyCode::Char(']') => state.shift_date(1),
+                        KeyCode::Char('t') => state.go_to_today(),
                         KeyCode::Tab | KeyCode::Right => {
                             state.current_tab = state.current_tab.next();
                             state.scroll_offset = 0;
@@ -144,53 +282,78 @@ fn run_app<B: ratatui::backend::Backend>(
     }
 }
 
-fn ui(
-    f: &mut Frame,
-    state: &AppState,
-    summaries: &[AppSummary],
-    total_secs: i64,
-    hourly: &[HourlyActivity],
-    detailed: &[(String, String, String, i64)],
-) {
+/// Send a finished prompt to the daemon, returning the line to show
+fn submit_prompt(client: &DaemonClient, prompt: Prompt) -> String {
+    let input = prompt.input.trim();
+    let result = match prompt.kind {
+        PromptKind::Label => client
+            .post::<serde_json::Value>("/api/pomodoro/start", &serde_json::json!({ "label": input }))
+            .and_then(|reply| match reply["status"].as_str() {
+                Some("disabled") => Err(anyhow::anyhow!("Pomodoro is off in the current mode")),
+                _ => Ok("🍅 Work session started".to_string()),
+            }),
+        PromptKind::Interruption(kind) => client
+            .post::<serde_json::Value>(
+                "/api/pomodoro/interruption",
+                &serde_json::json!({ "kind": kind.as_str(), "note": input }),
+            )
+            .map(|_| format!("Logged {} interruption", kind.as_str())),
+    };
+    result.unwrap_or_else(|e| format!("⚠ {}", e))
+}
+
+fn ui(f: &mut Frame, state: &AppState, data: &DashboardData) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
             Constraint::Length(3),  // Header with tabs
-            Constraint::Length(3),  // Today's progress
+            Constraint::Length(3),  // Day/week progress
             Constraint::Min(10),    // Main content
             Constraint::Length(2),  // Footer
         ])
         .split(f.area());
 
     // Header with date/time and tabs
-    render_header(f, chunks[0], state.current_tab);
+    render_header(f, chunks[0], state);
 
-    // Progress gauge
-    render_progress(f, chunks[1], total_secs);
+    // Progress gauge (8h per day, 40h per week)
+    let target_hours = if state.current_tab == Tab::Week { 40.0 } else { 8.0 };
+    render_progress(f, chunks[1], data.total_secs, target_hours);
 
     // Tab content
     match state.current_tab {
-        Tab::Summary => render_summary_tab(f, chunks[2], summaries, total_secs),
-        Tab::Detailed => render_detailed_tab(f, chunks[2], detailed, state.scroll_offset),
-        Tab::Timeline => render_timeline_tab(f, chunks[2], hourly, summaries),
+        Tab::Summary => render_summary_tab(f, chunks[2], &data.summaries, data.total_secs),
+        Tab::Detailed => render_detailed_tab(f, chunks[2], &data.detailed, state.scroll_offset),
+        Tab::Timeline => render_timeline_tab(f, chunks[2], &data.hourly, &data.summaries, state.is_today()),
+        Tab::Week => render_week_tab(f, chunks[2], &state.range(), &data.days, &data.categories),
+        Tab::Projects => render_projects_tab(f, chunks[2], &data.projects),
     }
 
     // Footer
-    render_footer(f, chunks[3], state.current_tab);
+    render_footer(f, chunks[3], state, data.pomodoro.as_ref());
 }
 
-fn render_header(f: &mut Frame, area: Rect, current_tab: Tab) {
+fn render_header(f: &mut Frame, area: Rect, state: &AppState) {
     let now = Local::now();
-    let date_str = now.format("%a, %b %d").to_string();
-    let time_str = now.format("%H:%M:%S").to_string();
+    let date_str = if state.current_tab == Tab::Week {
+        let range = state.range();
+        format!("{} – {}", range.first_day().format("%b %d"), range.last_day().format("%b %d"))
+    } else {
+        state.date.format("%a, %b %d").to_string()
+    };
+    let time_str = if state.is_today() {
+        now.format("%H:%M:%S").to_string()
+    } else {
+        "history".to_string()
+    };
 
     let header_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
             Constraint::Length(25),  // Logo
             Constraint::Min(30),     // Tabs
-            Constraint::Length(20),  // Date/time
+            Constraint::Length(26),  // Date/time
         ])
         .split(area);
 
@@ -207,25 +370,25 @@ fn render_header(f: &mut Frame, area: Rect, current_tab: Tab) {
         .map(|t| Line::from(*t))
         .collect();
     let tabs = Tabs::new(titles)
-        .select(current_tab.index())
+        .select(state.current_tab.index())
         .style(Style::default().fg(Color::DarkGray))
         .highlight_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
         .divider(" │ ");
     f.render_widget(tabs, header_layout[1]);
 
     // Date/time
+    let time_color = if state.is_today() { Color::Yellow } else { Color::Magenta };
     let datetime = Paragraph::new(Line::from(vec![
         Span::styled(&date_str, Style::default().fg(Color::DarkGray)),
         Span::raw(" "),
-        Span::styled(&time_str, Style::default().fg(Color::Yellow)),
+        Span::styled(&time_str, Style::default().fg(time_color)),
     ]))
     .alignment(Alignment::Right);
     f.render_widget(datetime, header_layout[2]);
 }
 
-fn render_progress(f: &mut Frame, area: Rect, total_secs: i64) {
+fn render_progress(f: &mut Frame, area: Rect, total_secs: i64, target_hours: f64) {
     let hours_worked = total_secs as f64 / 3600.0;
-    let target_hours = 8.0;
     let percent = ((hours_worked / target_hours) * 100.0).min(100.0) as u16;
 
     let color = if percent >= 100 {
@@ -297,6 +460,44 @@ fn render_summary_tab(f: &mut Frame, area: Rect, summaries: &[AppSummary], _tota
     f.render_widget(list, area);
 }
 
+fn render_projects_tab(f: &mut Frame, area: Rect, projects: &[ProjectSummary]) {
+    if projects.is_empty() {
+        let empty = Paragraph::new("No activity recorded yet. Add [[projects]] to the config to group it.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(Block::default().title("Projects").borders(Borders::ALL))
+            .alignment(Alignment::Center);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let total = projects.iter().map(|p| p.total_secs).sum::<i64>().max(1);
+    let max_secs = projects.iter().map(|p| p.total_secs).max().unwrap_or(1).max(1);
+
+    let items: Vec<ListItem> = projects
+        .iter()
+        .map(|p| {
+            let pct = (p.total_secs as f64 / total as f64 * 100.0) as u32;
+            let bar_width = ((p.total_secs as f64 / max_secs as f64) * 30.0) as usize;
+            // Unassigned time is what still needs sorting out, so keep it muted
+            let color = if p.id.is_some() { Color::Cyan } else { Color::DarkGray };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<20}", p.name), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{:>8}", format_duration(p.total_secs)), Style::default().fg(Color::White)),
+                Span::styled(format!(" {:>3}% ", pct), Style::default().fg(Color::DarkGray)),
+                Span::styled("█".repeat(bar_width), Style::default().fg(color)),
+                Span::styled(format!(" {} sessions", p.sessions), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default()
+            .title(format!(" Projects ({}) ", projects.iter().filter(|p| p.id.is_some()).count()))
+            .borders(Borders::ALL));
+    f.render_widget(list, area);
+}
+
 fn render_detailed_tab(
     f: &mut Frame,
     area: Rect,
@@ -367,6 +568,7 @@ fn render_timeline_tab(
     area: Rect,
     hourly: &[HourlyActivity],
     summaries: &[AppSummary],
+    is_today: bool,
 ) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -423,11 +625,13 @@ fn render_timeline_tab(
         .style(Style::default().fg(Color::DarkGray));
     f.render_widget(markers, sparkline_layout[1]);
 
-    // Current hour indicator
-    let indicator = format!("{}▲ Now ({}:00)", " ".repeat(current_hour * 3), current_hour);
-    let indicator_para = Paragraph::new(indicator)
-        .style(Style::default().fg(Color::Cyan));
-    f.render_widget(indicator_para, sparkline_layout[2]);
+    // Current hour indicator (only meaningful when viewing today)
+    if is_today {
+        let indicator = format!("{}▲ Now ({}:00)", " ".repeat(current_hour * 3), current_hour);
+        let indicator_para = Paragraph::new(indicator)
+            .style(Style::default().fg(Color::Cyan));
+        f.render_widget(indicator_para, sparkline_layout[2]);
+    }
 
     // Category breakdown
     let mut categories: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
@@ -436,7 +640,7 @@ fn render_timeline_tab(
     }
 
     let mut cat_list: Vec<_> = categories.into_iter().collect();
-    cat_list.sort_by(|a, b| b.1.cmp(&a.1));
+    cat_list.sort_by_key(|c| std::cmp::Reverse(c.1));
 
     let cat_items: Vec<ListItem> = cat_list
         .iter()
@@ -463,24 +667,144 @@ fn render_timeline_tab(
     f.render_widget(cat_list_widget, chunks[1]);
 }
 
-fn render_footer(f: &mut Frame, area: Rect, current_tab: Tab) {
-    let nav_hint = match current_tab {
+fn render_week_tab(
+    f: &mut Frame,
+    area: Rect,
+    range: &TimeRange,
+    days: &[PeriodSummary],
+    categories: &[CategorySummary],
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),    // Per-day chart
+            Constraint::Length(10), // Category split
+        ])
+        .split(area);
+
+    // One bar per day of the week, in minutes, labelled with the weekday
+    let today = Local::now().date_naive();
+    let bars: Vec<Bar> = range
+        .dates()
+        .iter()
+        .map(|date| {
+            let secs = days
+                .iter()
+                .find(|d| d.start == *date)
+                .map(|d| d.total_secs)
+                .unwrap_or(0);
+            let color = if *date == today { Color::Cyan } else { Color::Yellow };
+            Bar::default()
+                .value((secs / 60) as u64)
+                .label(Line::from(date.format("%a").to_string()))
+                .text_value(format_duration(secs))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+
+    let bar_width = (chunks[0].width.saturating_sub(2) / 7).saturating_sub(1).clamp(3, 12);
+    let chart = BarChart::default()
+        .block(Block::default()
+            .title(" Daily Activity ")
+            .borders(Borders::ALL))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .value_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+    f.render_widget(chart, chunks[0]);
+
+    // Category split across the week
+    let total = categories.iter().map(|c| c.total_secs).sum::<i64>().max(1);
+    let bar_space = chunks[1].width.saturating_sub(35) as f64;
+
+    let items: Vec<ListItem> = categories
+        .iter()
+        .map(|c| {
+            let color = category_color(&c.category);
+            let pct = c.total_secs as f64 / total as f64;
+            ListItem::new(Line::from(vec![
+                Span::styled("● ", Style::default().fg(color)),
+                Span::styled(
+                    format!("{:<15}", c.category),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD)
+                ),
+                Span::styled(
+                    format!("{:>8}", format_duration(c.total_secs)),
+                    Style::default().fg(Color::White)
+                ),
+                Span::styled(
+                    format!(" {:>3}% ", (pct * 100.0) as u32),
+                    Style::default().fg(Color::DarkGray)
+                ),
+                Span::styled("█".repeat((pct * bar_space) as usize), Style::default().fg(color)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default()
+            .title(" Categories this week ")
+            .borders(Borders::ALL));
+    f.render_widget(list, chunks[1]);
+}
+
+fn render_footer(f: &mut Frame, area: Rect, state: &AppState, pomodoro: Option<&PomodoroSession>) {
+    let status = if let Some(prompt) = &state.prompt {
+        Line::from(vec![
+            Span::styled(prompt.kind.title(), Style::default().fg(Color::Yellow)),
+            Span::raw(prompt.input.as_str()),
+            Span::styled("█", Style::default().fg(Color::DarkGray)),
+            Span::styled("  Enter confirm  Esc cancel", Style::default().fg(Color::DarkGray)),
+        ])
+    } else if let Some(message) = &state.message {
+        Line::from(Span::styled(message.as_str(), Style::default().fg(Color::Yellow)))
+    } else if let Some(session) = pomodoro {
+        let remaining = session.remaining_at(Local::now()).max(0);
+        let color = if session.kind == PomodoroKind::Work { Color::Red } else { Color::Green };
+        let mut spans = vec![
+            Span::styled("🍅 ", Style::default().fg(color)),
+            Span::styled(session.kind.label(), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!(" {:02}:{:02}", remaining / 60, remaining % 60),
+                Style::default().fg(Color::White),
+            ),
+        ];
+        if session.paused {
+            spans.push(Span::styled(" (paused)", Style::default().fg(Color::DarkGray)));
+        }
+        if let Some(label) = &session.label {
+            spans.push(Span::styled(format!(" · {}", label), Style::default().fg(Color::Gray)));
+        }
+        Line::from(spans)
+    } else {
+        Line::from(Span::styled("No pomodoro running", Style::default().fg(Color::DarkGray)))
+    };
+
+    let nav_hint = match state.current_tab {
         Tab::Summary => "↑↓ scroll",
         Tab::Detailed => "↑↓/jk scroll",
-        Tab::Timeline => "view only",
+        Tab::Timeline | Tab::Week | Tab::Projects => "view only",
     };
 
-    let footer = Paragraph::new(Line::from(vec![
-        Span::styled("1/2/3", Style::default().fg(Color::Cyan)),
+    let hints = Line::from(vec![
+        Span::styled("1-5", Style::default().fg(Color::Cyan)),
         Span::styled(" tabs  ", Style::default().fg(Color::DarkGray)),
         Span::styled("←→/Tab", Style::default().fg(Color::Cyan)),
         Span::styled(" switch  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("[ ]", Style::default().fg(Color::Cyan)),
+        Span::styled(" prev/next day  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("t", Style::default().fg(Color::Cyan)),
+        Span::styled(" today  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("p", Style::default().fg(Color::Cyan)),
+        Span::styled(" pomodoro  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("i/x", Style::default().fg(Color::Cyan)),
+        Span::styled(" interruption  ", Style::default().fg(Color::DarkGray)),
         Span::styled(nav_hint, Style::default().fg(Color::DarkGray)),
         Span::styled("  ", Style::default()),
         Span::styled("q", Style::default().fg(Color::Cyan)),
         Span::styled(" quit", Style::default().fg(Color::DarkGray)),
-    ]));
-    f.render_widget(footer, area);
+    ]);
+    f.render_widget(Paragraph::new(vec![status, hints]), area);
 }
 
 fn category_color(category: &str) -> Color {
@@ -497,37 +821,81 @@ fn category_color(category: &str) -> Color {
 }
 
 /// Print simple CLI stats (no TUI)
-pub fn print_stats(storage: &Storage) -> Result<()> {
-    let summaries = storage.get_today_summary()?;
-    let total_secs = storage.get_today_total_secs()?;
+pub fn print_stats(storage: &Storage, range: &TimeRange, group_by: Option<GroupBy>, by: Breakdown) -> Result<()> {
+    // (name, seconds) rows of the chosen breakdown, largest first
+    let summaries: Vec<(String, i64)> = match by {
+        Breakdown::App => storage
+            .get_range_summary(range)?
+            .into_iter()
+            .map(|s| (s.app_name, s.total_secs))
+            .collect(),
+        Breakdown::Category => storage
+            .get_range_categories(range)?
+            .into_iter()
+            .map(|c| (c.category, c.total_secs))
+            .collect(),
+        Breakdown::Project => storage
+            .get_range_projects(range)?
+            .into_iter()
+            .map(|p| (p.name, p.total_secs))
+            .collect(),
+    };
+    let total_secs = storage.get_range_total_secs(range)?;
+
+    let heading = if *range == TimeRange::today() {
+        "Today's Activity".to_string()
+    } else if range.is_single_day() {
+        format!("Activity on {}", range.first_day().format("%a, %b %d %Y"))
+    } else {
+        format!("Activity {} → {}", range.first_day(), range.last_day())
+    };
 
     println!();
-    println!("  FlowMode - Today's Activity");
+    println!("  FlowMode - {}", heading);
     println!("  ════════════════════════════════════════");
     println!();
     println!("  Total tracked: {}", format_duration(total_secs));
     println!();
 
     if summaries.is_empty() {
-        println!("  No activity recorded today.");
+        println!("  No activity recorded in this period.");
     } else {
-        let max_secs = summaries.iter().map(|s| s.total_secs).max().unwrap_or(1);
+        let max_secs = summaries.iter().map(|(_, secs)| *secs).max().unwrap_or(1).max(1);
 
-        for summary in &summaries {
-            let bar_len = ((summary.total_secs as f64 / max_secs as f64) * 20.0) as usize;
+        for (name, secs) in &summaries {
+            let bar_len = ((*secs as f64 / max_secs as f64) * 20.0) as usize;
             let bar: String = "█".repeat(bar_len);
-            let pct = (summary.total_secs as f64 / total_secs as f64 * 100.0) as u32;
+            let pct = (*secs as f64 / total_secs.max(1) as f64 * 100.0) as u32;
 
             println!(
                 "  {:<15} {:>8} {:>3}% {}",
-                summary.app_name,
-                format_duration(summary.total_secs),
+                name,
+                format_duration(*secs),
                 pct,
                 bar
             );
         }
     }
 
+    if let Some(group_by) = group_by {
+        let periods = storage.get_range_grouped(range, group_by)?;
+        if !periods.is_empty() {
+            let max_secs = periods.iter().map(|p| p.total_secs).max().unwrap_or(1).max(1);
+
+            println!();
+            println!("  By {} ─────────────────────────────────", group_by.as_str());
+            for period in &periods {
+                let bar_len = ((period.total_secs as f64 / max_secs as f64) * 20.0) as usize;
+                println!(
+                    "  {:<15} {:>8} {}",
+                    period.label,
+                    format_duration(period.total_secs),
+                    "█".repeat(bar_len)
+                );
+            }
+        }
+    }
+
     println!();
     Ok(())
 }
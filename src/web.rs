@@ -1,35 +1,43 @@
 use axum::{
-    extract::State,
-    http::{header, StatusCode, Uri},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode, Uri},
     response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
 use rust_embed::RustEmbed;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::LazyLock;
+use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
-use crate::pomodoro::{PomodoroTimer, SharedPomodoro};
-use crate::storage::Storage;
-use crate::title_parser::parse_title;
+use crate::browser::{BrowserTab, SharedActiveTab};
+use crate::config::Config;
+use crate::entries::{self, Entries, EntryDraft};
+use crate::export::{self, ExportFormat};
+use crate::pomodoro::{self, PomodoroCommand, SharedPomodoro};
+use crate::project::UNASSIGNED;
+use crate::storage::{
+    parse_date, ActivityRecord, AppSummary, ContextFilter, ForgetSelection, GroupBy, SessionEdit, SessionFilter,
+    SessionSelection, Storage, TimeRange, TitleSummary,
+};
+use crate::title_parser::TitleParser;
 use crate::tray::format_duration;
 
-/// Global Pomodoro timer instance
-static POMODORO: LazyLock<SharedPomodoro> = LazyLock::new(|| {
-    std::sync::Arc::new(PomodoroTimer::new())
-});
-
 /// Embedded static files from the web folder
 #[derive(RustEmbed)]
 #[folder = "web/dist"]
 struct Assets;
 
-/// Shared state for the web server - just the db path
+/// Shared state for the web server: the db path, the daemon's pomodoro timer,
+/// the browser tab last reported by the extension and the API token
 #[derive(Clone)]
 pub struct AppState {
     pub db_path: PathBuf,
+    pub pomodoro: SharedPomodoro,
+    pub browser: SharedActiveTab,
+    /// Bearer token required by destructive endpoints
+    pub api_token: Arc<str>,
 }
 
 /// API response for today's summary
@@ -54,6 +62,8 @@ pub struct AppStat {
     pub active_secs: i64,
     pub passive_secs: i64,
     pub active_percent: u32,
+    /// Productivity score from -2 to +2 of the time matched by `[[sites]]`
+    pub score: Option<f64>,
 }
 
 #[derive(Serialize)]
@@ -71,10 +81,121 @@ pub struct DetailedEntry {
     pub window_title: String,
     pub parsed_display: String,
     pub context_type: String,
+    pub context: String,
     pub secs: i64,
     pub formatted: String,
 }
 
+/// Query parameters for /api/contexts
+#[derive(Deserialize)]
+pub struct ContextQuery {
+    #[serde(flatten)]
+    pub range: RangeQuery,
+    /// Only this context type, e.g. "chat" or "folder"
+    #[serde(rename = "type")]
+    pub context_type: Option<String>,
+    pub app: Option<String>,
+}
+
+/// Tracked time for one parsed context
+#[derive(Serialize)]
+pub struct ContextEntry {
+    pub context_type: String,
+    pub context: String,
+    pub secs: i64,
+    pub formatted: String,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub sessions: i64,
+    pub days: i64,
+}
+
+/// Tracked time for one project; `id` is null for unassigned sessions
+#[derive(Serialize)]
+pub struct ProjectEntry {
+    pub id: Option<i64>,
+    pub name: String,
+    pub secs: i64,
+    pub formatted: String,
+    pub percent: u32,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub sessions: i64,
+}
+
+/// Body of POST /api/projects/assign: the sessions to move and where to
+#[derive(Deserialize)]
+pub struct ProjectAssignRequest {
+    /// Project name; "Unassigned" (or empty) clears the project
+    pub project: String,
+    #[serde(default)]
+    pub ids: Vec<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub app: Option<String>,
+    pub context: Option<String>,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub unassigned_only: bool,
+}
+
+/// Body of POST /api/activities: time spent away from the computer. Times are
+/// RFC 3339 or `YYYY-MM-DD HH:MM`; the category defaults to the app's, the title to the note.
+#[derive(Deserialize)]
+pub struct ActivityCreateRequest {
+    pub app_name: String,
+    pub category: Option<String>,
+    pub window_title: Option<String>,
+    pub started_at: String,
+    pub ended_at: String,
+    pub project: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Body of PATCH /api/activities/:id; missing fields stay as they are
+#[derive(Deserialize)]
+pub struct ActivityUpdateRequest {
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub app_name: Option<String>,
+    pub category: Option<String>,
+    pub window_title: Option<String>,
+    /// "Unassigned" or "" clears the project
+    pub project: Option<String>,
+    /// "" removes the note
+    pub note: Option<String>,
+}
+
+/// Body of POST /api/activities/:id/split
+#[derive(Deserialize)]
+pub struct ActivitySplitRequest {
+    pub at: String,
+}
+
+/// Body of POST /api/activities/merge
+#[derive(Deserialize)]
+pub struct ActivityMergeRequest {
+    pub ids: Vec<i64>,
+}
+
+/// Body of POST /api/forget: the sessions to delete (every criterion given must match)
+#[derive(Deserialize)]
+pub struct ForgetRequest {
+    #[serde(default)]
+    pub ids: Vec<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub app: Option<String>,
+    /// Regex on the window title
+    pub title_regex: Option<String>,
+    /// Only list the matching sessions
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Overwrite the deleted data and compact the database file
+    #[serde(default)]
+    pub vacuum: bool,
+}
+
 #[derive(Serialize)]
 pub struct StatusResponse {
     pub version: String,
@@ -86,6 +207,120 @@ pub struct HistoryDay {
     pub date: String,
     pub total_secs: i64,
     pub formatted: String,
+    pub score: Option<f64>,
+}
+
+/// Query parameters for /api/range endpoints
+#[derive(Deserialize)]
+pub struct RangeQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub group_by: Option<String>,
+}
+
+/// Query parameters for /api/export
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(flatten)]
+    pub range: RangeQuery,
+    pub format: Option<String>,
+    pub app: Option<String>,
+    pub category: Option<String>,
+}
+
+/// API response for an arbitrary date range
+#[derive(Serialize)]
+pub struct RangeSummary {
+    pub from: String,
+    pub to: String,
+    pub total_secs: i64,
+    pub total_formatted: String,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub active_percent: u32,
+    /// Tracked time during pomodoro work sessions and breaks
+    pub pomodoro_work_secs: i64,
+    pub pomodoro_break_secs: i64,
+    pub pomodoro_work_percent: u32,
+    pub score: Option<f64>,
+    pub apps: Vec<AppStat>,
+    pub categories: Vec<CategoryStat>,
+    pub hourly: Vec<HourlyStat>,
+    pub group_by: Option<String>,
+    pub groups: Vec<PeriodStat>,
+}
+
+#[derive(Serialize)]
+pub struct CategoryStat {
+    pub category: String,
+    pub secs: i64,
+    pub formatted: String,
+    pub percent: u32,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub score: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct PeriodStat {
+    pub start: String,
+    pub label: String,
+    pub total_secs: i64,
+    pub formatted: String,
+    pub active_secs: i64,
+    pub passive_secs: i64,
+    pub score: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct TimelineEntry {
+    pub id: i64,
+    pub app_name: String,
+    pub category: String,
+    pub window_title: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub secs: i64,
+    pub pomodoro: Option<String>,
+    /// Project name, or "Unassigned"
+    pub project: String,
+    /// Browser tab URL, when the extension reported it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Category and productivity score from `[[sites]]`, for browser sessions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i8>,
+    /// tracked, manual or imported
+    pub source: String,
+    /// Tracker an imported session came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub importer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl From<ActivityRecord> for TimelineEntry {
+    fn from(r: ActivityRecord) -> Self {
+        TimelineEntry {
+            id: r.id,
+            app_name: r.app_name,
+            category: r.category,
+            window_title: r.window_title,
+            started_at: r.started_at.to_rfc3339(),
+            ended_at: r.ended_at.map(|e| e.to_rfc3339()),
+            secs: r.duration_secs,
+            pomodoro: r.pomodoro,
+            project: r.project.unwrap_or_else(|| UNASSIGNED.to_string()),
+            url: r.url,
+            site_category: r.site_category,
+            score: r.score,
+            source: r.source,
+            importer: r.importer,
+            note: r.note,
+        }
+    }
 }
 
 /// Analytics summary with insights
@@ -98,6 +333,8 @@ pub struct AnalyticsSummary {
     pub focus_streak_mins: i64,
     pub total_apps_today: usize,
     pub active_percent: u32,
+    /// Today's productivity score (-2 to +2) over the time matched by `[[sites]]`
+    pub productivity_score: Option<f64>,
 }
 
 /// Daily trend data point
@@ -119,14 +356,99 @@ pub struct BurnoutAssessment {
     pub recommendation: String,
 }
 
-/// Pomodoro timer status
+/// Body of POST /api/pomodoro/start (optional)
+#[derive(Deserialize, Default)]
+pub struct PomodoroStartRequest {
+    /// Task or project the pomodoro is for
+    pub label: Option<String>,
+}
+
+/// Body of POST /api/pomodoro/interruption
+#[derive(Deserialize)]
+pub struct PomodoroInterruptionRequest {
+    /// "internal" or "external"
+    pub kind: String,
+    pub note: Option<String>,
+}
+
+/// Query parameters for /api/pomodoro/summary
+#[derive(Deserialize)]
+pub struct PomodoroSummaryQuery {
+    /// Work session id; the latest one when missing
+    pub id: Option<i64>,
+}
+
+/// API response for /api/pomodoro/summary
+#[derive(Serialize)]
+pub struct PomodoroSummaryResponse {
+    pub id: i64,
+    pub label: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub outcome: Option<String>,
+    pub headline: String,
+    pub apps: Vec<PomodoroAppTime>,
+    pub titles: Vec<PomodoroTitleTime>,
+    pub interruptions: Vec<PomodoroInterruptionEntry>,
+}
+
+#[derive(Serialize)]
+pub struct PomodoroAppTime {
+    pub app_name: String,
+    pub secs: i64,
+    pub formatted: String,
+}
+
+#[derive(Serialize)]
+pub struct PomodoroTitleTime {
+    pub app_name: String,
+    pub window_title: String,
+    pub secs: i64,
+    pub formatted: String,
+}
+
+#[derive(Serialize)]
+pub struct PomodoroInterruptionEntry {
+    pub kind: String,
+    pub note: Option<String>,
+    pub at: String,
+}
+
+/// Body of POST /api/pomodoro/mode
+#[derive(Deserialize)]
+pub struct PomodoroModeRequest {
+    /// Mode name from `[[modes]]`, or null for the plain `[pomodoro]` settings
+    pub mode: Option<String>,
+}
+
+/// Query parameters for /api/pomodoro/history
+#[derive(Deserialize)]
+pub struct PomodoroHistoryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// One day of pomodoro history
 #[derive(Serialize)]
-pub struct PomodoroStatus {
-    pub state: String,           // "idle", "working", "short_break", "long_break", "paused"
-    pub remaining_secs: u64,
-    pub remaining_formatted: String,
-    pub completed_pomodoros: u64,
-    pub enabled: bool,
+pub struct PomodoroDayStat {
+    pub date: String,
+    pub completed: u32,
+    pub skipped: u32,
+    pub interrupted: u32,
+    pub breaks: u32,
+    pub focus_secs: i64,
+    pub focus_formatted: String,
+}
+
+/// API response for /api/pomodoro/history
+#[derive(Serialize)]
+pub struct PomodoroHistory {
+    pub from: String,
+    pub to: String,
+    pub total_completed: u32,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub days: Vec<PomodoroDayStat>,
 }
 
 /// Create the web server router
@@ -143,11 +465,27 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/today/hourly", get(api_today_hourly))
         .route("/api/status", get(api_status))
         .route("/api/history", get(api_history))
+        .route("/api/range", get(api_range))
+        .route("/api/range/detailed", get(api_range_detailed))
+        .route("/api/range/timeline", get(api_range_timeline))
+        .route("/api/contexts", get(api_contexts))
+        .route("/api/projects", get(api_projects))
+        .route("/api/projects/assign", post(api_projects_assign))
+        .route("/api/forget", post(api_forget))
+        .route("/api/activities", get(api_range_timeline).post(api_activity_create))
+        .route("/api/activities/merge", post(api_activity_merge))
+        .route(
+            "/api/activities/:id",
+            get(api_activity).patch(api_activity_update).delete(api_activity_delete),
+        )
+        .route("/api/activities/:id/split", post(api_activity_split))
+        .route("/api/export", get(api_export))
         .route("/api/analytics/summary", get(api_analytics_summary))
         .route("/api/analytics/trends", get(api_analytics_trends))
         .route("/api/analytics/burnout", get(api_analytics_burnout))
         .route("/api/tracking/pause", post(api_pause))
         .route("/api/tracking/resume", post(api_resume))
+        .route("/api/browser/tab", post(api_browser_tab))
         // Pomodoro routes
         .route("/api/pomodoro/status", get(api_pomodoro_status))
         .route("/api/pomodoro/start", post(api_pomodoro_start))
@@ -155,6 +493,10 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/pomodoro/resume", post(api_pomodoro_resume))
         .route("/api/pomodoro/reset", post(api_pomodoro_reset))
         .route("/api/pomodoro/skip", post(api_pomodoro_skip))
+        .route("/api/pomodoro/history", get(api_pomodoro_history))
+        .route("/api/pomodoro/interruption", post(api_pomodoro_interruption))
+        .route("/api/pomodoro/summary", get(api_pomodoro_summary))
+        .route("/api/pomodoro/mode", post(api_pomodoro_mode))
         // Static files (Svelte app)
         .fallback(static_handler)
         .layer(cors)
@@ -216,26 +558,10 @@ async fn api_today(State(state): State<AppState>) -> impl IntoResponse {
     let summaries = storage.get_today_summary().unwrap_or_default();
     let hourly = storage.get_today_hourly_detailed().unwrap_or_default();
 
-    let total = summaries.iter().map(|s| s.total_secs).sum::<i64>().max(1);
     let total_active: i64 = summaries.iter().map(|s| s.active_secs).sum();
     let total_passive: i64 = summaries.iter().map(|s| s.passive_secs).sum();
 
-    let apps: Vec<AppStat> = summaries
-        .iter()
-        .map(|s| {
-            let app_total = s.total_secs.max(1);
-            AppStat {
-                name: s.app_name.clone(),
-                category: s.category.clone(),
-                secs: s.total_secs,
-                formatted: format_duration(s.total_secs),
-                percent: ((s.total_secs as f64 / total as f64) * 100.0) as u32,
-                active_secs: s.active_secs,
-                passive_secs: s.passive_secs,
-                active_percent: ((s.active_secs as f64 / app_total as f64) * 100.0) as u32,
-            }
-        })
-        .collect();
+    let apps = app_stats(&summaries);
 
     let hourly_stats: Vec<HourlyStat> = hourly
         .iter()
@@ -247,11 +573,7 @@ async fn api_today(State(state): State<AppState>) -> impl IntoResponse {
         })
         .collect();
 
-    let overall_active_percent = if total_secs > 0 {
-        ((total_active as f64 / total_secs as f64) * 100.0) as u32
-    } else {
-        0
-    };
+    let overall_active_percent = percent_of(total_active, total_secs);
 
     Json(TodaySummary {
         total_secs,
@@ -264,6 +586,109 @@ async fn api_today(State(state): State<AppState>) -> impl IntoResponse {
     })
 }
 
+/// Build per-app stats with percentages of the combined total
+fn app_stats(summaries: &[AppSummary]) -> Vec<AppStat> {
+    let total = summaries.iter().map(|s| s.total_secs).sum::<i64>().max(1);
+
+    summaries
+        .iter()
+        .map(|s| {
+            let app_total = s.total_secs.max(1);
+            AppStat {
+                name: s.app_name.clone(),
+                category: s.category.clone(),
+                secs: s.total_secs,
+                formatted: format_duration(s.total_secs),
+                percent: ((s.total_secs as f64 / total as f64) * 100.0) as u32,
+                active_secs: s.active_secs,
+                passive_secs: s.passive_secs,
+                active_percent: ((s.active_secs as f64 / app_total as f64) * 100.0) as u32,
+                score: s.score,
+            }
+        })
+        .collect()
+}
+
+/// Integer percentage of `part` in `whole`, 0 when `whole` is empty
+fn percent_of(part: i64, whole: i64) -> u32 {
+    if whole > 0 {
+        ((part as f64 / whole as f64) * 100.0) as u32
+    } else {
+        0
+    }
+}
+
+/// Resolve `from`/`to` query parameters into a range (defaults to today)
+fn parse_range_query(query: &RangeQuery) -> Result<(TimeRange, Option<GroupBy>), String> {
+    let today = chrono::Local::now().date_naive();
+    let from = match &query.from {
+        Some(s) => parse_date(s).map_err(|e| e.to_string())?,
+        None => today,
+    };
+    let to = match &query.to {
+        Some(s) => parse_date(s).map_err(|e| e.to_string())?,
+        None => from.max(today),
+    };
+    if to < from {
+        return Err(format!("'to' ({}) is before 'from' ({})", to, from));
+    }
+    let group_by = query.group_by.as_deref().map(str::parse).transpose()?;
+    Ok((TimeRange::days(from, to), group_by))
+}
+
+/// Optional range of a session selection: none without 'from' and 'to'
+fn parse_selection_range(from: &Option<String>, to: &Option<String>) -> Result<Option<TimeRange>, String> {
+    if from.is_none() && to.is_none() {
+        return Ok(None);
+    }
+    let query = RangeQuery { from: from.clone(), to: to.clone(), group_by: None };
+    parse_range_query(&query).map(|(range, _)| Some(range))
+}
+
+fn bad_request(message: String) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": message}))).into_response()
+}
+
+fn internal_error(e: anyhow::Error) -> axum::response::Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+}
+
+/// Whether the request carries the API token (`Authorization: Bearer <token>`)
+fn authorized(headers: &HeaderMap, state: &AppState) -> bool {
+    let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    // Compare in constant time, so the token can't be guessed byte by byte
+    let expected = state.api_token.as_bytes();
+    token.len() == expected.len() && token.bytes().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn unauthorized() -> axum::response::Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({"error": "missing or wrong API token (see `flowmode web --token`)"})),
+    )
+        .into_response()
+}
+
+/// Whether a tab report comes from the extension or a local program. Web pages
+/// always send their `Origin`, so only extension origins (`chrome-extension://`,
+/// `moz-extension://`), requests without one (`flowmode browser-host`) and
+/// requests with the API token may report tabs.
+fn tab_reporter_allowed(headers: &HeaderMap, state: &AppState) -> bool {
+    match headers.get(header::ORIGIN).map(|v| v.to_str().unwrap_or_default()) {
+        None => true,
+        Some(origin) => {
+            ["chrome-extension://", "moz-extension://"].iter().any(|scheme| origin.starts_with(scheme))
+                || authorized(headers, state)
+        }
+    }
+}
+
 /// GET /api/today/detailed - Detailed window titles
 async fn api_today_detailed(State(state): State<AppState>) -> impl IntoResponse {
     let storage = match Storage::open(&state.db_path) {
@@ -271,25 +696,8 @@ async fn api_today_detailed(State(state): State<AppState>) -> impl IntoResponse
         Err(_) => return Json(Vec::<DetailedEntry>::new()),
     };
 
-    let detailed = storage.get_today_detailed().unwrap_or_default();
-
-    let entries: Vec<DetailedEntry> = detailed
-        .iter()
-        .map(|(app, cat, title, secs)| {
-            let parsed = parse_title(app, cat, title);
-            DetailedEntry {
-                app_name: app.clone(),
-                category: cat.clone(),
-                window_title: title.clone(),
-                parsed_display: parsed.display,
-                context_type: parsed.context_type,
-                secs: *secs,
-                formatted: format_duration(*secs),
-            }
-        })
-        .collect();
-
-    Json(entries)
+    let titles = storage.get_range_titles(&TimeRange::today()).unwrap_or_default();
+    Json(detailed_entries(titles))
 }
 
 /// GET /api/today/hourly - Hourly breakdown
@@ -336,16 +744,631 @@ async fn api_history(State(state): State<AppState>) -> impl IntoResponse {
 
     let days: Vec<HistoryDay> = history
         .iter()
-        .map(|(date, secs)| HistoryDay {
-            date: date.to_string(),
-            total_secs: *secs,
-            formatted: format_duration(*secs),
+        .map(|day| HistoryDay {
+            date: day.start.to_string(),
+            total_secs: day.total_secs,
+            formatted: format_duration(day.total_secs),
+            score: day.score,
         })
         .collect();
 
     Json(days)
 }
 
+/// GET /api/range?from=&to=&group_by= - Summary for any date range
+async fn api_range(
+    State(state): State<AppState>,
+    Query(query): Query<RangeQuery>,
+) -> axum::response::Response {
+    let (range, group_by) = match parse_range_query(&query) {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    let summaries = storage.get_range_summary(&range).unwrap_or_default();
+    let categories = storage.get_range_categories(&range).unwrap_or_default();
+    let hourly = storage.get_range_hourly_detailed(&range).unwrap_or_default();
+    let split = storage.get_range_pomodoro_split(&range).unwrap_or_default();
+    let groups = match group_by {
+        Some(g) => storage.get_range_grouped(&range, g).unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    let total_secs: i64 = summaries.iter().map(|s| s.total_secs).sum();
+    let active_secs: i64 = summaries.iter().map(|s| s.active_secs).sum();
+    let passive_secs: i64 = summaries.iter().map(|s| s.passive_secs).sum();
+
+    Json(RangeSummary {
+        from: range.first_day().to_string(),
+        to: range.last_day().to_string(),
+        total_secs,
+        total_formatted: format_duration(total_secs),
+        active_secs,
+        passive_secs,
+        active_percent: percent_of(active_secs, total_secs),
+        pomodoro_work_secs: split.work_secs,
+        pomodoro_break_secs: split.break_secs,
+        pomodoro_work_percent: percent_of(split.work_secs, total_secs),
+        score: storage.get_range_score(&range).unwrap_or_default(),
+        apps: app_stats(&summaries),
+        categories: categories
+            .iter()
+            .map(|c| CategoryStat {
+                category: c.category.clone(),
+                secs: c.total_secs,
+                formatted: format_duration(c.total_secs),
+                percent: percent_of(c.total_secs, total_secs),
+                active_secs: c.active_secs,
+                passive_secs: c.passive_secs,
+                score: c.score,
+            })
+            .collect(),
+        hourly: hourly
+            .iter()
+            .map(|h| HourlyStat {
+                hour: h.hour,
+                secs: h.active_secs + h.passive_secs,
+                active_secs: h.active_secs,
+                passive_secs: h.passive_secs,
+            })
+            .collect(),
+        group_by: group_by.map(|g| g.as_str().to_string()),
+        groups: groups
+            .iter()
+            .map(|p| PeriodStat {
+                start: p.start.to_string(),
+                label: p.label.clone(),
+                total_secs: p.total_secs,
+                formatted: format_duration(p.total_secs),
+                active_secs: p.active_secs,
+                passive_secs: p.passive_secs,
+                score: p.score,
+            })
+            .collect(),
+    })
+    .into_response()
+}
+
+/// GET /api/range/detailed?from=&to= - Window titles for any date range
+async fn api_range_detailed(
+    State(state): State<AppState>,
+    Query(query): Query<RangeQuery>,
+) -> axum::response::Response {
+    let (range, _) = match parse_range_query(&query) {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    let titles = storage.get_range_titles(&range).unwrap_or_default();
+    Json(detailed_entries(titles)).into_response()
+}
+
+/// Detailed rows from stored parses; titles never parsed (stored before an
+/// upgrade, not yet backfilled) are parsed with the current rules
+fn detailed_entries(titles: Vec<TitleSummary>) -> Vec<DetailedEntry> {
+    let mut parser = None;
+    titles
+        .into_iter()
+        .map(|t| {
+            let parsed = t.parsed.unwrap_or_else(|| {
+                parser
+                    .get_or_insert_with(|| TitleParser::from_config(&Config::load().unwrap_or_default()))
+                    .parse(&t.app_name, &t.category, &t.window_title)
+            });
+            DetailedEntry {
+                app_name: t.app_name,
+                category: t.category,
+                window_title: t.window_title,
+                parsed_display: parsed.display,
+                context_type: parsed.context_type,
+                context: parsed.context,
+                secs: t.total_secs,
+                formatted: format_duration(t.total_secs),
+            }
+        })
+        .collect()
+}
+
+/// GET /api/contexts?from=&to=&type=&app= - Tracked time per parsed context
+async fn api_contexts(
+    State(state): State<AppState>,
+    Query(query): Query<ContextQuery>,
+) -> axum::response::Response {
+    let (range, _) = match parse_range_query(&query.range) {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    let filter = ContextFilter { context_type: query.context_type, app: query.app };
+    let contexts = match storage.get_range_contexts(&range, &filter) {
+        Ok(c) => c,
+        Err(e) => return internal_error(e),
+    };
+
+    Json(
+        contexts
+            .into_iter()
+            .map(|c| ContextEntry {
+                context_type: c.context_type,
+                context: c.context,
+                secs: c.total_secs,
+                formatted: format_duration(c.total_secs),
+                active_secs: c.active_secs,
+                passive_secs: c.passive_secs,
+                sessions: c.sessions,
+                days: c.days,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response()
+}
+
+/// GET /api/projects?from=&to= - Tracked time per project, unassigned included
+async fn api_projects(
+    State(state): State<AppState>,
+    Query(query): Query<RangeQuery>,
+) -> axum::response::Response {
+    let (range, _) = match parse_range_query(&query) {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    let projects = match storage.get_range_projects(&range) {
+        Ok(p) => p,
+        Err(e) => return internal_error(e),
+    };
+    let total = projects.iter().map(|p| p.total_secs).sum::<i64>().max(1);
+
+    Json(
+        projects
+            .into_iter()
+            .map(|p| ProjectEntry {
+                id: p.id,
+                percent: (p.total_secs as f64 / total as f64 * 100.0) as u32,
+                name: p.name,
+                secs: p.total_secs,
+                formatted: format_duration(p.total_secs),
+                active_secs: p.active_secs,
+                passive_secs: p.passive_secs,
+                sessions: p.sessions,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response()
+}
+
+/// POST /api/projects/assign - Move sessions to a project by hand
+async fn api_projects_assign(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ProjectAssignRequest>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let range = match parse_selection_range(&request.from, &request.to) {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    if request.ids.is_empty() && range.is_none() {
+        return bad_request("pick sessions with 'ids' or a 'from'/'to' range".to_string());
+    }
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    let name = request.project.trim();
+    let project = Some(name).filter(|n| !n.is_empty() && !n.eq_ignore_ascii_case(UNASSIGNED));
+    let selection = SessionSelection {
+        ids: request.ids,
+        range,
+        app: request.app,
+        context: request.context,
+        title: request.title,
+        unassigned_only: request.unassigned_only,
+    };
+    match storage.assign_project(&selection, project) {
+        Ok(sessions) => Json(serde_json::json!({
+            "status": "assigned",
+            "project": project.unwrap_or(UNASSIGNED),
+            "sessions": sessions,
+        }))
+        .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// GET /api/range/timeline?from=&to= - Individual sessions for any date range
+/// (also GET /api/activities; needs the API token, as titles and URLs are in it)
+async fn api_range_timeline(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<RangeQuery>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let (range, _) = match parse_range_query(&query) {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    let entries: Vec<TimelineEntry> = storage
+        .get_range_timeline(&range)
+        .unwrap_or_default()
+        .into_iter()
+        .map(TimelineEntry::from)
+        .collect();
+
+    Json(entries).into_response()
+}
+
+/// Parse a time field of an activity request
+fn parse_time_field(field: &str, value: &str) -> Result<chrono::DateTime<chrono::Local>, String> {
+    entries::parse_time(value).map_err(|e| format!("{}: {}", field, e))
+}
+
+/// A stored session with the ids of the others it overlaps
+fn activity_response(storage: &Storage, record: ActivityRecord) -> axum::response::Response {
+    let overlaps: Vec<i64> = match storage.get_overlapping_sessions(record.started_at, record.end_time(), Some(record.id)) {
+        Ok(records) => records.iter().map(|r| r.id).collect(),
+        Err(e) => return internal_error(e),
+    };
+    Json(serde_json::json!({ "activity": TimelineEntry::from(record), "overlaps": overlaps })).into_response()
+}
+
+fn not_found(id: i64) -> axum::response::Response {
+    (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": format!("no session with id {}", id)}))).into_response()
+}
+
+/// GET /api/activities/:id - One session (needs the API token)
+async fn api_activity(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+    match storage.get_session(id) {
+        Ok(Some(record)) => Json(TimelineEntry::from(record)).into_response(),
+        Ok(None) => not_found(id),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// POST /api/activities - Add a manual entry (needs the API token)
+async fn api_activity_create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ActivityCreateRequest>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let (started_at, ended_at) = match (
+        parse_time_field("started_at", &request.started_at),
+        parse_time_field("ended_at", &request.ended_at),
+    ) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(e), _) | (_, Err(e)) => return bad_request(e),
+    };
+    if request.app_name.trim().is_empty() {
+        return bad_request("'app_name' is empty".to_string());
+    }
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+    let session = Entries::from_config(&Config::load().unwrap_or_default()).manual_session(EntryDraft {
+        app_name: request.app_name,
+        category: request.category,
+        window_title: request.window_title,
+        started_at,
+        ended_at,
+        project: request.project,
+        note: request.note,
+    });
+    match storage.add_manual_session(&session).and_then(|id| storage.get_session(id)) {
+        Ok(Some(record)) => (StatusCode::CREATED, activity_response(&storage, record)).into_response(),
+        Ok(None) => internal_error(anyhow::anyhow!("the new session vanished")),
+        Err(e) => bad_request(e.to_string()),
+    }
+}
+
+/// PATCH /api/activities/:id - Change a session's times, app, category, title,
+/// project or note (needs the API token)
+async fn api_activity_update(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    Json(request): Json<ActivityUpdateRequest>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let parse = |field, value: Option<String>| value.map(|v| parse_time_field(field, &v)).transpose();
+    let (started_at, ended_at) = match (parse("started_at", request.started_at), parse("ended_at", request.ended_at)) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(e), _) | (_, Err(e)) => return bad_request(e),
+    };
+    let mut edit = SessionEdit {
+        started_at,
+        ended_at,
+        app_name: request.app_name.filter(|a| !a.trim().is_empty()),
+        category: request.category.filter(|c| !c.trim().is_empty()),
+        window_title: request.window_title,
+        parsed: None,
+        project: request.project.as_deref().map(entries::project_name),
+        note: request.note.map(|n| Some(n).filter(|n| !n.trim().is_empty())),
+    };
+    if edit.is_empty() {
+        return bad_request("nothing to change".to_string());
+    }
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+    let record = match storage.get_session(id) {
+        Ok(Some(record)) => record,
+        Ok(None) => return not_found(id),
+        Err(e) => return internal_error(e),
+    };
+    Entries::from_config(&Config::load().unwrap_or_default()).complete_edit(&record, &mut edit);
+    match storage.edit_session(id, &edit) {
+        Ok(record) => activity_response(&storage, record),
+        Err(e) => bad_request(e.to_string()),
+    }
+}
+
+/// DELETE /api/activities/:id - Delete a session (needs the API token)
+async fn api_activity_delete(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+    match storage.forget(&ForgetSelection { ids: vec![id], ..Default::default() }, false) {
+        Ok(stats) if stats.sessions == 0 => not_found(id),
+        Ok(_) => Json(serde_json::json!({"status": "deleted", "id": id})).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// POST /api/activities/:id/split - Split a session in two (needs the API token)
+async fn api_activity_split(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    Json(request): Json<ActivitySplitRequest>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let at = match parse_time_field("at", &request.at) {
+        Ok(at) => at,
+        Err(e) => return bad_request(e),
+    };
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+    match storage.get_session(id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(id),
+        Err(e) => return internal_error(e),
+    }
+    let second = match storage.split_session(id, at) {
+        Ok(second) => second,
+        Err(e) => return bad_request(e.to_string()),
+    };
+    let mut parts = Vec::new();
+    for part in [id, second] {
+        match storage.get_session(part) {
+            Ok(Some(record)) => parts.push(TimelineEntry::from(record)),
+            Ok(None) => {}
+            Err(e) => return internal_error(e),
+        }
+    }
+    Json(serde_json::json!({ "activities": parts })).into_response()
+}
+
+/// POST /api/activities/merge - Merge sessions into the earliest one (needs the API token)
+async fn api_activity_merge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ActivityMergeRequest>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+    match storage.merge_sessions(&request.ids) {
+        Ok(record) => activity_response(&storage, record),
+        Err(e) => bad_request(e.to_string()),
+    }
+}
+
+/// Sessions listed by a POST /api/forget dry run
+const FORGET_PREVIEW: usize = 100;
+
+/// POST /api/forget - Delete sessions by id, app, title regex or range (needs the API token)
+async fn api_forget(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ForgetRequest>,
+) -> axum::response::Response {
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let range = match parse_selection_range(&request.from, &request.to) {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    let title_regex = match request.title_regex.as_deref().map(regex::Regex::new).transpose() {
+        Ok(r) => r,
+        Err(e) => return bad_request(format!("invalid title_regex: {}", e)),
+    };
+    let selection = ForgetSelection { ids: request.ids, range, app: request.app, title_regex };
+    if selection.is_empty() {
+        return bad_request("pick sessions with 'ids', 'app', 'title_regex' or a 'from'/'to' range".to_string());
+    }
+
+    let storage = match Storage::open(&state.db_path) {
+        Ok(s) => s,
+        Err(e) => return internal_error(e),
+    };
+
+    if request.dry_run {
+        return match storage.get_forget_sessions(&selection) {
+            Ok(records) => Json(serde_json::json!({
+                "status": "preview",
+                "sessions": records.len(),
+                "secs": records.iter().map(|r| r.duration_secs).sum::<i64>(),
+                "preview": records.into_iter().take(FORGET_PREVIEW).map(TimelineEntry::from).collect::<Vec<_>>(),
+            }))
+            .into_response(),
+            Err(e) => internal_error(e),
+        };
+    }
+    match storage.forget(&selection, request.vacuum) {
+        Ok(stats) => Json(serde_json::json!({
+            "status": "forgotten",
+            "sessions": stats.sessions,
+            "secs": stats.secs,
+            "rollup_rows": stats.rollup_rows,
+        }))
+        .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// GET /api/export?from=&to=&format=&app=&category= - Download sessions as a file
+async fn api_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> axum::response::Response {
+    // Exports carry every title and URL, so they need the token like changes do
+    if !authorized(&headers, &state) {
+        return unauthorized();
+    }
+    let (range, _) = match parse_range_query(&query.range) {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+    let format: ExportFormat = match query.format.as_deref().unwrap_or("csv").parse() {
+        Ok(f) => f,
+        Err(e) => return bad_request(e),
+    };
+    let filter = SessionFilter { app: query.app, category: query.category };
+
+    // Stream from a blocking task so large exports never sit in memory
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    let db_path = state.db_path.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut out = ChannelWriter::new(tx.clone());
+        let result = Storage::open(&db_path)
+            .and_then(|storage| export::export(&storage, &range, &filter, format, &mut out));
+        if let Err(e) = result {
+            tracing::error!("Export failed: {}", e);
+            let _ = tx.blocking_send(Err(std::io::Error::other(e.to_string())));
+        }
+    });
+
+    let filename = format!(
+        "flowmode-{}-{}.{}",
+        range.first_day(),
+        range.last_day(),
+        format.extension()
+    );
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        axum::body::Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+/// `Write` adapter that forwards buffered chunks to a response body channel
+struct ChannelWriter {
+    tx: tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>,
+    buf: Vec<u8>,
+}
+
+impl ChannelWriter {
+    const CHUNK_SIZE: usize = 16 * 1024;
+
+    fn new(tx: tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>) -> Self {
+        Self { tx, buf: Vec::with_capacity(Self::CHUNK_SIZE) }
+    }
+}
+
+impl std::io::Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= Self::CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(Self::CHUNK_SIZE));
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client disconnected"))
+    }
+}
+
 /// GET /api/analytics/summary - Today's insights
 async fn api_analytics_summary(State(state): State<AppState>) -> impl IntoResponse {
     let storage = match Storage::open(&state.db_path) {
@@ -358,6 +1381,7 @@ async fn api_analytics_summary(State(state): State<AppState>) -> impl IntoRespon
             focus_streak_mins: 0,
             total_apps_today: 0,
             active_percent: 0,
+            productivity_score: None,
         }),
     };
 
@@ -407,6 +1431,7 @@ async fn api_analytics_summary(State(state): State<AppState>) -> impl IntoRespon
         focus_streak_mins: max_streak / 60,
         total_apps_today: summaries.len(),
         active_percent: active_pct,
+        productivity_score: storage.get_range_score(&TimeRange::today()).unwrap_or_default(),
     })
 }
 
@@ -423,10 +1448,10 @@ async fn api_analytics_trends(State(state): State<AppState>) -> impl IntoRespons
     // Future: add get_history_with_activity() that returns active/passive
     let trends: Vec<TrendDay> = history
         .iter()
-        .map(|(date, secs)| TrendDay {
-            date: date.to_string(),
-            total_secs: *secs,
-            active_secs: *secs, // Placeholder - assume all active for historical
+        .map(|day| TrendDay {
+            date: day.start.to_string(),
+            total_secs: day.total_secs,
+            active_secs: day.total_secs, // Placeholder - assume all active for historical
             passive_secs: 0,
         })
         .collect();
@@ -447,7 +1472,12 @@ async fn api_analytics_burnout(State(state): State<AppState>) -> impl IntoRespon
         }),
     };
 
-    let history = storage.get_history_days(14).unwrap_or_default();
+    let history: Vec<(chrono::NaiveDate, i64)> = storage
+        .get_history_days(14)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|day| (day.start, day.total_secs))
+        .collect();
 
     // Calculate weekly hours (last 7 days)
     let weekly_secs: i64 = history.iter().take(7).map(|(_, secs)| secs).sum();
@@ -524,64 +1554,216 @@ async fn api_resume() -> impl IntoResponse {
 }
 
 /// GET /api/pomodoro/status - Get current Pomodoro timer state
-async fn api_pomodoro_status() -> impl IntoResponse {
-    let state = POMODORO.get_state().await;
-    Json(PomodoroStatus {
-        state: state.as_str().to_string(),
-        remaining_secs: POMODORO.get_remaining_secs(),
-        remaining_formatted: POMODORO.format_remaining(),
-        completed_pomodoros: POMODORO.get_completed_pomodoros(),
-        enabled: POMODORO.is_enabled(),
-    })
+async fn api_pomodoro_status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.pomodoro.status())
 }
 
-/// POST /api/pomodoro/start - Start a work session
-async fn api_pomodoro_start() -> impl IntoResponse {
-    POMODORO.start_work().await;
+/// POST /api/pomodoro/start - Start a work session, optionally `{"label": "..."}`
+async fn api_pomodoro_start(
+    State(state): State<AppState>,
+    request: Option<Json<PomodoroStartRequest>>,
+) -> impl IntoResponse {
+    if !state.pomodoro.is_enabled() {
+        return Json(serde_json::json!({"status": "disabled", "message": "Pomodoro is off in this mode"}));
+    }
+    let Json(request) = request.unwrap_or_default();
+    run_pomodoro(&state, PomodoroCommand::Start { label: request.label });
     Json(serde_json::json!({"status": "started", "message": "Work session started"}))
 }
 
 /// POST /api/pomodoro/pause - Pause the timer
-async fn api_pomodoro_pause() -> impl IntoResponse {
-    POMODORO.pause().await;
+async fn api_pomodoro_pause(State(state): State<AppState>) -> impl IntoResponse {
+    run_pomodoro(&state, PomodoroCommand::Pause);
     Json(serde_json::json!({"status": "paused", "message": "Timer paused"}))
 }
 
 /// POST /api/pomodoro/resume - Resume the timer
-async fn api_pomodoro_resume() -> impl IntoResponse {
-    POMODORO.resume().await;
+async fn api_pomodoro_resume(State(state): State<AppState>) -> impl IntoResponse {
+    run_pomodoro(&state, PomodoroCommand::Resume);
     Json(serde_json::json!({"status": "resumed", "message": "Timer resumed"}))
 }
 
 /// POST /api/pomodoro/reset - Reset the timer
-async fn api_pomodoro_reset() -> impl IntoResponse {
-    POMODORO.reset().await;
+async fn api_pomodoro_reset(State(state): State<AppState>) -> impl IntoResponse {
+    run_pomodoro(&state, PomodoroCommand::Reset);
     Json(serde_json::json!({"status": "reset", "message": "Timer reset"}))
 }
 
 /// POST /api/pomodoro/skip - Skip current session
-async fn api_pomodoro_skip() -> impl IntoResponse {
-    POMODORO.skip().await;
+async fn api_pomodoro_skip(State(state): State<AppState>) -> impl IntoResponse {
+    run_pomodoro(&state, PomodoroCommand::Skip);
     Json(serde_json::json!({"status": "skipped", "message": "Session skipped"}))
 }
 
-/// Start the web server
-pub async fn start_web_server(db_path: PathBuf, port: u16) -> anyhow::Result<()> {
-    let state = AppState { db_path };
-    let app = create_router(state);
+/// POST /api/pomodoro/interruption - Log an interruption of the running work session
+async fn api_pomodoro_interruption(
+    State(state): State<AppState>,
+    Json(request): Json<PomodoroInterruptionRequest>,
+) -> axum::response::Response {
+    let kind = match request.kind.parse() {
+        Ok(kind) => kind,
+        Err(e) => return bad_request(e),
+    };
+    match pomodoro::log_interruption(&state.db_path, kind, request.note.as_deref()) {
+        Ok(()) => Json(serde_json::json!({"status": "logged", "kind": request.kind})).into_response(),
+        Err(e) => (StatusCode::CONFLICT, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
+}
 
-    // Start Pomodoro timer tick task
-    tokio::spawn(async {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
-        loop {
-            interval.tick().await;
-            let completed = POMODORO.tick().await;
-            if completed {
-                tracing::info!("Pomodoro session completed!");
-                // Could send notification here in the future
-            }
+/// GET /api/pomodoro/summary?id= - Apps, titles and interruptions of a work session
+async fn api_pomodoro_summary(
+    State(state): State<AppState>,
+    Query(query): Query<PomodoroSummaryQuery>,
+) -> axum::response::Response {
+    let summary = match pomodoro::load_summary(&state.db_path, query.id) {
+        Ok(summary) => summary,
+        Err(e) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": e.to_string()}))).into_response()
         }
+    };
+
+    Json(PomodoroSummaryResponse {
+        id: summary.session.id,
+        label: summary.session.label.clone(),
+        started_at: summary.session.started_at.to_rfc3339(),
+        ended_at: summary.session.ended_at.map(|e| e.to_rfc3339()),
+        outcome: summary.session.outcome.map(|o| o.as_str().to_string()),
+        headline: summary.headline(),
+        apps: summary
+            .apps
+            .iter()
+            .map(|(app, secs)| PomodoroAppTime {
+                app_name: app.clone(),
+                secs: *secs,
+                formatted: format_duration(*secs),
+            })
+            .collect(),
+        titles: summary
+            .titles
+            .iter()
+            .map(|(app, title, secs)| PomodoroTitleTime {
+                app_name: app.clone(),
+                window_title: title.clone(),
+                secs: *secs,
+                formatted: format_duration(*secs),
+            })
+            .collect(),
+        interruptions: summary
+            .interruptions
+            .iter()
+            .map(|i| PomodoroInterruptionEntry {
+                kind: i.kind.as_str().to_string(),
+                note: i.note.clone(),
+                at: i.at.to_rfc3339(),
+            })
+            .collect(),
+    })
+    .into_response()
+}
+
+/// POST /api/pomodoro/mode - Apply a mode's pomodoro overrides
+async fn api_pomodoro_mode(
+    State(state): State<AppState>,
+    Json(request): Json<PomodoroModeRequest>,
+) -> axum::response::Response {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => return internal_error(e),
+    };
+    let timer = &state.pomodoro;
+    if let Err(e) = pomodoro::activate_mode(timer, &config, request.mode.as_deref()) {
+        return bad_request(e.to_string());
+    }
+    Json(serde_json::json!({"status": "ok", "mode": timer.mode(), "enabled": timer.is_enabled()}))
+        .into_response()
+}
+
+/// GET /api/pomodoro/history?from=YYYY-MM-DD&to=YYYY-MM-DD - Daily counts and streaks
+/// (last 30 days by default)
+async fn api_pomodoro_history(
+    State(state): State<AppState>,
+    Query(query): Query<PomodoroHistoryQuery>,
+) -> axum::response::Response {
+    let today = chrono::Local::now().date_naive();
+    let range_query = RangeQuery {
+        from: query.from.or_else(|| Some((today - chrono::Duration::days(29)).to_string())),
+        to: query.to,
+        group_by: None,
+    };
+    let range = match parse_range_query(&range_query) {
+        Ok((range, _)) => range,
+        Err(e) => return bad_request(e),
+    };
+
+    let result = Storage::open(&state.db_path).and_then(|storage| {
+        Ok((storage.get_pomodoro_days(&range)?, storage.get_pomodoro_streak_days()?))
     });
+    let (days, streak_days) = match result {
+        Ok(r) => r,
+        Err(e) => return internal_error(e),
+    };
+    let (current_streak, longest_streak) = pomodoro::streaks(&streak_days, today);
+
+    Json(PomodoroHistory {
+        from: range.first_day().to_string(),
+        to: range.last_day().to_string(),
+        total_completed: days.iter().map(|d| d.completed).sum(),
+        current_streak,
+        longest_streak,
+        days: days
+            .into_iter()
+            .map(|d| PomodoroDayStat {
+                date: d.date.to_string(),
+                completed: d.completed,
+                skipped: d.skipped,
+                interrupted: d.interrupted,
+                breaks: d.breaks,
+                focus_secs: d.focus_secs,
+                focus_formatted: format_duration(d.focus_secs),
+            })
+            .collect(),
+    })
+    .into_response()
+}
+
+/// Apply a timer control and record it; failures are logged, not fatal
+fn run_pomodoro(state: &AppState, command: PomodoroCommand) {
+    if let Err(e) = pomodoro::run_command(&state.pomodoro, &state.db_path, command) {
+        tracing::warn!("Failed to save pomodoro session: {}", e);
+    }
+}
+
+/// POST /api/browser/tab - Active tab from the browser extension (directly or via `flowmode browser-host`)
+async fn api_browser_tab(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(tab): Json<BrowserTab>,
+) -> axum::response::Response {
+    if !tab_reporter_allowed(&headers, &state) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "tabs are only reported by the FlowMode extension"})),
+        )
+            .into_response();
+    }
+    if let Err(e) = url::Url::parse(&tab.url) {
+        return bad_request(format!("invalid url '{}': {}", tab.url, e));
+    }
+    state.browser.report(tab);
+    Json(serde_json::json!({"status": "ok"})).into_response()
+}
+
+/// Start the web server. The pomodoro API drives the daemon's shared timer;
+/// tab reports update `browser` for the tracking loop.
+pub async fn start_web_server(
+    db_path: PathBuf,
+    port: u16,
+    pomodoro: SharedPomodoro,
+    browser: SharedActiveTab,
+    api_token: String,
+) -> anyhow::Result<()> {
+    let state = AppState { db_path, pomodoro, browser, api_token: api_token.into() };
+    let app = create_router(state);
 
     let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
     tracing::info!("Web dashboard at http://localhost:{}", port);
@@ -591,3 +1773,70 @@ pub async fn start_web_server(db_path: PathBuf, port: u16) -> anyhow::Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_project_assignment_needs_token() {
+        let dir = std::env::temp_dir().join(format!("flowmode-web-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("activity.db");
+        let _ = std::fs::remove_file(&db_path);
+        let state = AppState {
+            db_path: db_path.clone(),
+            pomodoro: pomodoro::create_shared_pomodoro(),
+            browser: SharedActiveTab::default(),
+            api_token: "secret".into(),
+        };
+        let request = || {
+            let body = serde_json::json!({"project": "flowmode", "ids": [1]});
+            Json(serde_json::from_value::<ProjectAssignRequest>(body).unwrap())
+        };
+
+        // Any web page can reach the API, so a request without the token changes nothing
+        let response = api_projects_assign(State(state.clone()), HeaderMap::new(), request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!db_path.exists());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        let response = api_projects_assign(State(state), headers, request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_needs_token() {
+        let state = AppState {
+            db_path: std::env::temp_dir().join(format!("flowmode-web-export-{}.db", std::process::id())),
+            pomodoro: pomodoro::create_shared_pomodoro(),
+            browser: SharedActiveTab::default(),
+            api_token: "secret".into(),
+        };
+        let query = || Query(serde_json::from_value::<ExportQuery>(serde_json::json!({"from": "2000-01-01"})).unwrap());
+
+        let response = api_export(State(state.clone()), HeaderMap::new(), query()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!state.db_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_session_reads_need_token() {
+        let state = AppState {
+            db_path: std::env::temp_dir().join(format!("flowmode-web-activities-{}.db", std::process::id())),
+            pomodoro: pomodoro::create_shared_pomodoro(),
+            browser: SharedActiveTab::default(),
+            api_token: "secret".into(),
+        };
+        let query = Query(serde_json::from_value::<RangeQuery>(serde_json::json!({"from": "2000-01-01"})).unwrap());
+
+        let response = api_range_timeline(State(state.clone()), HeaderMap::new(), query).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = api_activity(State(state.clone()), HeaderMap::new(), Path(1)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!state.db_path.exists());
+    }
+}
@@ -20,6 +20,23 @@ export async function fetchHistory() {
   return res.json();
 }
 
+export async function fetchRange(from, to, groupBy) {
+  const params = new URLSearchParams({ from, to });
+  if (groupBy) params.set('group_by', groupBy);
+  const res = await fetch(`${API_BASE}/range?${params}`);
+  return res.json();
+}
+
+// Exports carry every title and URL, so they are fetched with the API token
+export async function fetchExport(format, from, to) {
+  const params = new URLSearchParams({ format, from, to });
+  const res = await fetch(`${API_BASE}/export?${params}`, {
+    headers: { Authorization: `Bearer ${apiToken() || ''}` },
+  });
+  if (!res.ok) throw new Error(`${res.status} ${res.statusText}`);
+  return res.blob();
+}
+
 export async function fetchAnalyticsSummary() {
   const res = await fetch(`${API_BASE}/analytics/summary`);
   return res.json();
@@ -50,6 +67,74 @@ export async function resumeTracking() {
   return res.json();
 }
 
+// Changes to stored sessions need the API token, which `flowmode web` and the
+// tray hand over in the URL fragment (#token=...) so it never reaches the server
+const TOKEN_KEY = 'flowmode-api-token';
+
+export function apiToken() {
+  const match = location.hash.match(/token=([0-9a-f]+)/);
+  if (match) {
+    sessionStorage.setItem(TOKEN_KEY, match[1]);
+    window.history.replaceState(null, '', location.pathname + location.search);
+  }
+  return sessionStorage.getItem(TOKEN_KEY);
+}
+
+async function authorizedFetch(path, method, body) {
+  const headers = { Authorization: `Bearer ${apiToken() || ''}` };
+  if (body !== undefined) headers['Content-Type'] = 'application/json';
+  const res = await fetch(`${API_BASE}${path}`, {
+    method,
+    headers,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  const data = await res.json();
+  if (!res.ok) throw new Error(data.error || `${res.status} ${res.statusText}`);
+  return data;
+}
+
+// Delete sessions for good; a dry run only reports what would go
+export async function forgetSessions(selection, dryRun = false) {
+  return authorizedFetch('/forget', 'POST', { ...selection, dry_run: dryRun });
+}
+
+// Sessions: list, manual entries and edits
+export async function fetchActivities(from, to) {
+  const params = new URLSearchParams({ from, to });
+  return authorizedFetch(`/activities?${params}`, 'GET');
+}
+
+export async function createActivity(entry) {
+  return authorizedFetch('/activities', 'POST', entry);
+}
+
+export async function updateActivity(id, changes) {
+  return authorizedFetch(`/activities/${id}`, 'PATCH', changes);
+}
+
+export async function deleteActivity(id) {
+  return authorizedFetch(`/activities/${id}`, 'DELETE');
+}
+
+export async function splitActivity(id, at) {
+  return authorizedFetch(`/activities/${id}/split`, 'POST', { at });
+}
+
+export async function mergeActivities(ids) {
+  return authorizedFetch('/activities/merge', 'POST', { ids });
+}
+
+// Projects: time per project, and moving sessions by hand
+export async function fetchProjects(from, to) {
+  const params = new URLSearchParams({ from, to });
+  const res = await fetch(`${API_BASE}/projects?${params}`);
+  return res.json();
+}
+
+export async function assignProject(project, ids) {
+  return authorizedFetch('/projects/assign', 'POST', { project, ids });
+}
+
 export function getCategoryClass(category) {
   const map = {
     'Development': 'cat-development',
@@ -106,3 +191,20 @@ export async function skipPomodoro() {
   const res = await fetch(`${API_BASE}/pomodoro/skip`, { method: 'POST' });
   return res.json();
 }
+
+export async function fetchPomodoroHistory(from, to) {
+  const params = new URLSearchParams();
+  if (from) params.set('from', from);
+  if (to) params.set('to', to);
+  const res = await fetch(`${API_BASE}/pomodoro/history?${params}`);
+  return res.json();
+}
+
+export async function setPomodoroMode(mode) {
+  const res = await fetch(`${API_BASE}/pomodoro/mode`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ mode }),
+  });
+  return res.json();
+}